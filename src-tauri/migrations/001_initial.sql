@@ -48,5 +48,20 @@ VALUES ('autoExport', 'true', datetime('now'));
 INSERT OR IGNORE INTO settings (key, value, updated_at)
 VALUES ('activeProfile', 'default', datetime('now'));
 
+INSERT OR IGNORE INTO settings (key, value, updated_at)
+VALUES ('tokenizer', 'cl100k_base', datetime('now'));
+
+INSERT OR IGNORE INTO settings (key, value, updated_at)
+VALUES ('toolContextBudget', '20000', datetime('now'));
+
+INSERT OR IGNORE INTO settings (key, value, updated_at)
+VALUES ('toolNamespaceStrategy', 'prefix', datetime('now'));
+
+INSERT OR IGNORE INTO settings (key, value, updated_at)
+VALUES ('toolNamespaceSeparator', '_', datetime('now'));
+
+INSERT OR IGNORE INTO settings (key, value, updated_at)
+VALUES ('toolNameMaxLength', '64', datetime('now'));
+
 INSERT OR IGNORE INTO profiles (id, name, created_at, updated_at)
 VALUES ('default', 'Default', datetime('now'), datetime('now'));
@@ -0,0 +1,14 @@
+-- Clients allowed to reach the app-hosted HTTP gateway.
+-- Bearer tokens themselves are kept in the OS keyring.
+
+CREATE TABLE IF NOT EXISTS gateway_clients (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+INSERT OR IGNORE INTO settings (key, value, updated_at)
+VALUES ('gatewayHttpEnabled', 'false', datetime('now'));
+
+INSERT OR IGNORE INTO settings (key, value, updated_at)
+VALUES ('gatewayHttpPort', '7341', datetime('now'));
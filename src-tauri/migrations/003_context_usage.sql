@@ -0,0 +1,14 @@
+-- Context usage per server, per direction, in hourly buckets.
+-- `bucket` is the UTC start of the hour, e.g. 2025-01-31T14:00:00Z.
+
+CREATE TABLE IF NOT EXISTS context_usage_samples (
+    server_id TEXT NOT NULL,
+    bucket TEXT NOT NULL,
+    direction TEXT NOT NULL,
+    bytes INTEGER NOT NULL DEFAULT 0,
+    tokens INTEGER NOT NULL DEFAULT 0,
+    messages INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (server_id, bucket, direction)
+);
+
+CREATE INDEX IF NOT EXISTS idx_context_usage_samples_bucket ON context_usage_samples(bucket);
@@ -0,0 +1,20 @@
+-- Approval decisions for gateway tool calls (asked, denied by policy, timed out).
+
+CREATE TABLE IF NOT EXISTS approval_decisions (
+    id TEXT PRIMARY KEY,
+    server_id TEXT NOT NULL,
+    server_name TEXT NOT NULL,
+    tool TEXT NOT NULL,
+    arguments TEXT NOT NULL,
+    client TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    requested_at TEXT NOT NULL,
+    decided_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_approval_decisions_decided_at ON approval_decisions(decided_at);
+
+INSERT OR IGNORE INTO settings (key, value, updated_at)
+VALUES ('approvalTimeoutSecs', '120', datetime('now'));
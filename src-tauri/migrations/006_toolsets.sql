@@ -0,0 +1,12 @@
+-- Named groups of tools across servers, exposed by the gateway while active.
+-- `tools` is a JSON array of { serverId, tools: [upstream tool name globs] }.
+
+CREATE TABLE IF NOT EXISTS toolsets (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    tools TEXT NOT NULL DEFAULT '[]',
+    active INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
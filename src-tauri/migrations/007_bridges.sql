@@ -0,0 +1,12 @@
+-- Stdio servers served over HTTP by the app, at most one bridge per server.
+-- `mode` is 'shared' (one managed process) or 'isolated' (one per session).
+
+CREATE TABLE IF NOT EXISTS bridges (
+    server_id TEXT PRIMARY KEY,
+    mode TEXT NOT NULL DEFAULT 'shared',
+    port INTEGER NOT NULL DEFAULT 0,
+    lan INTEGER NOT NULL DEFAULT 0,
+    enabled INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
@@ -0,0 +1,274 @@
+//! Tamper-evident audit log of tool calls, hash-chained in SQLite.
+
+use crate::utils::redaction;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+/// `prev_hash` of the first entry.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallStatus {
+    Ok,
+    /// JSON-RPC error or a result with `isError`.
+    Error,
+    /// Replaced by a response policy.
+    Rejected,
+    /// Not forwarded: denied by approval policy, by the user, or timed out.
+    Denied,
+    /// Not forwarded: over a rate or concurrency limit.
+    Throttled,
+}
+
+impl CallStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Error => "error",
+            Self::Rejected => "rejected",
+            Self::Denied => "denied",
+            Self::Throttled => "throttled",
+        }
+    }
+}
+
+/// One tool call, as reported by the gateway or the inspector.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    pub timestamp: String,
+    pub client: String,
+    pub server_id: String,
+    pub server_name: String,
+    pub tool: String,
+    pub arguments: Value,
+    pub status: CallStatus,
+    pub duration_ms: u64,
+    /// Approval outcome, when the call needed one.
+    pub approval: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub seq: i64,
+    pub timestamp: String,
+    pub client: String,
+    pub server_id: String,
+    pub server_name: String,
+    pub tool: String,
+    pub arguments_digest: String,
+    /// Full arguments (JSON) when `auditLogArguments` is `full`.
+    pub arguments: Option<String>,
+    pub status: String,
+    pub duration_ms: i64,
+    pub approval: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// The hashed fields of an entry, in a fixed order.
+#[derive(Serialize)]
+struct HashedFields<'a> {
+    seq: i64,
+    timestamp: &'a str,
+    client: &'a str,
+    server_id: &'a str,
+    server_name: &'a str,
+    tool: &'a str,
+    arguments_digest: &'a str,
+    arguments: Option<&'a str>,
+    status: &'a str,
+    duration_ms: i64,
+    approval: Option<&'a str>,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> String {
+        let fields = HashedFields {
+            seq: self.seq,
+            timestamp: &self.timestamp,
+            client: &self.client,
+            server_id: &self.server_id,
+            server_name: &self.server_name,
+            tool: &self.tool,
+            arguments_digest: &self.arguments_digest,
+            arguments: self.arguments.as_deref(),
+            status: &self.status,
+            duration_ms: self.duration_ms,
+            approval: self.approval.as_deref(),
+        };
+        let canonical = serde_json::to_string(&fields).unwrap_or_default();
+        sha256_hex(&format!("{}\n{}", self.prev_hash, canonical))
+    }
+}
+
+fn sha256_hex(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+/// Settings key holding `seq:hash` of the newest entry, so dropping entries
+/// from the end of the table is detectable.
+const CHAIN_HEAD_SETTING: &str = "auditChainHead";
+
+/// Append a call to the chain. The chain head is read and extended inside one
+/// write transaction, so the app and stdio gateways can share the table.
+pub async fn append(db: &SqlitePool, record: &ToolCallRecord, full_arguments: bool) -> Result<AuditEntry, String> {
+    // Digested after redaction too, so a known secret cannot be confirmed by
+    // hashing guesses.
+    let arguments = redaction::redacted(&record.arguments).to_string();
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to open audit log: {}", e))?;
+
+    // Write first, so the transaction takes the write lock (waiting on the
+    // busy timeout) before the head is read.
+    sqlx::query("INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES (?, '', ?)")
+        .bind(CHAIN_HEAD_SETTING)
+        .bind(&record.timestamp)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to lock audit log: {}", e))?;
+
+    let head: Option<(i64, String)> = sqlx::query_as("SELECT seq, hash FROM audit_log ORDER BY seq DESC LIMIT 1")
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to append to audit log: {}", e))?;
+    let (seq, prev_hash) = match head {
+        Some((seq, hash)) => (seq + 1, hash),
+        None => (1, GENESIS_HASH.to_string()),
+    };
+
+    let mut entry = AuditEntry {
+        seq,
+        timestamp: record.timestamp.clone(),
+        client: record.client.clone(),
+        server_id: record.server_id.clone(),
+        server_name: record.server_name.clone(),
+        tool: record.tool.clone(),
+        arguments_digest: sha256_hex(&arguments),
+        arguments: full_arguments.then(|| arguments.clone()),
+        status: record.status.as_str().to_string(),
+        duration_ms: record.duration_ms as i64,
+        approval: record.approval.clone(),
+        prev_hash,
+        hash: String::new(),
+    };
+    entry.hash = entry.compute_hash();
+
+    sqlx::query(
+        "INSERT INTO audit_log (seq, timestamp, client, server_id, server_name, tool, arguments_digest, arguments, status, duration_ms, approval, prev_hash, hash)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(entry.seq)
+    .bind(&entry.timestamp)
+    .bind(&entry.client)
+    .bind(&entry.server_id)
+    .bind(&entry.server_name)
+    .bind(&entry.tool)
+    .bind(&entry.arguments_digest)
+    .bind(&entry.arguments)
+    .bind(&entry.status)
+    .bind(entry.duration_ms)
+    .bind(&entry.approval)
+    .bind(&entry.prev_hash)
+    .bind(&entry.hash)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to append to audit log: {}", e))?;
+
+    sqlx::query("UPDATE settings SET value = ?, updated_at = ? WHERE key = ?")
+        .bind(format!("{}:{}", entry.seq, entry.hash))
+        .bind(&entry.timestamp)
+        .bind(CHAIN_HEAD_SETTING)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to append to audit log: {}", e))?;
+
+    // Dropping `tx` on an error or cancellation rolls it back.
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to append to audit log: {}", e))?;
+
+    Ok(entry)
+}
+
+/// Append using the `auditLogArguments` setting; failures are logged, since
+/// the call itself has already happened.
+pub async fn record(db: &SqlitePool, record: &ToolCallRecord) {
+    let mode = crate::commands::settings::read_setting(db, "auditLogArguments", "digest").await;
+    if let Err(e) = append(db, record, mode == "full").await {
+        eprintln!("[Relay] {}", e);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub entries: u64,
+    /// First entry whose hash or link does not match.
+    pub broken_at: Option<i64>,
+    pub message: String,
+}
+
+/// Walk the whole chain and check every link and hash.
+pub async fn verify(db: &SqlitePool) -> Result<AuditVerification, String> {
+    let entries = sqlx::query_as::<_, AuditEntry>("SELECT * FROM audit_log ORDER BY seq")
+        .fetch_all(db)
+        .await
+        .map_err(|e| format!("Failed to read audit log: {}", e))?;
+
+    let mut expected_prev = GENESIS_HASH.to_string();
+    for (entry, expected_seq) in entries.iter().zip(1..) {
+        let problem = if entry.seq != expected_seq {
+            Some(format!("entry {} is missing", expected_seq))
+        } else if entry.prev_hash != expected_prev {
+            Some(format!("entry {} does not link to the previous entry", entry.seq))
+        } else if entry.hash != entry.compute_hash() {
+            Some(format!("entry {} was modified", entry.seq))
+        } else {
+            None
+        };
+
+        if let Some(message) = problem {
+            return Ok(AuditVerification {
+                valid: false,
+                entries: entries.len() as u64,
+                broken_at: Some(expected_seq),
+                message,
+            });
+        }
+
+        expected_prev = entry.hash.clone();
+    }
+
+    // The walk above cannot notice entries dropped from the end; compare the
+    // last entry with the recorded head.
+    let head = crate::commands::settings::read_setting(db, CHAIN_HEAD_SETTING, "").await;
+    let last = entries.last().map(|e| format!("{}:{}", e.seq, e.hash)).unwrap_or_default();
+    if head != last {
+        let recorded = head.split(':').next().and_then(|seq| seq.parse::<i64>().ok());
+        let count = entries.len() as i64;
+        let (broken_at, message) = match recorded {
+            Some(seq) if seq > count => (count + 1, format!("entries after {} are missing", count)),
+            Some(seq) if seq == count => (seq, format!("entry {} does not match the chain head", seq)),
+            Some(seq) => (seq + 1, format!("entries after {} were not appended by Relay", seq)),
+            None => (1, "the chain head is missing".to_string()),
+        };
+        return Ok(AuditVerification {
+            valid: false,
+            entries: entries.len() as u64,
+            broken_at: Some(broken_at),
+            message,
+        });
+    }
+
+    Ok(AuditVerification {
+        valid: true,
+        entries: entries.len() as u64,
+        broken_at: None,
+        message: format!("{} entries verified", entries.len()),
+    })
+}
@@ -0,0 +1,1010 @@
+//! Stdio-to-HTTP bridge: serves one stdio server over Streamable HTTP so
+//! clients that cannot launch processes (containerized agents, other machines
+//! on the LAN) can reach it. Clients authenticate with gateway client tokens.
+//!
+//! Client request ids are rewritten to ids from one counter per bridge, so
+//! sessions cannot collide in the server or in traffic stats. In shared mode
+//! every session talks to the process the app manages for the server, which
+//! is initialized once, and server messages about a request go only to the
+//! session that sent it. In isolated mode each session gets a process
+//! of its own, stopped when the session ends or goes idle.
+//!
+//! Servers with gateway policies are not bridged, and calls to tools the
+//! gateway would hold for approval (those marked destructive) are refused,
+//! since nobody can approve them here.
+
+use crate::commands::mcp::load_server;
+use crate::gateway::approval::{self, ApprovalMode};
+use crate::gateway::http::{authorize, ClientTokens};
+use crate::proxy::{record_traffic, TrafficDirection};
+use crate::state::AppState;
+use crate::utils::process::spawn_server;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::{IpAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::process::{Child, ChildStdin};
+use tokio::sync::{broadcast, oneshot, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::{timeout, Duration};
+
+const REQUEST_TIMEOUT_SECS: u64 = 300;
+/// Sessions unused for this long are ended, stopping their process in
+/// isolated mode.
+const SESSION_IDLE_SECS: u64 = 30 * 60;
+const SWEEP_INTERVAL_SECS: u64 = 60;
+/// Sessions one bridge serves at once; in isolated mode each is a process.
+const MAX_SESSIONS: usize = 16;
+const SHUTDOWN_TIMEOUT_SECS: u64 = 5;
+const EVENT_CAPACITY: usize = 256;
+const MAX_LIST_PAGES: usize = 50;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BridgeMode {
+    /// All sessions share the server process the app manages.
+    #[default]
+    Shared,
+    /// Each session gets a server process of its own.
+    Isolated,
+}
+
+impl BridgeMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "shared" => Some(Self::Shared),
+            "isolated" => Some(Self::Isolated),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Shared => "shared",
+            Self::Isolated => "isolated",
+        }
+    }
+}
+
+/// A request of ours waiting on the server.
+struct InFlight {
+    /// The session that sent it; `None` before the session exists.
+    session: Option<String>,
+    /// The id the client used, restored on the response.
+    client_id: Value,
+    /// The client's progress token, which the server sees as our id.
+    progress_token: Option<Value>,
+    waiter: oneshot::Sender<Value>,
+}
+
+type Pending = Mutex<HashMap<u64, InFlight>>;
+/// Requests from the server by JSON id, and the session asked to answer.
+type ServerRequests = Mutex<HashMap<String, String>>;
+/// The server's tool definitions by name, once fetched.
+type Tools = Mutex<Option<HashMap<String, Value>>>;
+
+/// Our request ids are strings so they never match the numeric ids the
+/// inspector uses on the same server.
+const ID_PREFIX: &str = "bridge-";
+
+fn parse_id(id: &Value) -> Option<u64> {
+    id.as_str()?.strip_prefix(ID_PREFIX)?.parse().ok()
+}
+
+fn link_id(message: &Value) -> Option<u64> {
+    parse_id(message.get("id")?)
+}
+
+/// A server message for the sessions' event streams.
+#[derive(Clone)]
+struct Routed {
+    /// The session it is for; `None` for every session.
+    session: Option<String>,
+    message: Value,
+}
+
+impl Routed {
+    fn is_for(&self, session_id: &str) -> bool {
+        self.session.as_deref().is_none_or(|session| session == session_id)
+    }
+}
+
+/// Decide which session a server notification or request goes to. Progress
+/// and cancellations go to the session whose request they concern, under
+/// the client's own token and id. On the shared link a server request goes
+/// to the session with the oldest request in flight, which the server is
+/// most likely serving; with none in flight there is nobody to answer it.
+/// Returns `Ok(None)` for messages to drop and `Err` with the reply to send
+/// the server for requests nobody can answer.
+async fn route(
+    shared: bool,
+    pending: &Pending,
+    server_requests: &ServerRequests,
+    mut message: Value,
+) -> Result<Option<Routed>, Value> {
+    let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+    let pending = pending.lock().await;
+
+    match method {
+        "notifications/progress" | "notifications/cancelled" => {
+            let pointer = if method == "notifications/progress" {
+                "/params/progressToken"
+            } else {
+                "/params/requestId"
+            };
+            let Some(request) = message.pointer(pointer).and_then(parse_id).and_then(|id| pending.get(&id)) else {
+                return Ok(None);
+            };
+            let restored = if method == "notifications/progress" {
+                request.progress_token.clone()
+            } else {
+                Some(request.client_id.clone())
+            };
+            let session = request.session.clone();
+            if let Some(field) = message.pointer_mut(pointer) {
+                *field = restored.unwrap_or(Value::Null);
+            }
+            Ok(Some(Routed { session, message }))
+        }
+        _ if shared && message.get("id").is_some() => {
+            let session = pending
+                .iter()
+                .filter_map(|(id, request)| Some((id, request.session.as_ref()?)))
+                .min_by_key(|(id, _)| **id)
+                .map(|(_, session)| session.clone());
+            let id = message.get("id").cloned().unwrap_or(Value::Null);
+            let Some(session) = session else {
+                return Err(json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": -32603, "message": "No bridge session is waiting on the server to answer this request" }
+                }));
+            };
+            server_requests.lock().await.insert(id.to_string(), session.clone());
+            Ok(Some(Routed { session: Some(session), message }))
+        }
+        _ => Ok(Some(Routed { session: None, message })),
+    }
+}
+
+async fn write_line(stdin: &Mutex<ChildStdin>, line: &str) -> Result<(), String> {
+    let mut stdin = stdin.lock().await;
+    stdin
+        .write_all(format!("{}\n", line).as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write to the server: {}", e))?;
+    stdin
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to write to the server: {}", e))
+}
+
+/// JSON-RPC over the stdio of one server process.
+struct Link {
+    stdin: Arc<Mutex<ChildStdin>>,
+    pending: Arc<Pending>,
+    /// The bridge's id counter, shared by all its links.
+    next_id: Arc<AtomicU64>,
+    /// Serves every session (shared mode) rather than one.
+    shared: bool,
+    server_requests: Arc<ServerRequests>,
+    /// Fetched on the first tool call and dropped when the server says its
+    /// tools changed.
+    tools: Arc<Tools>,
+    /// Notifications and requests from the server, for the sessions' event
+    /// streams.
+    events: broadcast::Sender<Routed>,
+    /// Initialize result, replayed to later sessions in shared mode.
+    initialize: Mutex<Option<Value>>,
+    initialized: AtomicBool,
+    reader: JoinHandle<()>,
+    /// The process, when the link owns it (isolated mode).
+    child: Option<Child>,
+}
+
+impl Link {
+    fn open(
+        app: &AppHandle,
+        server_id: &str,
+        stdin: Arc<Mutex<ChildStdin>>,
+        mut output: broadcast::Receiver<String>,
+        child: Option<Child>,
+        next_id: Arc<AtomicU64>,
+    ) -> Arc<Self> {
+        // Only the link to the managed process is without a child of its own.
+        let shared = child.is_none();
+        let pending: Arc<Pending> = Arc::new(Mutex::new(HashMap::new()));
+        let server_requests: Arc<ServerRequests> = Arc::new(Mutex::new(HashMap::new()));
+        let tools: Arc<Tools> = Arc::new(Mutex::new(None));
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+
+        let reader = {
+            let stdin = Arc::clone(&stdin);
+            let pending = Arc::clone(&pending);
+            let server_requests = Arc::clone(&server_requests);
+            let tools = Arc::clone(&tools);
+            let events = events.clone();
+            let app = app.clone();
+            let server_id = server_id.to_string();
+            tokio::spawn(async move {
+                loop {
+                    let line = match output.recv().await {
+                        Ok(line) => line,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    // Anything else on stdout is logging, already in the server log.
+                    let Ok(value) = serde_json::from_str::<Value>(&line) else {
+                        continue;
+                    };
+                    let state = app.state::<AppState>();
+                    record_traffic(&state, &app, &server_id, None, TrafficDirection::Inbound, &line).await;
+
+                    let messages = match value {
+                        Value::Array(batch) => batch,
+                        single => vec![single],
+                    };
+                    for message in messages {
+                        if let Some(method) = message.get("method").and_then(Value::as_str) {
+                            if method == "notifications/tools/list_changed" {
+                                tools.lock().await.take();
+                            }
+                            match route(shared, &pending, &server_requests, message).await {
+                                Ok(Some(routed)) => {
+                                    let _ = events.send(routed);
+                                }
+                                Ok(None) => {}
+                                Err(reply) => {
+                                    let line = reply.to_string();
+                                    record_traffic(&state, &app, &server_id, None, TrafficDirection::Outbound, &line)
+                                        .await;
+                                    let _ = write_line(&stdin, &line).await;
+                                }
+                            }
+                            continue;
+                        }
+                        // Responses to requests that timed out are dropped.
+                        let request = match link_id(&message) {
+                            Some(id) => pending.lock().await.remove(&id),
+                            None => None,
+                        };
+                        if let Some(request) = request {
+                            let _ = request.waiter.send(message);
+                        }
+                    }
+                }
+                pending.lock().await.clear();
+            })
+        };
+
+        Arc::new(Self {
+            stdin,
+            pending,
+            next_id,
+            shared,
+            server_requests,
+            tools,
+            events,
+            initialize: Mutex::new(None),
+            initialized: AtomicBool::new(false),
+            reader,
+            child,
+        })
+    }
+
+    async fn send(&self, app: &AppHandle, server_id: &str, client: &str, message: &Value) -> Result<(), String> {
+        let line = message.to_string();
+        let state = app.state::<AppState>();
+        record_traffic(&state, app, server_id, Some(client), TrafficDirection::Outbound, &line).await;
+        write_line(&self.stdin, &line).await
+    }
+
+    /// Forward a client request under an id of our own and return the
+    /// response under the client's id. `session` is the session sending it,
+    /// once it has one.
+    async fn request(
+        &self,
+        app: &AppHandle,
+        server_id: &str,
+        session: Option<&str>,
+        client: &str,
+        mut message: Value,
+    ) -> Result<Value, String> {
+        let client_id = message.get("id").cloned().unwrap_or(Value::Null);
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or("request")
+            .to_string();
+
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let link_id = json!(format!("{}{}", ID_PREFIX, id));
+        message["id"] = link_id.clone();
+        // Progress comes back under our id, so tokens from different
+        // sessions cannot collide.
+        let progress_token = message
+            .pointer_mut("/params/_meta/progressToken")
+            .map(|token| std::mem::replace(token, link_id));
+
+        let (waiter, response) = oneshot::channel();
+        self.pending.lock().await.insert(
+            id,
+            InFlight {
+                session: session.map(str::to_string),
+                client_id: client_id.clone(),
+                progress_token,
+                waiter,
+            },
+        );
+
+        if let Err(e) = self.send(app, server_id, client, &message).await {
+            self.pending.lock().await.remove(&id);
+            return Err(e);
+        }
+
+        let mut response = match timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS), response).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(_)) => return Err(format!("Server exited during {}", method)),
+            Err(_) => {
+                self.pending.lock().await.remove(&id);
+                return Err(format!(
+                    "Timeout waiting for the server during {} (waited {}s)",
+                    method, REQUEST_TIMEOUT_SECS
+                ));
+            }
+        };
+        response["id"] = client_id;
+        Ok(response)
+    }
+
+    /// The definition of one of the server's tools, listing them first if
+    /// they are not known yet.
+    async fn tool_definition(
+        &self,
+        app: &AppHandle,
+        server_id: &str,
+        session: &str,
+        client: &str,
+        tool: &str,
+    ) -> Result<Option<Value>, String> {
+        if let Some(tools) = self.tools.lock().await.as_ref() {
+            return Ok(tools.get(tool).cloned());
+        }
+
+        // Not holding the lock while waiting: the reader takes it when the
+        // server's tools change.
+        let mut listed = HashMap::new();
+        let mut cursor: Option<Value> = None;
+        for _page in 0..MAX_LIST_PAGES {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let request = json!({ "jsonrpc": "2.0", "id": 0, "method": "tools/list", "params": params });
+            let response = self.request(app, server_id, Some(session), client, request).await?;
+            if let Some(error) = response.get("error") {
+                return Err(format!("Server returned tools/list error: {}", error));
+            }
+            let page = response.pointer("/result/tools").and_then(Value::as_array);
+            for definition in page.into_iter().flatten() {
+                if let Some(name) = definition.get("name").and_then(Value::as_str) {
+                    listed.insert(name.to_string(), definition.clone());
+                }
+            }
+            cursor = response
+                .pointer("/result/nextCursor")
+                .filter(|c| !c.is_null())
+                .cloned();
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        let definition = listed.get(tool).cloned();
+        *self.tools.lock().await = Some(listed);
+        Ok(definition)
+    }
+
+    /// Forward a session's cancellation under our id for the request. A
+    /// request that already finished needs no cancelling.
+    async fn cancel(
+        &self,
+        app: &AppHandle,
+        server_id: &str,
+        session: &str,
+        client: &str,
+        mut message: Value,
+    ) -> Result<(), String> {
+        let request_id = message.pointer("/params/requestId").cloned().unwrap_or(Value::Null);
+        let id = self
+            .pending
+            .lock()
+            .await
+            .iter()
+            .find(|(_, request)| request.session.as_deref() == Some(session) && request.client_id == request_id)
+            .map(|(id, _)| *id);
+        let Some(id) = id else {
+            return Ok(());
+        };
+        message["params"]["requestId"] = json!(format!("{}{}", ID_PREFIX, id));
+        self.send(app, server_id, client, &message).await
+    }
+
+    /// Forward a session's response to a server request. On the shared
+    /// link only the session the request went to may answer it.
+    async fn respond(
+        &self,
+        app: &AppHandle,
+        server_id: &str,
+        session: &str,
+        client: &str,
+        message: &Value,
+    ) -> Result<(), String> {
+        if self.shared {
+            let key = message.get("id").map(Value::to_string).unwrap_or_default();
+            let mut requests = self.server_requests.lock().await;
+            if requests.get(&key).map(String::as_str) != Some(session) {
+                return Ok(());
+            }
+            requests.remove(&key);
+        }
+        self.send(app, server_id, client, message).await
+    }
+
+    /// Send `notifications/initialized` unless this process already got it.
+    async fn notify_initialized(&self, app: &AppHandle, server_id: &str, client: &str) -> Result<(), String> {
+        if self.initialized.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        self.send(app, server_id, client, &json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await
+    }
+}
+
+impl Drop for Link {
+    fn drop(&mut self) {
+        self.reader.abort();
+        if let Some(child) = self.child.as_mut() {
+            let _ = child.start_kill();
+        }
+    }
+}
+
+struct BridgeSession {
+    client: String,
+    /// The session's own process in isolated mode; `None` in shared mode.
+    link: Option<Arc<Link>>,
+    last_seen: Instant,
+}
+
+struct BridgeState {
+    app: AppHandle,
+    server_id: String,
+    mode: BridgeMode,
+    tokens: ClientTokens,
+    sessions: Mutex<HashMap<String, BridgeSession>>,
+    /// Link to the managed process, replaced when the process restarts.
+    shared: Mutex<Option<Arc<Link>>>,
+    /// Params of the first initialize, replayed after the managed process
+    /// restarts so existing sessions keep working.
+    initialize_params: Mutex<Option<Value>>,
+    next_id: Arc<AtomicU64>,
+}
+
+fn error_response(id: Value, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": -32603, "message": message }
+    })
+}
+
+impl BridgeState {
+    /// Link to the process the app manages for the server.
+    async fn shared_link(&self) -> Result<Arc<Link>, String> {
+        let state = self.app.state::<AppState>();
+        let processes = state.processes.lock().await;
+        let process = processes
+            .get(&self.server_id)
+            .ok_or("The server is not running; start it in Relay to use the bridge")?;
+
+        let mut shared = self.shared.lock().await;
+        if let Some(link) = shared.as_ref().filter(|link| Arc::ptr_eq(&link.stdin, &process.stdin)) {
+            return Ok(Arc::clone(link));
+        }
+        let link = Link::open(
+            &self.app,
+            &self.server_id,
+            Arc::clone(&process.stdin),
+            process.output.subscribe(),
+            None,
+            Arc::clone(&self.next_id),
+        );
+        *shared = Some(Arc::clone(&link));
+        Ok(link)
+    }
+
+    /// The shared link, initialized again if the process restarted since
+    /// the first session initialized it. `client` is the session asking.
+    async fn ready_shared_link(&self, client: &str) -> Result<Arc<Link>, String> {
+        let link = self.shared_link().await?;
+        let mut initialize = link.initialize.lock().await;
+        if initialize.is_none() {
+            if let Some(params) = self.initialize_params.lock().await.clone() {
+                let request = json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": params });
+                let response = link.request(&self.app, &self.server_id, None, client, request).await?;
+                if let Some(error) = response.get("error") {
+                    return Err(format!("Server returned initialize error: {}", error));
+                }
+                *initialize = response.get("result").cloned();
+                link.notify_initialized(&self.app, &self.server_id, client).await?;
+            }
+        }
+        drop(initialize);
+        Ok(link)
+    }
+
+    /// Start a process of the server for an isolated session.
+    async fn spawn_link(&self) -> Result<Arc<Link>, String> {
+        let state = self.app.state::<AppState>();
+        let server = load_server(&state, &self.server_id).await?;
+        let args: Vec<String> = serde_json::from_str(&server.args).unwrap_or_default();
+        let envs: HashMap<String, String> = serde_json::from_str(&server.env).unwrap_or_default();
+        let secrets: Vec<String> = serde_json::from_str(&server.secrets).unwrap_or_default();
+
+        let process = spawn_server(
+            server.id.clone(),
+            server.name.clone(),
+            server.command.clone(),
+            args,
+            envs,
+            secrets,
+            self.app.clone(),
+        )
+        .await?;
+        let output = process.output.subscribe();
+        Ok(Link::open(
+            &self.app,
+            &self.server_id,
+            process.stdin,
+            output,
+            Some(process.child),
+            Arc::clone(&self.next_id),
+        ))
+    }
+
+    /// Answer a client's initialize and open its session. Returns the
+    /// response and, when the server accepted it, the new session id.
+    async fn initialize(&self, client: &str, message: Value) -> Result<(Value, Option<String>), String> {
+        self.end_idle_sessions().await;
+        self.check_capacity(&*self.sessions.lock().await)?;
+
+        let (response, link) = match self.mode {
+            BridgeMode::Shared => {
+                let link = self.shared_link().await?;
+                let mut initialize = link.initialize.lock().await;
+                let response = match initialize.as_ref() {
+                    Some(result) => json!({
+                        "jsonrpc": "2.0",
+                        "id": message.get("id").cloned().unwrap_or(Value::Null),
+                        "result": result
+                    }),
+                    None => {
+                        let params = message.get("params").cloned().unwrap_or(json!({}));
+                        let response = link.request(&self.app, &self.server_id, None, client, message).await?;
+                        if let Some(result) = response.get("result") {
+                            *initialize = Some(result.clone());
+                            *self.initialize_params.lock().await = Some(params);
+                        }
+                        response
+                    }
+                };
+                (response, None)
+            }
+            BridgeMode::Isolated => {
+                let link = self.spawn_link().await?;
+                let response = link.request(&self.app, &self.server_id, None, client, message).await?;
+                (response, Some(link))
+            }
+        };
+
+        if response.get("error").is_some() {
+            return Ok((response, None));
+        }
+
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let mut sessions = self.sessions.lock().await;
+        // Other sessions may have started while the server was initializing.
+        self.check_capacity(&sessions)?;
+        sessions.insert(
+            session_id.clone(),
+            BridgeSession {
+                client: client.to_string(),
+                link,
+                last_seen: Instant::now(),
+            },
+        );
+        Ok((response, Some(session_id)))
+    }
+
+    /// Refuse calls the gateway would hold for approval: the bridge has no
+    /// way to ask, and must not make them unasked.
+    async fn check_call(&self, link: &Link, session: &str, client: &str, message: &Value) -> Result<(), String> {
+        let tool = message.pointer("/params/name").and_then(Value::as_str).unwrap_or("");
+        let definition = link
+            .tool_definition(&self.app, &self.server_id, session, client, tool)
+            .await
+            .map_err(|e| format!("Could not check whether {} needs approval: {}", tool, e))?;
+        if approval::mode_for(None, tool, definition.as_ref()) != ApprovalMode::AlwaysAllow {
+            return Err(format!(
+                "{} is marked destructive, so Relay requires approval for it, which the bridge cannot ask for. Call it through the Relay HTTP gateway instead.",
+                tool
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_capacity(&self, sessions: &HashMap<String, BridgeSession>) -> Result<(), String> {
+        if sessions.len() >= MAX_SESSIONS {
+            return Err(format!(
+                "The bridge already serves {} sessions; end one before starting another",
+                MAX_SESSIONS
+            ));
+        }
+        Ok(())
+    }
+
+    /// End sessions unused for `SESSION_IDLE_SECS`, stopping their process
+    /// in isolated mode, and forget server requests left to them.
+    async fn end_idle_sessions(&self) {
+        let mut sessions = self.sessions.lock().await;
+        sessions.retain(|_, session| session.last_seen.elapsed() < Duration::from_secs(SESSION_IDLE_SECS));
+
+        if let Some(link) = self.shared.lock().await.as_ref() {
+            link.server_requests
+                .lock()
+                .await
+                .retain(|_, session| sessions.contains_key(session));
+        }
+    }
+
+    /// Look up the caller's session, returning its id and its link, which
+    /// is `None` in shared mode.
+    async fn session_link<'a>(
+        &self,
+        session_id: Option<&'a str>,
+        client: &str,
+    ) -> Result<(&'a str, Option<Arc<Link>>), Response> {
+        let Some(session_id) = session_id else {
+            return Err((StatusCode::BAD_REQUEST, "Missing mcp-session-id header; send initialize first").into_response());
+        };
+
+        let mut sessions = self.sessions.lock().await;
+        match sessions.get_mut(session_id) {
+            Some(session) if session.client == client => {
+                session.last_seen = Instant::now();
+                Ok((session_id, session.link.clone()))
+            }
+            _ => Err((StatusCode::NOT_FOUND, "Unknown session").into_response()),
+        }
+    }
+
+    /// Forward one message from `client`'s session. Returns the response to
+    /// a request.
+    async fn forward(&self, link: Option<Arc<Link>>, session: &str, client: &str, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str).map(str::to_string);
+
+        let link = match link {
+            Some(link) => link,
+            None => match self.ready_shared_link(client).await {
+                Ok(link) => link,
+                Err(e) => return id.map(|id| error_response(id, e)),
+            },
+        };
+
+        if method.as_deref() == Some("tools/call") {
+            if let Err(reason) = self.check_call(&link, session, client, &message).await {
+                let refusal = json!({
+                    "content": [{ "type": "text", "text": reason }],
+                    "isError": true
+                });
+                return id.map(|id| json!({ "jsonrpc": "2.0", "id": id, "result": refusal }));
+            }
+        }
+
+        let result = match (&id, method.as_deref()) {
+            (Some(_), Some(_)) => link.request(&self.app, &self.server_id, Some(session), client, message).await.map(Some),
+            (None, Some("notifications/initialized")) => {
+                link.notify_initialized(&self.app, &self.server_id, client).await.map(|_| None)
+            }
+            (None, Some("notifications/cancelled")) => link
+                .cancel(&self.app, &self.server_id, session, client, message)
+                .await
+                .map(|_| None),
+            (Some(_), None) => link
+                .respond(&self.app, &self.server_id, session, client, &message)
+                .await
+                .map(|_| None),
+            _ => link.send(&self.app, &self.server_id, client, &message).await.map(|_| None),
+        };
+
+        match result {
+            Ok(response) => response,
+            Err(e) => match (id, method) {
+                (Some(id), Some(_)) => Some(error_response(id, e)),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Address of this machine on the LAN. Connecting a UDP socket sends
+/// nothing; it only picks the outgoing interface.
+fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).ok()?;
+    socket.connect(("192.0.2.1", 9)).ok()?;
+    socket.local_addr().ok().map(|address| address.ip())
+}
+
+pub struct StdioBridge {
+    pub server_id: String,
+    pub port: u16,
+    pub mode: BridgeMode,
+    /// Listening on all interfaces rather than localhost only.
+    pub lan: bool,
+    state: Arc<BridgeState>,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+    sweep: JoinHandle<()>,
+}
+
+impl StdioBridge {
+    pub async fn start(
+        app: AppHandle,
+        tokens: ClientTokens,
+        server_id: String,
+        mode: BridgeMode,
+        port: u16,
+        lan: bool,
+    ) -> Result<Self, String> {
+        let host = if lan { "0.0.0.0" } else { "127.0.0.1" };
+        let listener = TcpListener::bind((host, port))
+            .await
+            .map_err(|e| format!("Failed to bind {}:{}: {}", host, port, e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| e.to_string())?
+            .port();
+
+        let state = Arc::new(BridgeState {
+            app,
+            server_id: server_id.clone(),
+            mode,
+            tokens,
+            sessions: Mutex::new(HashMap::new()),
+            shared: Mutex::new(None),
+            initialize_params: Mutex::new(None),
+            next_id: Arc::new(AtomicU64::new(1)),
+        });
+
+        let sweep = {
+            let state = Arc::downgrade(&state);
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(SWEEP_INTERVAL_SECS));
+                loop {
+                    interval.tick().await;
+                    let Some(state) = state.upgrade() else {
+                        break;
+                    };
+                    state.end_idle_sessions().await;
+                }
+            })
+        };
+
+        let router = Router::new()
+            .route("/mcp", post(handle_post).get(handle_get).delete(handle_delete))
+            .with_state(Arc::clone(&state));
+
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let _ = axum::serve(listener, router)
+                .with_graceful_shutdown(async {
+                    let _ = shutdown_rx.await;
+                })
+                .await;
+        });
+
+        Ok(Self {
+            server_id,
+            port,
+            mode,
+            lan,
+            state,
+            shutdown,
+            task,
+            sweep,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        let host = if self.lan {
+            lan_address().map(|ip| ip.to_string())
+        } else {
+            None
+        };
+        format!("http://{}:{}/mcp", host.as_deref().unwrap_or("127.0.0.1"), self.port)
+    }
+
+    pub async fn session_count(&self) -> usize {
+        self.state.sessions.lock().await.len()
+    }
+
+    pub async fn stop(self) {
+        self.sweep.abort();
+        // Ending the sessions closes their event streams, which graceful
+        // shutdown would otherwise wait on.
+        self.state.sessions.lock().await.clear();
+        self.state.shared.lock().await.take();
+        let _ = self.shutdown.send(());
+
+        let mut task = self.task;
+        if timeout(Duration::from_secs(SHUTDOWN_TIMEOUT_SECS), &mut task).await.is_err() {
+            task.abort();
+        }
+    }
+}
+
+async fn handle_post(State(bridge): State<Arc<BridgeState>>, headers: HeaderMap, body: String) -> Response {
+    let client = match authorize(&bridge.tokens, &headers).await {
+        Ok(client) => client,
+        Err(response) => return response,
+    };
+
+    let payload = match serde_json::from_str::<Value>(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            let error = json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": -32700, "message": format!("Parse error: {}", e) }
+            });
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    let is_batch = payload.is_array();
+    let messages = match payload {
+        Value::Array(batch) => batch,
+        single => vec![single],
+    };
+
+    let mut session_id = headers
+        .get("mcp-session-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let mut started = None;
+    let mut responses = Vec::new();
+
+    for message in messages {
+        if message.get("method").and_then(Value::as_str) == Some("initialize") {
+            let id = message.get("id").cloned().unwrap_or(Value::Null);
+            match bridge.initialize(&client, message).await {
+                Ok((response, id)) => {
+                    if let Some(id) = id {
+                        session_id = Some(id.clone());
+                        started = Some(id);
+                    }
+                    responses.push(response);
+                }
+                Err(e) => responses.push(error_response(id, e)),
+            }
+            continue;
+        }
+
+        let (session, link) = match bridge.session_link(session_id.as_deref(), &client).await {
+            Ok(found) => found,
+            Err(response) => return response,
+        };
+        if let Some(response) = bridge.forward(link, session, &client, message).await {
+            responses.push(response);
+        }
+    }
+
+    if responses.is_empty() {
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    let body = if is_batch {
+        Value::Array(responses)
+    } else {
+        responses.remove(0)
+    };
+    let mut response = Json(body).into_response();
+
+    if let Some(value) = started.and_then(|id| HeaderValue::from_str(&id).ok()) {
+        response.headers_mut().insert("mcp-session-id", value);
+    }
+
+    response
+}
+
+/// Server-to-client stream carrying the server's notifications and
+/// requests for the caller's session.
+async fn handle_get(State(bridge): State<Arc<BridgeState>>, headers: HeaderMap) -> Response {
+    let client = match authorize(&bridge.tokens, &headers).await {
+        Ok(client) => client,
+        Err(response) => return response,
+    };
+
+    let accepts_sse = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.contains("text/event-stream"))
+        .unwrap_or(false);
+    if !accepts_sse {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+
+    let session_id = headers.get("mcp-session-id").and_then(|v| v.to_str().ok());
+    let (session, link) = match bridge.session_link(session_id, &client).await {
+        Ok((session, Some(link))) => (session.to_string(), link),
+        Ok((session, None)) => match bridge.shared_link().await {
+            Ok(link) => (session.to_string(), link),
+            Err(e) => return (StatusCode::SERVICE_UNAVAILABLE, e).into_response(),
+        },
+        Err(response) => return response,
+    };
+
+    // The stream ends when the link goes away with its session or process.
+    let receiver = link.events.subscribe();
+    drop(link);
+    let stream = futures_util::stream::unfold(receiver, move |mut receiver| {
+        let session = session.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(routed) if routed.is_for(&session) => {
+                        let event = Event::default().event("message").data(routed.message.to_string());
+                        return Some((Ok::<Event, Infallible>(event), receiver));
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+async fn handle_delete(State(bridge): State<Arc<BridgeState>>, headers: HeaderMap) -> Response {
+    let client = match authorize(&bridge.tokens, &headers).await {
+        Ok(client) => client,
+        Err(response) => return response,
+    };
+
+    if let Some(session_id) = headers.get("mcp-session-id").and_then(|v| v.to_str().ok()) {
+        let mut sessions = bridge.sessions.lock().await;
+        if sessions.get(session_id).is_some_and(|session| session.client == client) {
+            sessions.remove(session_id);
+        }
+    }
+
+    StatusCode::OK.into_response()
+}
@@ -0,0 +1,155 @@
+use crate::gateway::approval::{ApprovalDecision, ApprovalRequest};
+use crate::gateway::control::ControlCommand;
+use crate::gateway::Gateway;
+use crate::state::AppState;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::broadcast;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ApprovalLogEntry {
+    pub id: String,
+    pub server_id: String,
+    pub server_name: String,
+    pub tool: String,
+    pub arguments: String, // JSON
+    pub client: String,
+    pub session_id: String,
+    pub mode: String,
+    pub outcome: String,
+    pub requested_at: String,
+    pub decided_at: String,
+}
+
+/// Bring the main window forward so the approval prompt is seen.
+pub(crate) fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Record a decision in `approval_decisions`. Also used by `relay --gateway`
+/// instances, which write to the app's database directly.
+pub(crate) async fn log_decision(db: &SqlitePool, decision: &ApprovalDecision) -> Result<(), String> {
+    let request = &decision.request;
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO approval_decisions (id, server_id, server_name, tool, arguments, client, session_id, mode, outcome, requested_at, decided_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&request.id)
+    .bind(&request.server_id)
+    .bind(&request.server_name)
+    .bind(&request.tool)
+    .bind(request.arguments.to_string())
+    .bind(&request.client)
+    .bind(&request.session_id)
+    .bind(request.mode.as_str())
+    .bind(decision.outcome.as_str())
+    .bind(&request.requested_at)
+    .bind(&decision.decided_at)
+    .execute(db)
+    .await
+    .map_err(|e| format!("Failed to log approval decision: {}", e))?;
+
+    Ok(())
+}
+
+/// Surface the gateway's approval requests in the app and log every decision.
+pub(crate) fn forward_approvals(gateway: &Gateway, app: &AppHandle) {
+    let mut requests = gateway.approvals().subscribe_requests();
+    let request_app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            match requests.recv().await {
+                Ok(request) => {
+                    eprintln!(
+                        "[Relay] Approval requested for {}/{} by {}",
+                        request.server_name, request.tool, request.client
+                    );
+                    show_main_window(&request_app);
+                    let _ = request_app.emit("tool-approval-request", &request);
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    let mut decisions = gateway.approvals().subscribe_decisions();
+    let app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            match decisions.recv().await {
+                Ok(decision) => {
+                    let state: State<AppState> = app.state();
+                    let db = state.db.lock().await.clone();
+                    if let Err(e) = log_decision(&db, &decision).await {
+                        eprintln!("[Relay] {}", e);
+                    }
+                    let _ = app.emit("tool-approval-decided", &decision);
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn resolve_tool_approval(
+    state: State<'_, AppState>,
+    request_id: String,
+    approved: bool,
+) -> Result<(), String> {
+    if let Some(server) = state.http_gateway.lock().await.as_ref() {
+        if server.gateway.approvals().resolve(&request_id, approved).await {
+            return Ok(());
+        }
+    }
+
+    // Requests from `relay --gateway` instances are answered over the
+    // control channel.
+    let sessions = state.gateway_sessions.lock().await;
+    match sessions
+        .values()
+        .find(|session| session.pending_approvals.contains_key(&request_id))
+    {
+        Some(session) => session
+            .commands
+            .send(ControlCommand::ResolveApproval { request_id, approved })
+            .map_err(|_| format!("Gateway session {} is closing", session.info.instance_id)),
+        None => Err("This approval request has already been decided or has expired".to_string()),
+    }
+}
+
+/// Calls still waiting for a decision.
+#[tauri::command]
+pub async fn list_pending_approvals(state: State<'_, AppState>) -> Result<Vec<ApprovalRequest>, String> {
+    let mut pending = match state.http_gateway.lock().await.as_ref() {
+        Some(server) => server.gateway.approvals().pending().await,
+        None => Vec::new(),
+    };
+    for session in state.gateway_sessions.lock().await.values() {
+        pending.extend(session.pending_approvals.values().cloned());
+    }
+    pending.sort_by(|a, b| a.requested_at.cmp(&b.requested_at));
+    Ok(pending)
+}
+
+#[tauri::command]
+pub async fn list_approval_decisions(
+    state: State<'_, AppState>,
+    limit: Option<i64>,
+) -> Result<Vec<ApprovalLogEntry>, String> {
+    let db = state.db.lock().await;
+
+    sqlx::query_as::<_, ApprovalLogEntry>("SELECT * FROM approval_decisions ORDER BY decided_at DESC LIMIT ?")
+        .bind(limit.unwrap_or(100))
+        .fetch_all(&*db)
+        .await
+        .map_err(|e| format!("Failed to fetch approval decisions: {}", e))
+}
@@ -0,0 +1,142 @@
+use crate::audit::{self, AuditEntry, AuditVerification};
+use crate::state::AppState;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::io::Write;
+use tauri::State;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditQuery {
+    /// Inclusive RFC 3339 start; from the beginning when omitted.
+    pub from: Option<String>,
+    /// Exclusive RFC 3339 end; up to now when omitted.
+    pub to: Option<String>,
+    pub server_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditExportFormat {
+    Jsonl,
+    Csv,
+}
+
+/// Normalize to the form entries are stored in (`to_rfc3339` in UTC) so the
+/// range compares as text.
+fn to_entry_time(value: &str) -> Result<String, String> {
+    let parsed = chrono::DateTime::parse_from_rfc3339(value)
+        .map_err(|e| format!("Invalid timestamp {}: {}", value, e))?;
+    Ok(parsed.with_timezone(&chrono::Utc).to_rfc3339())
+}
+
+/// Entries matching the query in chain order; only the newest `limit` when
+/// given.
+async fn query_entries(db: &SqlitePool, query: &AuditQuery, limit: Option<i64>) -> Result<Vec<AuditEntry>, String> {
+    let from = query.from.as_deref().map(to_entry_time).transpose()?;
+    let to = query.to.as_deref().map(to_entry_time).transpose()?;
+
+    sqlx::query_as::<_, AuditEntry>(
+        "SELECT * FROM (
+            SELECT * FROM audit_log
+            WHERE (? IS NULL OR timestamp >= ?)
+              AND (? IS NULL OR timestamp < ?)
+              AND (? IS NULL OR server_id = ?)
+            ORDER BY seq DESC
+            LIMIT ?
+         ) ORDER BY seq",
+    )
+    .bind(&from)
+    .bind(&from)
+    .bind(&to)
+    .bind(&to)
+    .bind(&query.server_id)
+    .bind(&query.server_id)
+    .bind(limit.unwrap_or(-1))
+    .fetch_all(db)
+    .await
+    .map_err(|e| format!("Failed to read audit log: {}", e))
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_csv(out: &mut impl Write, entries: &[AuditEntry]) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "seq,timestamp,client,server_id,server_name,tool,arguments_digest,arguments,status,duration_ms,approval,prev_hash,hash"
+    )?;
+    for entry in entries {
+        let fields = [
+            entry.seq.to_string(),
+            csv_field(&entry.timestamp),
+            csv_field(&entry.client),
+            csv_field(&entry.server_id),
+            csv_field(&entry.server_name),
+            csv_field(&entry.tool),
+            entry.arguments_digest.clone(),
+            csv_field(entry.arguments.as_deref().unwrap_or("")),
+            entry.status.clone(),
+            entry.duration_ms.to_string(),
+            csv_field(entry.approval.as_deref().unwrap_or("")),
+            entry.prev_hash.clone(),
+            entry.hash.clone(),
+        ];
+        writeln!(out, "{}", fields.join(","))?;
+    }
+    Ok(())
+}
+
+fn write_jsonl(out: &mut impl Write, entries: &[AuditEntry]) -> std::io::Result<()> {
+    for entry in entries {
+        let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Most recent audit entries matching the query, newest last.
+#[tauri::command]
+pub async fn get_audit_log(
+    state: State<'_, AppState>,
+    query: AuditQuery,
+    limit: Option<i64>,
+) -> Result<Vec<AuditEntry>, String> {
+    let db = state.db.lock().await.clone();
+    query_entries(&db, &query, Some(limit.unwrap_or(200).max(0))).await
+}
+
+#[tauri::command]
+pub async fn verify_audit_log(state: State<'_, AppState>) -> Result<AuditVerification, String> {
+    let db = state.db.lock().await.clone();
+    audit::verify(&db).await
+}
+
+/// Write matching entries to `path` as JSONL or CSV. Entries keep their
+/// hashes, so a complete export can be verified outside Relay. Returns the
+/// number of entries written.
+#[tauri::command]
+pub async fn export_audit_log(
+    state: State<'_, AppState>,
+    query: AuditQuery,
+    format: AuditExportFormat,
+    path: String,
+) -> Result<usize, String> {
+    let db = state.db.lock().await.clone();
+    let entries = query_entries(&db, &query, None).await?;
+
+    let file = std::fs::File::create(&path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    let mut out = std::io::BufWriter::new(file);
+    match format {
+        AuditExportFormat::Jsonl => write_jsonl(&mut out, &entries),
+        AuditExportFormat::Csv => write_csv(&mut out, &entries),
+    }
+    .and_then(|_| out.flush())
+    .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+
+    Ok(entries.len())
+}
@@ -0,0 +1,226 @@
+use crate::bridge::{BridgeMode, StdioBridge};
+use crate::commands::gateway::load_client_tokens;
+use crate::commands::mcp::load_server;
+use crate::gateway::config::GatewayServerConfig;
+use crate::models::bridge::Bridge;
+use crate::models::server::Server;
+use crate::state::AppState;
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BridgeStatus {
+    pub server_id: String,
+    pub mode: BridgeMode,
+    pub port: u16,
+    pub lan: bool,
+    pub running: bool,
+    pub url: Option<String>,
+    pub sessions: usize,
+}
+
+impl BridgeStatus {
+    fn stopped(bridge: &Bridge) -> Self {
+        Self {
+            server_id: bridge.server_id.clone(),
+            mode: BridgeMode::parse(&bridge.mode).unwrap_or_default(),
+            port: u16::try_from(bridge.port).unwrap_or(0),
+            lan: bridge.lan,
+            running: false,
+            url: None,
+            sessions: 0,
+        }
+    }
+
+    async fn running(bridge: &StdioBridge) -> Self {
+        Self {
+            server_id: bridge.server_id.clone(),
+            mode: bridge.mode,
+            port: bridge.port,
+            lan: bridge.lan,
+            running: true,
+            url: Some(bridge.url()),
+            sessions: bridge.session_count().await,
+        }
+    }
+}
+
+/// The bridge forwards raw JSON-RPC, so it cannot serve a server whose calls
+/// the gateway would filter, hold for approval, throttle or rewrite.
+fn check_policies(server: &Server) -> Result<(), String> {
+    let policies = GatewayServerConfig::from_server(server).call_policies();
+    if policies.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "{} has policies the bridge cannot enforce ({}); remove them or connect through the Relay HTTP gateway",
+        server.name,
+        policies.join(", ")
+    ))
+}
+
+async fn saved_bridge(state: &AppState, server_id: &str) -> Result<Option<Bridge>, String> {
+    let db = state.db.lock().await;
+
+    sqlx::query_as::<_, Bridge>("SELECT * FROM bridges WHERE server_id = ?")
+        .bind(server_id)
+        .fetch_optional(&*db)
+        .await
+        .map_err(|e| format!("Failed to fetch bridge: {}", e))
+}
+
+/// Start (or restart) the bridge for a server. Unset options keep their
+/// saved values; a port of 0 picks a free one, which is then kept.
+async fn start(
+    state: &AppState,
+    app: &AppHandle,
+    server_id: &str,
+    mode: Option<BridgeMode>,
+    port: Option<u16>,
+    lan: Option<bool>,
+) -> Result<BridgeStatus, String> {
+    let server = load_server(state, server_id).await?;
+    if !matches!(server.transport.as_deref(), None | Some("stdio") | Some("")) {
+        return Err(format!("{} is not a stdio server", server.name));
+    }
+    check_policies(&server)?;
+
+    let saved = saved_bridge(state, server_id).await?;
+    let mode = mode
+        .or_else(|| saved.as_ref().and_then(|b| BridgeMode::parse(&b.mode)))
+        .unwrap_or_default();
+    let port = port
+        .or_else(|| saved.as_ref().and_then(|b| u16::try_from(b.port).ok()))
+        .unwrap_or(0);
+    let lan = lan.or_else(|| saved.as_ref().map(|b| b.lan)).unwrap_or(false);
+    load_client_tokens(state).await?;
+
+    let mut bridges = state.bridges.lock().await;
+    if let Some(existing) = bridges.remove(&server.id) {
+        existing.stop().await;
+    }
+    let bridge = StdioBridge::start(
+        app.clone(),
+        state.gateway_tokens.clone(),
+        server.id.clone(),
+        mode,
+        port,
+        lan,
+    )
+    .await?;
+    let status = BridgeStatus::running(&bridge).await;
+    bridges.insert(server.id.clone(), bridge);
+    drop(bridges);
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let db = state.db.lock().await;
+    sqlx::query(
+        "INSERT INTO bridges (server_id, mode, port, lan, enabled, created_at, updated_at) VALUES (?, ?, ?, ?, 1, ?, ?)
+         ON CONFLICT(server_id) DO UPDATE SET mode = excluded.mode, port = excluded.port, lan = excluded.lan, enabled = 1, updated_at = excluded.updated_at",
+    )
+    .bind(&server.id)
+    .bind(mode.as_str())
+    .bind(status.port as i64)
+    .bind(lan)
+    .bind(&now)
+    .bind(&now)
+    .execute(&*db)
+    .await
+    .map_err(|e| format!("Failed to save bridge: {}", e))?;
+
+    Ok(status)
+}
+
+/// Stop a server's bridge, if running, and optionally forget its settings.
+pub(crate) async fn stop(state: &AppState, server_id: &str, forget: bool) -> Result<(), String> {
+    if let Some(bridge) = state.bridges.lock().await.remove(server_id) {
+        bridge.stop().await;
+    }
+
+    let db = state.db.lock().await;
+    let query = if forget {
+        "DELETE FROM bridges WHERE server_id = ?"
+    } else {
+        "UPDATE bridges SET enabled = 0 WHERE server_id = ?"
+    };
+    sqlx::query(query)
+        .bind(server_id)
+        .execute(&*db)
+        .await
+        .map_err(|e| format!("Failed to save bridge: {}", e))?;
+
+    Ok(())
+}
+
+/// Stop running bridges whose server has since been given call policies.
+pub(crate) async fn stop_unenforceable_bridges(state: &AppState) {
+    let running: Vec<String> = state.bridges.lock().await.keys().cloned().collect();
+    for server_id in running {
+        let Ok(server) = load_server(state, &server_id).await else {
+            continue;
+        };
+        if let Err(e) = check_policies(&server) {
+            eprintln!("[Relay] Stopping bridge: {}", e);
+            if let Err(e) = stop(state, &server_id, false).await {
+                eprintln!("[Relay] {}", e);
+            }
+        }
+    }
+}
+
+/// Start the bridges that were running when the app last quit.
+pub async fn restore_bridges(state: &AppState, app: &AppHandle) {
+    let enabled = {
+        let db = state.db.lock().await;
+        sqlx::query_scalar::<_, String>("SELECT server_id FROM bridges WHERE enabled = 1")
+            .fetch_all(&*db)
+            .await
+            .unwrap_or_default()
+    };
+
+    for server_id in enabled {
+        if let Err(e) = start(state, app, &server_id, None, None, None).await {
+            eprintln!("[Relay] Failed to start bridge for {}: {}", server_id, e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_bridges(state: State<'_, AppState>) -> Result<Vec<BridgeStatus>, String> {
+    let saved = {
+        let db = state.db.lock().await;
+        sqlx::query_as::<_, Bridge>("SELECT * FROM bridges ORDER BY created_at")
+            .fetch_all(&*db)
+            .await
+            .map_err(|e| format!("Failed to fetch bridges: {}", e))?
+    };
+
+    let bridges = state.bridges.lock().await;
+    let mut statuses = Vec::with_capacity(saved.len());
+    for bridge in &saved {
+        statuses.push(match bridges.get(&bridge.server_id) {
+            Some(running) => BridgeStatus::running(running).await,
+            None => BridgeStatus::stopped(bridge),
+        });
+    }
+    Ok(statuses)
+}
+
+/// Serve a stdio server over Streamable HTTP at `/mcp`, authenticated with
+/// gateway client tokens. `lan` listens on all interfaces.
+#[tauri::command]
+pub async fn start_bridge(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    server_id: String,
+    mode: Option<BridgeMode>,
+    port: Option<u16>,
+    lan: Option<bool>,
+) -> Result<BridgeStatus, String> {
+    start(&state, &app, &server_id, mode, port, lan).await
+}
+
+#[tauri::command]
+pub async fn stop_bridge(state: State<'_, AppState>, server_id: String) -> Result<(), String> {
+    stop(&state, &server_id, false).await
+}
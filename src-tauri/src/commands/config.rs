@@ -1,9 +1,22 @@
 use tauri::State;
+use crate::commands::bridges::stop_unenforceable_bridges;
+use crate::commands::gateway::{client_token_for, ensure_http_gateway};
 use crate::commands::profiles::get_active_profile_id_from_db;
+use crate::commands::settings::read_setting;
 use crate::state::AppState;
 use crate::models::server::Server;
+use crate::models::toolset::Toolset;
+use crate::telemetry::TelemetryConfig;
+use crate::gateway::approval::DEFAULT_TIMEOUT_SECS;
+use crate::gateway::config::{GatewayConfig, GatewayServerConfig};
+use crate::gateway::namespace::{NamespaceConfig, NamespaceStrategy, DEFAULT_MAX_NAME_LENGTH};
+use crate::gateway::search::ToolMode;
+use crate::gateway::toolsets::ToolsetConfig;
 use crate::utils::paths::get_claude_config_path;
+use crate::utils::tokenizer::Tokenizer;
+use sqlx::SqlitePool;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use serde_json::{json, Value};
 
 #[tauri::command]
@@ -15,91 +28,169 @@ pub async fn get_config_path() -> Result<String, String> {
 
 use tauri::{AppHandle, Manager};
 
-#[tauri::command]
-pub async fn export_to_claude(app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
-    let db = state.db.lock().await;
-    let active_profile = get_active_profile_id_from_db(&*db).await?;
+/// Gateway configuration for the enabled servers of the active profile.
+pub(crate) async fn load_gateway_config(db: &SqlitePool) -> Result<GatewayConfig, String> {
+    let active_profile = get_active_profile_id_from_db(db).await?;
 
     let servers = sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE enabled = 1 AND profile_id = ?")
         .bind(&active_profile)
-        .fetch_all(&*db)
+        .fetch_all(db)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    // Prepare proper args and env for each server
-    let mut relay_servers = Vec::new();
-    for server in servers {
-        let args: Vec<String> = serde_json::from_str(&server.args)
-            .unwrap_or_default();
-        let env: HashMap<String, String> = serde_json::from_str(&server.env)
-            .unwrap_or_default();
+    let toolsets = sqlx::query_as::<_, Toolset>("SELECT * FROM toolsets ORDER BY name")
+        .fetch_all(db)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
 
-        let mut server_json = json!({
-            "id": server.id,
-            "name": server.name,
-            "command": server.command,
-            "args": args,
-        });
+    let tokenizer = read_setting(db, "tokenizer", Tokenizer::default().as_setting()).await;
+    let namespace = NamespaceConfig {
+        strategy: NamespaceStrategy::from_setting(&read_setting(db, "toolNamespaceStrategy", "prefix").await),
+        separator: read_setting(db, "toolNamespaceSeparator", "_").await,
+        max_length: read_setting(db, "toolNameMaxLength", &DEFAULT_MAX_NAME_LENGTH.to_string())
+            .await
+            .parse()
+            .unwrap_or(DEFAULT_MAX_NAME_LENGTH),
+    };
+    namespace.validate()?;
+    let approval_timeout_secs = read_setting(db, "approvalTimeoutSecs", &DEFAULT_TIMEOUT_SECS.to_string())
+        .await
+        .parse()
+        .ok();
+    let telemetry = TelemetryConfig {
+        otlp_endpoint: Some(read_setting(db, "traceOtlpEndpoint", "").await).filter(|url| !url.trim().is_empty()),
+        file: Some(read_setting(db, "traceOtlpFile", "").await).filter(|path| !path.trim().is_empty()),
+        ..Default::default()
+    };
 
-        if !env.is_empty() {
-            server_json["env"] = json!(env);
-        }
-        
-        relay_servers.push(server_json);
-    }
+    Ok(GatewayConfig {
+        servers: servers.iter().map(GatewayServerConfig::from_server).collect(),
+        tokenizer: Tokenizer::from_setting(&tokenizer),
+        namespace,
+        approval_timeout_secs,
+        telemetry: telemetry.is_enabled().then_some(telemetry),
+        toolsets: toolsets.iter().map(ToolsetConfig::from_toolset).collect(),
+    })
+}
 
-    let relay_config = json!({
-        "servers": relay_servers
-    });
+/// Write the gateway's `relay.json` for the enabled servers of the active
+/// profile and return its path.
+async fn write_relay_config(app: &AppHandle, db: &SqlitePool) -> Result<PathBuf, String> {
+    let relay_config = load_gateway_config(db).await?;
 
-    // 1. Write relay.json to AppData
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     if !app_data_dir.exists() {
         std::fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
     }
-    
+
     let relay_config_path = app_data_dir.join("relay.json");
-    
+
     std::fs::write(&relay_config_path, serde_json::to_string_pretty(&relay_config).unwrap())
         .map_err(|e| format!("Failed to write relay config: {}", e))?;
 
-    // 2. Determine Gateway Script Path (dist/gateway.js)
-    // In actual prod, we might need to copy this file to app_data_dir or find it in resources.
-    // For now, we point to the absolute path in the project dist folder.
-    let cwd = std::env::current_dir().unwrap(); // Likely src-tauri
-    
-    // Check cwd/dist/gateway.js
-    let mut gateway_script_path = cwd.join("dist").join("gateway.js");
-
-    // If not found, check parent (cwd/../dist/gateway.js)
-    if !gateway_script_path.exists() {
-        if let Some(parent) = cwd.parent() {
-            let parent_path = parent.join("dist").join("gateway.js");
-            if parent_path.exists() {
-                gateway_script_path = parent_path;
+    Ok(relay_config_path)
+}
+
+/// Push server changes to running gateways: rewrite `relay.json` if it has
+/// been exported (`relay --gateway` watches it), reload the HTTP gateway and
+/// stop bridges that can no longer serve their server. Runs in the
+/// background, since connecting servers can take a while.
+pub(crate) fn reload_gateways(app: &AppHandle) {
+    let app = app.clone();
+    tokio::spawn(async move {
+        let state: State<AppState> = app.state();
+        let db = state.db.lock().await.clone();
+
+        let exported = app
+            .path()
+            .app_data_dir()
+            .map(|dir| dir.join("relay.json").exists())
+            .unwrap_or(false);
+        if exported {
+            if let Err(e) = write_relay_config(&app, &db).await {
+                eprintln!("[Relay] Failed to update relay.json: {}", e);
             }
         }
+
+        let gateway = state
+            .http_gateway
+            .lock()
+            .await
+            .as_ref()
+            .map(|server| server.gateway.clone());
+        if let Some(gateway) = gateway {
+            match load_gateway_config(&db).await {
+                Ok(config) => {
+                    gateway.reload(config).await;
+                }
+                Err(e) => eprintln!("[Relay] Failed to reload HTTP gateway: {}", e),
+            }
+        }
+
+        stop_unenforceable_bridges(&state).await;
+    });
+}
+
+/// Client config entry that launches this binary in gateway mode.
+fn gateway_entry(relay_config_path: &Path, tool_mode: ToolMode) -> Result<Value, String> {
+    // AppImages run from a temporary mount; point clients at the image itself.
+    let executable = match std::env::var_os("APPIMAGE") {
+        Some(appimage) => PathBuf::from(appimage),
+        None => std::env::current_exe()
+            .map_err(|e| format!("Failed to locate the Relay executable: {}", e))?,
+    };
+
+    let mut args = vec!["--gateway"];
+    if tool_mode == ToolMode::Search {
+        args.extend(["--tool-mode", "search"]);
     }
 
-    if !gateway_script_path.exists() {
-        return Err(format!("Gateway script not found at {}. Please run 'npm run build:gateway'.", gateway_script_path.display()));
+    Ok(json!({
+        "command": executable.to_string_lossy(),
+        "args": args,
+        "env": {
+            "RELAY_CONFIG_PATH": relay_config_path.to_string_lossy()
+        }
+    }))
+}
+
+/// Client config entry pointing at the HTTP gateway, starting it if needed,
+/// with the bearer token of `client_name`.
+async fn gateway_url_entry(
+    app: &AppHandle,
+    state: &AppState,
+    client_name: &str,
+    tool_mode: ToolMode,
+) -> Result<Value, String> {
+    let mut url = ensure_http_gateway(state, app).await?;
+    if tool_mode == ToolMode::Search {
+        url.push_str("?tool_mode=search");
     }
+    let token = client_token_for(state, client_name).await?;
+
+    Ok(json!({
+        "type": "http",
+        "url": url,
+        "headers": {
+            "Authorization": format!("Bearer {}", token)
+        }
+    }))
+}
+
+#[tauri::command]
+pub async fn export_to_claude(app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let db = state.db.lock().await;
 
-    // 3. Write Claude Config with Single Gateway Entry
+    // 1. Write relay.json to AppData
+    let relay_config_path = write_relay_config(&app, &db).await?;
+
+    // 2. Write Claude Config with Single Gateway Entry
     let config_path = get_claude_config_path()
         .ok_or_else(|| "Could not determine Claude config path".to_string())?;
 
     let claude_config = json!({
         "mcpServers": {
-            "Relay Gateway": {
-                "command": "node",
-                "args": [
-                    gateway_script_path.to_string_lossy(),
-                ],
-                "env": {
-                    "RELAY_CONFIG_PATH": relay_config_path.to_string_lossy()
-                }
-            }
+            "Relay Gateway": gateway_entry(&relay_config_path, ToolMode::Full)?
         }
     });
 
@@ -161,73 +252,17 @@ pub async fn export_config_to_path(
     state: State<'_, AppState>,
     key: String,
     path: String,
+    mode: Option<String>,
+    client_name: Option<String>,
+    tool_mode: Option<ToolMode>,
 ) -> Result<String, String> {
-    let db = state.db.lock().await;
-    let active_profile = get_active_profile_id_from_db(&*db).await?;
-
-    // 1. Get enabled servers
-    let servers = sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE enabled = 1 AND profile_id = ?")
-        .bind(&active_profile)
-        .fetch_all(&*db)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
-
-    // 2. Prepare relay.json content (list of servers)
-    let mut relay_servers = Vec::new();
-    for server in servers {
-        let args: Vec<String> = serde_json::from_str(&server.args)
-            .unwrap_or_default();
-        let env: HashMap<String, String> = serde_json::from_str(&server.env)
-            .unwrap_or_default();
-
-        let mut server_json = json!({
-            "id": server.id,
-            "name": server.name,
-            "command": server.command,
-            "args": args,
-        });
-
-        if !env.is_empty() {
-            server_json["env"] = json!(env);
-        }
-        
-        relay_servers.push(server_json);
-    }
-
-    let relay_config = json!({
-        "servers": relay_servers
-    });
-
-    // 3. Write relay.json to persistent AppData
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-    if !app_data_dir.exists() {
-        std::fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
-    }
-    
-    let relay_config_path = app_data_dir.join("relay.json");
-    
-    std::fs::write(&relay_config_path, serde_json::to_string_pretty(&relay_config).unwrap())
-        .map_err(|e| format!("Failed to write relay config: {}", e))?;
-
-    // 4. Locate Gateway Script (dist/gateway.js)
-    let cwd = std::env::current_dir().unwrap();
-    let mut gateway_script_path = cwd.join("dist").join("gateway.js");
-
-    // Fallback: check parent dir if running from src-tauri
-    if !gateway_script_path.exists() {
-        if let Some(parent) = cwd.parent() {
-            let parent_path = parent.join("dist").join("gateway.js");
-            if parent_path.exists() {
-                gateway_script_path = parent_path;
-            }
-        }
-    }
-
-    if !gateway_script_path.exists() {
-        return Err(format!("Gateway script not found at {}. Please run 'npm run build:gateway'.", gateway_script_path.display()));
-    }
+    // 1. Write relay.json to persistent AppData
+    let relay_config_path = {
+        let db = state.db.lock().await;
+        write_relay_config(&app, &db).await?
+    };
 
-    // 5. Read Client Config File
+    // 2. Read Client Config File
     let path_buf = std::path::PathBuf::from(&path);
     
     if let Some(parent) = path_buf.parent() {
@@ -246,18 +281,18 @@ pub async fn export_config_to_path(
         json!({})
     };
 
-    // 6. Construct the 'Relay Gateway' Config
-    let gateway_config = json!({
-        "command": "node",
-        "args": [
-            gateway_script_path.to_string_lossy(),
-        ],
-        "env": {
-            "RELAY_CONFIG_PATH": relay_config_path.to_string_lossy()
-        }
-    });
+    // 3. Construct the 'Relay Gateway' Config: a command that launches the
+    // gateway, or a URL pointing at the app-hosted HTTP gateway. In search
+    // mode the client only sees the tool search meta-tools.
+    let tool_mode = tool_mode.unwrap_or_default();
+    let gateway_config = if mode.as_deref() == Some("url") {
+        let client_name = client_name.unwrap_or_else(|| key.clone());
+        gateway_url_entry(&app, &state, &client_name, tool_mode).await?
+    } else {
+        gateway_entry(&relay_config_path, tool_mode)?
+    };
 
-    // 7. Update the specific key in Client Config
+    // 4. Update the specific key in Client Config
     let parts: Vec<&str> = key.split('.').collect();
     let mut current = &mut config_json;
 
@@ -282,7 +317,7 @@ pub async fn export_config_to_path(
         return Err(format!("Target path '{}' is not an object", key));
     }
 
-    // 8. Write Client Config Back
+    // 5. Write Client Config Back
     let new_content = serde_json::to_string_pretty(&config_json)
         .map_err(|e| format!("Failed to serialize config: {}", e))?;
     
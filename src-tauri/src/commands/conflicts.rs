@@ -1,4 +1,6 @@
 use serde::Serialize;
+use crate::commands::gateway::inspection_gateway;
+use crate::gateway::ExposedTool;
 use crate::state::AppState;
 use tauri::State;
 
@@ -14,8 +16,17 @@ pub struct ConflictingServer {
     pub name: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct StaleToolOverride {
+    pub server_id: String,
+    pub server_name: String,
+    /// Upstream tool name the override targets.
+    pub tool_name: String,
+}
+
 /// Detect tool name conflicts across all enabled servers in the active profile.
-/// Compares the tool lists from each server and returns duplicates.
+/// Each server's tool list (after its tool filter) is read through the
+/// inspection gateway and duplicate names are returned.
 #[tauri::command]
 pub async fn detect_tool_conflicts(state: State<'_, AppState>) -> Result<Vec<ToolConflict>, String> {
     use crate::commands::profiles::get_active_profile_id_from_db;
@@ -33,24 +44,30 @@ pub async fn detect_tool_conflicts(state: State<'_, AppState>) -> Result<Vec<Too
 
     drop(db);
 
-    // Collect tool names per server by parsing the stored tool list
-    // (We can't call the actual MCP servers here as they may not be running,
-    //  so we use a simpler heuristic: check for servers with the same package/command)
+    let tools_by_server = inspection_gateway(&state).await?.upstream_tools().await;
+
     let mut tool_map: HashMap<String, Vec<ConflictingServer>> = HashMap::new();
 
     for server in &servers {
-        // Use marketplace_id or command as a proxy for tool identity
-        // This catches the most common case: the same server installed twice
-        let tool_key = server.marketplace_id.clone()
-            .unwrap_or_else(|| format!("{}:{}", server.command, server.args));
-
-        tool_map
-            .entry(tool_key)
-            .or_default()
-            .push(ConflictingServer {
-                id: server.id.clone(),
-                name: server.name.clone(),
-            });
+        let conflicting = || ConflictingServer {
+            id: server.id.clone(),
+            name: server.name.clone(),
+        };
+
+        match tools_by_server.get(&server.id) {
+            Some(tools) => {
+                for name in tools.iter().filter_map(|tool| tool.get("name")?.as_str()) {
+                    tool_map.entry(name.to_string()).or_default().push(conflicting());
+                }
+            }
+            None => {
+                // Server could not be reached: fall back to catching the same
+                // package installed twice.
+                let tool_key = server.marketplace_id.clone()
+                    .unwrap_or_else(|| format!("{}:{}", server.command, server.args));
+                tool_map.entry(tool_key).or_default().push(conflicting());
+            }
+        }
     }
 
     // Only keep entries with conflicts (more than 1 server)
@@ -65,3 +82,33 @@ pub async fn detect_tool_conflicts(state: State<'_, AppState>) -> Result<Vec<Too
 
     Ok(conflicts)
 }
+
+/// Find tool overrides in the active profile whose upstream tool no longer
+/// exists (renamed or removed by a server update).
+#[tauri::command]
+pub async fn detect_stale_tool_overrides(state: State<'_, AppState>) -> Result<Vec<StaleToolOverride>, String> {
+    let gateway = inspection_gateway(&state).await?;
+    let mut stale = Vec::new();
+    for (server_id, tool_name) in gateway.stale_overrides().await {
+        let server_name = gateway
+            .server_config(&server_id)
+            .await
+            .map(|server| server.name)
+            .unwrap_or_default();
+        stale.push(StaleToolOverride {
+            server_id,
+            server_name,
+            tool_name,
+        });
+    }
+
+    Ok(stale)
+}
+
+/// Tool names as the gateway exposes them for the active profile, after
+/// filters, overrides and namespacing, with the server and upstream tool each
+/// one routes to.
+#[tauri::command]
+pub async fn get_exposed_tool_names(state: State<'_, AppState>) -> Result<Vec<ExposedTool>, String> {
+    Ok(inspection_gateway(&state).await?.exposed_tool_names().await)
+}
@@ -1,4 +1,5 @@
 use crate::state::AppState;
+use crate::utils::redaction;
 use crate::utils::secrets::SecretManager;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -294,6 +295,12 @@ pub async fn test_server_connection(
             }
         }
     }
+    // Secrets typed into the form but not saved yet are masked too.
+    for key in &input.secrets {
+        if let Some(value) = env.get(key) {
+            redaction::register_secret(value);
+        }
+    }
 
     let missing_dependencies = collect_missing_dependencies(&input.command, &input.args).await;
     if !missing_dependencies.is_empty() {
@@ -348,7 +355,7 @@ pub async fn test_server_connection(
             if guard.len() >= 8 {
                 break;
             }
-            guard.push(line);
+            guard.push(redaction::redact(&line));
         }
     });
 
@@ -381,7 +388,7 @@ pub async fn test_server_connection(
     match result {
         Ok(response) => {
             if let Some(error) = response.get("error") {
-                let message = format!("Server returned MCP initialize error: {}", error);
+                let message = redaction::redact(&format!("Server returned MCP initialize error: {}", error));
                 return Ok(ConnectionTestResult {
                     success: false,
                     message: message.clone(),
@@ -0,0 +1,484 @@
+use crate::audit;
+use crate::commands::approvals::forward_approvals;
+use crate::commands::config::load_gateway_config;
+use crate::commands::settings::{read_setting, write_setting};
+use crate::gateway::control::ControlCommand;
+use crate::gateway::http::HttpGatewayServer;
+use crate::gateway::Gateway;
+use crate::proxy::{record_cache_hit, record_gateway_traffic, record_limit_event, record_policy_hit, record_transform};
+use crate::state::AppState;
+use crate::telemetry;
+use crate::utils::secrets::SecretManager;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::broadcast;
+
+const DEFAULT_HTTP_PORT: u16 = 7341;
+/// Keyring namespace for client bearer tokens (keyed by client id).
+const TOKEN_SECRET_ID: &str = "gateway";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HttpGatewayStatus {
+    pub running: bool,
+    pub port: u16,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct GatewayClient {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+}
+
+/// A newly issued client. The token is only ever returned once.
+#[derive(Debug, Clone, Serialize)]
+pub struct GatewayClientToken {
+    pub id: String,
+    pub name: String,
+    pub token: String,
+    pub created_at: String,
+}
+
+async fn configured_port(state: &AppState) -> u16 {
+    let db = state.db.lock().await;
+    read_setting(&db, "gatewayHttpPort", &DEFAULT_HTTP_PORT.to_string())
+        .await
+        .parse()
+        .unwrap_or(DEFAULT_HTTP_PORT)
+}
+
+/// Reload the token -> client map from the keyring.
+pub(crate) async fn load_client_tokens(state: &AppState) -> Result<(), String> {
+    let clients = {
+        let db = state.db.lock().await;
+        sqlx::query_as::<_, GatewayClient>("SELECT * FROM gateway_clients")
+            .fetch_all(&*db)
+            .await
+            .map_err(|e| format!("Failed to fetch gateway clients: {}", e))?
+    };
+
+    let mut tokens = state.gateway_tokens.write().await;
+    tokens.clear();
+    for client in clients {
+        match SecretManager::get_secret(TOKEN_SECRET_ID, &client.id) {
+            Ok(token) => {
+                tokens.insert(token, client.name);
+            }
+            Err(e) => eprintln!("[Relay] Missing token for gateway client {}: {}", client.name, e),
+        }
+    }
+
+    Ok(())
+}
+
+async fn start_gateway(
+    state: &AppState,
+    app: &AppHandle,
+    port: Option<u16>,
+) -> Result<HttpGatewayStatus, String> {
+    let port = match port {
+        Some(port) => port,
+        None => configured_port(state).await,
+    };
+    let config = {
+        let db = state.db.lock().await;
+        load_gateway_config(&db).await?
+    };
+    load_client_tokens(state).await?;
+
+    let mut slot = state.http_gateway.lock().await;
+    if let Some(existing) = slot.take() {
+        existing.stop().await;
+    }
+
+    let telemetry = config.telemetry.clone().filter(|t| t.is_enabled());
+    let gateway = Gateway::start(config).await;
+    if let Some(telemetry) = telemetry {
+        telemetry::spawn_exporter(telemetry, gateway.subscribe_spans());
+    }
+    forward_policy_hits(&gateway, app);
+    forward_transforms(&gateway, app);
+    forward_cache_hits(&gateway, app);
+    forward_limit_events(&gateway, app);
+    forward_approvals(&gateway, app);
+    forward_tool_calls(&gateway, state);
+    forward_request_samples(&gateway, state);
+    forward_traffic(&gateway, app);
+    let server = match HttpGatewayServer::start(gateway.clone(), state.gateway_tokens.clone(), port).await {
+        Ok(server) => server,
+        Err(e) => {
+            gateway.shutdown().await;
+            return Err(e);
+        }
+    };
+
+    let status = HttpGatewayStatus {
+        running: true,
+        port: server.port,
+        url: Some(server.url()),
+    };
+    *slot = Some(server);
+    drop(slot);
+
+    // The HTTP gateway now answers tool list questions.
+    if let Some(inspection) = state.inspection_gateway.lock().await.take() {
+        inspection.shutdown().await;
+    }
+
+    let db = state.db.lock().await;
+    write_setting(&db, "gatewayHttpEnabled", "true").await?;
+    write_setting(&db, "gatewayHttpPort", &status.port.to_string()).await?;
+
+    Ok(status)
+}
+
+/// Count the gateway's response policy hits in the app's usage stats.
+fn forward_policy_hits(gateway: &Gateway, app: &AppHandle) {
+    let mut hits = gateway.subscribe_policy_hits();
+    let app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            match hits.recv().await {
+                Ok(hit) => {
+                    let state: State<AppState> = app.state();
+                    record_policy_hit(&state, &app, &hit).await;
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Count tool results rewritten by transforms in the app's usage stats.
+fn forward_transforms(gateway: &Gateway, app: &AppHandle) {
+    let mut reports = gateway.subscribe_transforms();
+    let app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            match reports.recv().await {
+                Ok(report) => {
+                    let state: State<AppState> = app.state();
+                    record_transform(&state, &app, &report).await;
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Count calls answered from the gateway's response cache in the usage stats.
+fn forward_cache_hits(gateway: &Gateway, app: &AppHandle) {
+    let mut hits = gateway.subscribe_cache_hits();
+    let app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            match hits.recv().await {
+                Ok(hit) => {
+                    let state: State<AppState> = app.state();
+                    record_cache_hit(&state, &app, &hit).await;
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Count calls queued or refused by rate and concurrency limits.
+fn forward_limit_events(gateway: &Gateway, app: &AppHandle) {
+    let mut events = gateway.subscribe_limit_events();
+    let app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    let state: State<AppState> = app.state();
+                    record_limit_event(&state, &app, &event).await;
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Append every call through the gateway to the audit log.
+fn forward_tool_calls(gateway: &Gateway, state: &AppState) {
+    let mut calls = gateway.subscribe_tool_calls();
+    let db = state.db.clone();
+    tokio::spawn(async move {
+        loop {
+            match calls.recv().await {
+                Ok(call) => {
+                    let pool = db.lock().await.clone();
+                    audit::record(&pool, &call).await;
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("[Relay] Audit log missed {} tool calls", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Count the gateway's upstream traffic in the app's context usage, its
+/// history and the per-tool breakdown.
+fn forward_traffic(gateway: &Gateway, app: &AppHandle) {
+    let mut samples = gateway.subscribe_traffic();
+    let app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            match samples.recv().await {
+                Ok(sample) => {
+                    let state: State<AppState> = app.state();
+                    record_gateway_traffic(&state, &app, &sample).await;
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Feed upstream request latency into the app's metrics.
+fn forward_request_samples(gateway: &Gateway, state: &AppState) {
+    let mut samples = gateway.subscribe_request_samples();
+    let metrics = state.metrics.clone();
+    tokio::spawn(async move {
+        loop {
+            match samples.recv().await {
+                Ok(sample) => metrics.lock().await.record(sample),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// URL of the running HTTP gateway, starting it on the configured port first
+/// if necessary.
+pub(crate) async fn ensure_http_gateway(state: &AppState, app: &AppHandle) -> Result<String, String> {
+    if let Some(server) = state.http_gateway.lock().await.as_ref() {
+        return Ok(server.url());
+    }
+
+    start_gateway(state, app, None)
+        .await?
+        .url
+        .ok_or_else(|| "HTTP gateway did not start".to_string())
+}
+
+/// Gateway for reading the active profile's tool lists: the running HTTP
+/// gateway, or else a background one that stays connected between calls and
+/// is reloaded with the current config, so unchanged servers are not
+/// restarted.
+pub(crate) async fn inspection_gateway(state: &AppState) -> Result<Arc<Gateway>, String> {
+    if let Some(server) = state.http_gateway.lock().await.as_ref() {
+        return Ok(server.gateway.clone());
+    }
+
+    let config = {
+        let db = state.db.lock().await;
+        load_gateway_config(&db).await?
+    };
+    let mut slot = state.inspection_gateway.lock().await;
+    match slot.as_ref() {
+        Some(gateway) => {
+            gateway.reload(config).await;
+            Ok(gateway.clone())
+        }
+        None => {
+            let gateway = Gateway::start(config).await;
+            *slot = Some(gateway.clone());
+            Ok(gateway)
+        }
+    }
+}
+
+/// Register a client and issue its bearer token.
+async fn create_client_token(state: &AppState, name: &str) -> Result<GatewayClientToken, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Client name is required".to_string());
+    }
+
+    let client = GatewayClientToken {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        token: format!(
+            "relay_{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        ),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    SecretManager::set_secret(TOKEN_SECRET_ID, &client.id, &client.token)?;
+
+    {
+        let db = state.db.lock().await;
+        sqlx::query("INSERT INTO gateway_clients (id, name, created_at) VALUES (?, ?, ?)")
+            .bind(&client.id)
+            .bind(&client.name)
+            .bind(&client.created_at)
+            .execute(&*db)
+            .await
+            .map_err(|e| format!("Failed to save gateway client: {}", e))?;
+    }
+
+    state
+        .gateway_tokens
+        .write()
+        .await
+        .insert(client.token.clone(), client.name.clone());
+
+    Ok(client)
+}
+
+/// Bearer token for `name`, reusing the client's existing token so exporting
+/// the same client again doesn't pile up valid tokens. A client whose token
+/// is missing from the keyring is replaced.
+pub(crate) async fn client_token_for(state: &AppState, name: &str) -> Result<String, String> {
+    let existing = {
+        let db = state.db.lock().await;
+        sqlx::query_as::<_, GatewayClient>(
+            "SELECT * FROM gateway_clients WHERE name = ? ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(name.trim())
+        .fetch_optional(&*db)
+        .await
+        .map_err(|e| format!("Failed to fetch gateway clients: {}", e))?
+    };
+
+    if let Some(client) = existing {
+        match SecretManager::get_secret(TOKEN_SECRET_ID, &client.id) {
+            Ok(token) => {
+                state
+                    .gateway_tokens
+                    .write()
+                    .await
+                    .insert(token.clone(), client.name);
+                return Ok(token);
+            }
+            Err(_) => revoke_client(state, &client.id).await?,
+        }
+    }
+
+    Ok(create_client_token(state, name).await?.token)
+}
+
+/// Start the HTTP gateway on launch if it was running when the app last quit.
+pub async fn restore_http_gateway(state: &AppState, app: &AppHandle) {
+    let enabled = {
+        let db = state.db.lock().await;
+        read_setting(&db, "gatewayHttpEnabled", "false").await == "true"
+    };
+
+    if enabled {
+        if let Err(e) = start_gateway(state, app, None).await {
+            eprintln!("[Relay] Failed to start HTTP gateway: {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn start_http_gateway(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    port: Option<u16>,
+) -> Result<HttpGatewayStatus, String> {
+    start_gateway(&state, &app, port).await
+}
+
+#[tauri::command]
+pub async fn stop_http_gateway(state: State<'_, AppState>) -> Result<(), String> {
+    if let Some(server) = state.http_gateway.lock().await.take() {
+        server.stop().await;
+    }
+
+    let db = state.db.lock().await;
+    write_setting(&db, "gatewayHttpEnabled", "false").await
+}
+
+/// Drop cached tool results for a server (or all servers), optionally only
+/// for one upstream tool. Running `relay --gateway` instances are told to do
+/// the same. Returns how many results the HTTP gateway dropped.
+#[tauri::command]
+pub async fn clear_response_cache(
+    state: State<'_, AppState>,
+    server_id: Option<String>,
+    tool: Option<String>,
+) -> Result<usize, String> {
+    for session in state.gateway_sessions.lock().await.values() {
+        let _ = session.commands.send(ControlCommand::ClearCache {
+            server_id: server_id.clone(),
+            tool: tool.clone(),
+        });
+    }
+
+    match state.http_gateway.lock().await.as_ref() {
+        Some(server) => Ok(server.gateway.clear_cache(server_id.as_deref(), tool.as_deref()).await),
+        None => Ok(0),
+    }
+}
+
+#[tauri::command]
+pub async fn get_http_gateway_status(state: State<'_, AppState>) -> Result<HttpGatewayStatus, String> {
+    if let Some(server) = state.http_gateway.lock().await.as_ref() {
+        return Ok(HttpGatewayStatus {
+            running: true,
+            port: server.port,
+            url: Some(server.url()),
+        });
+    }
+
+    Ok(HttpGatewayStatus {
+        running: false,
+        port: configured_port(&state).await,
+        url: None,
+    })
+}
+
+#[tauri::command]
+pub async fn list_gateway_clients(state: State<'_, AppState>) -> Result<Vec<GatewayClient>, String> {
+    let db = state.db.lock().await;
+
+    sqlx::query_as::<_, GatewayClient>("SELECT * FROM gateway_clients ORDER BY created_at")
+        .fetch_all(&*db)
+        .await
+        .map_err(|e| format!("Failed to fetch gateway clients: {}", e))
+}
+
+#[tauri::command]
+pub async fn create_gateway_client(
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<GatewayClientToken, String> {
+    create_client_token(&state, &name).await
+}
+
+async fn revoke_client(state: &AppState, client_id: &str) -> Result<(), String> {
+    if let Ok(token) = SecretManager::get_secret(TOKEN_SECRET_ID, client_id) {
+        state.gateway_tokens.write().await.remove(&token);
+        let _ = SecretManager::delete_secret(TOKEN_SECRET_ID, client_id);
+    }
+
+    let db = state.db.lock().await;
+    sqlx::query("DELETE FROM gateway_clients WHERE id = ?")
+        .bind(client_id)
+        .execute(&*db)
+        .await
+        .map_err(|e| format!("Failed to revoke gateway client: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn revoke_gateway_client(state: State<'_, AppState>, client_id: String) -> Result<(), String> {
+    revoke_client(&state, &client_id).await
+}
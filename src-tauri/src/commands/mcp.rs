@@ -6,9 +6,12 @@ use std::collections::HashMap;
 use std::process::Stdio;
 use tauri::{AppHandle, State};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
-use tokio::process::{ChildStdin, ChildStdout, Command};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
 use tokio::time::{timeout, Duration};
 
+/// Client name the inspector's calls are audited under.
+const INSPECTOR_CLIENT: &str = "inspector";
+
 async fn send_json_message(
     stdin: &mut ChildStdin,
     state: &AppState,
@@ -17,7 +20,7 @@ async fn send_json_message(
     payload: Value,
 ) -> Result<(), String> {
     let line = payload.to_string();
-    record_traffic(state, app, server_id, TrafficDirection::Outbound, &line).await;
+    record_traffic(state, app, server_id, Some(INSPECTOR_CLIENT), TrafficDirection::Outbound, &line).await;
     stdin
         .write_all(format!("{}\n", line).as_bytes())
         .await
@@ -44,7 +47,7 @@ async fn read_json_response(
                     continue;
                 }
 
-                record_traffic(state, app, server_id, TrafficDirection::Inbound, &line).await;
+                record_traffic(state, app, server_id, None, TrafficDirection::Inbound, &line).await;
 
                 if let Ok(json_value) = serde_json::from_str::<Value>(&line) {
                     if json_value.get("jsonrpc").is_some() {
@@ -88,88 +91,151 @@ fn build_command(command: &str) -> Command {
     }
 }
 
-#[tauri::command]
-pub async fn list_server_tools(
-    state: State<'_, AppState>,
-    app: AppHandle,
-    server_id: String,
-) -> Result<Value, String> {
+pub(crate) async fn load_server(state: &AppState, server_id: &str) -> Result<Server, String> {
     let db = state.db.lock().await;
 
-    let server = sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE id = ?")
-        .bind(&server_id)
+    sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE id = ?")
+        .bind(server_id)
         .fetch_one(&*db)
         .await
-        .map_err(|e| format!("Server not found: {}", e))?;
-
-    let args: Vec<String> = serde_json::from_str(&server.args).unwrap_or_default();
-    let envs: HashMap<String, String> = serde_json::from_str(&server.env).unwrap_or_default();
-
-    let mut command_builder = build_command(&server.command);
-    let mut child = command_builder
-        .args(args)
-        .envs(envs)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to spawn server: {}", e))?;
-
-    let mut stdin = child.stdin.take().ok_or("Failed to open stdin")?;
-    let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
-    let mut reader = BufReader::new(stdout).lines();
-
-    let init_request = json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "initialize",
-        "params": {
+        .map_err(|e| format!("Server not found: {}", e))
+}
+
+/// A short-lived, initialized MCP connection to a server, used by the inspector
+/// and other tooling that needs to talk to a server outside of the gateway.
+/// All traffic is recorded through `record_traffic` under the server's id.
+pub(crate) struct InspectorSession {
+    child: Child,
+    stdin: ChildStdin,
+    reader: Lines<BufReader<ChildStdout>>,
+    server_id: String,
+    next_id: u64,
+}
+
+impl InspectorSession {
+    pub(crate) async fn connect(
+        state: &AppState,
+        app: &AppHandle,
+        server: &Server,
+    ) -> Result<Self, String> {
+        let args: Vec<String> = serde_json::from_str(&server.args).unwrap_or_default();
+        let envs: HashMap<String, String> =
+            serde_json::from_str(&server.env).unwrap_or_default();
+
+        let mut command_builder = build_command(&server.command);
+        let mut child = command_builder
+            .args(args)
+            .envs(envs)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to spawn server: {}", e))?;
+
+        let stdin = child.stdin.take().ok_or("Failed to open stdin")?;
+        let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
+
+        let mut session = Self {
+            child,
+            stdin,
+            reader: BufReader::new(stdout).lines(),
+            server_id: server.id.clone(),
+            next_id: 1,
+        };
+
+        let init_params = json!({
             "protocolVersion": "2024-11-05",
             "capabilities": {},
             "clientInfo": {
                 "name": "Relay-Inspector",
                 "version": "1.0.0"
             }
+        });
+        let init_response = session
+            .request(state, app, "initialize", init_params, 15)
+            .await?;
+        if let Some(error) = init_response.get("error") {
+            return Err(format!("Server returned initialize error: {}", error));
         }
-    });
 
-    send_json_message(&mut stdin, &state, &app, &server_id, init_request).await?;
-    let _ = read_json_response(
-        &mut reader,
-        15,
-        "initialization",
-        &state,
-        &app,
-        &server_id,
-    )
-    .await?;
-
-    let initialized_notification = json!({
-        "jsonrpc": "2.0",
-        "method": "notifications/initialized"
-    });
-    send_json_message(
-        &mut stdin,
-        &state,
-        &app,
-        &server_id,
-        initialized_notification,
-    )
-    .await?;
-
-    let list_tools_request = json!({
-        "jsonrpc": "2.0",
-        "id": 2,
-        "method": "tools/list",
-        "params": {}
-    });
-    send_json_message(&mut stdin, &state, &app, &server_id, list_tools_request).await?;
+        let initialized_notification = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/initialized"
+        });
+        send_json_message(
+            &mut session.stdin,
+            state,
+            app,
+            &session.server_id,
+            initialized_notification,
+        )
+        .await?;
+
+        Ok(session)
+    }
+
+    /// Send a request and wait for the response carrying the same id.
+    /// Returns the full JSON-RPC response, including any `error` member.
+    pub(crate) async fn request(
+        &mut self,
+        state: &AppState,
+        app: &AppHandle,
+        method: &str,
+        params: Value,
+        timeout_secs: u64,
+    ) -> Result<Value, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        });
+        send_json_message(&mut self.stdin, state, app, &self.server_id, request).await?;
+
+        // Skip server notifications and anything not addressed to this request.
+        for _attempt in 0..20 {
+            let response = read_json_response(
+                &mut self.reader,
+                timeout_secs,
+                method,
+                state,
+                app,
+                &self.server_id,
+            )
+            .await?;
+
+            if response.get("id").and_then(Value::as_u64) == Some(id) {
+                return Ok(response);
+            }
+        }
 
-    let response_json =
-        read_json_response(&mut reader, 10, "tools/list", &state, &app, &server_id).await?;
-    let _ = child.kill().await;
+        Err(format!("No response with id {} received during {}", id, method))
+    }
+
+    pub(crate) async fn close(mut self) {
+        let _ = self.child.kill().await;
+    }
+}
+
+#[tauri::command]
+pub async fn list_server_tools(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    server_id: String,
+) -> Result<Value, String> {
+    let server = load_server(&state, &server_id).await?;
 
-    Ok(response_json
+    let mut session = InspectorSession::connect(&state, &app, &server).await?;
+    let response_json = session
+        .request(&state, &app, "tools/list", json!({}), 10)
+        .await;
+    session.close().await;
+
+    Ok(response_json?
         .get("result")
         .cloned()
         .unwrap_or(json!({ "tools": [] })))
@@ -183,85 +249,19 @@ pub async fn call_server_tool(
     tool_name: String,
     arguments: Value,
 ) -> Result<Value, String> {
-    let db = state.db.lock().await;
+    let server = load_server(&state, &server_id).await?;
 
-    let server = sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE id = ?")
-        .bind(&server_id)
-        .fetch_one(&*db)
-        .await
-        .map_err(|e| format!("Server not found: {}", e))?;
-
-    let args: Vec<String> = serde_json::from_str(&server.args).unwrap_or_default();
-    let envs: HashMap<String, String> = serde_json::from_str(&server.env).unwrap_or_default();
-
-    let mut command_builder = build_command(&server.command);
-    let mut child = command_builder
-        .args(args)
-        .envs(envs)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to spawn server: {}", e))?;
-
-    let mut stdin = child.stdin.take().ok_or("Failed to open stdin")?;
-    let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
-    let mut reader = BufReader::new(stdout).lines();
-
-    let init_request = json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "initialize",
-        "params": {
-            "protocolVersion": "2024-11-05",
-            "capabilities": {},
-            "clientInfo": {
-                "name": "Relay-Inspector",
-                "version": "1.0.0"
-            }
-        }
-    });
-
-    send_json_message(&mut stdin, &state, &app, &server_id, init_request).await?;
-    let _ = read_json_response(
-        &mut reader,
-        15,
-        "initialization",
-        &state,
-        &app,
-        &server_id,
-    )
-    .await?;
-
-    let initialized_notification = json!({
-        "jsonrpc": "2.0",
-        "method": "notifications/initialized"
+    let mut session = InspectorSession::connect(&state, &app, &server).await?;
+    let params = json!({
+        "name": tool_name,
+        "arguments": arguments
     });
-    send_json_message(
-        &mut stdin,
-        &state,
-        &app,
-        &server_id,
-        initialized_notification,
-    )
-    .await?;
-
-    let call_tool_request = json!({
-        "jsonrpc": "2.0",
-        "id": 2,
-        "method": "tools/call",
-        "params": {
-            "name": tool_name,
-            "arguments": arguments
-        }
-    });
-    send_json_message(&mut stdin, &state, &app, &server_id, call_tool_request).await?;
-
-    let response_json =
-        read_json_response(&mut reader, 30, "tools/call", &state, &app, &server_id).await?;
-    let _ = child.kill().await;
+    let response_json = session
+        .request(&state, &app, "tools/call", params, 30)
+        .await;
+    session.close().await;
 
-    Ok(response_json
+    Ok(response_json?
         .get("result")
         .cloned()
         .unwrap_or(json!({ "content": [] })))
@@ -0,0 +1,120 @@
+use crate::commands::settings::{read_setting, write_setting};
+use crate::metrics::{self, MetricsEndpoint, MetricsSnapshot, MetricsSources};
+use crate::state::AppState;
+use serde::Serialize;
+use tauri::State;
+
+const DEFAULT_METRICS_PORT: u16 = 9464;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsEndpointStatus {
+    pub running: bool,
+    pub port: u16,
+    pub url: Option<String>,
+}
+
+async fn configured_port(state: &AppState) -> u16 {
+    let db = state.db.lock().await;
+    read_setting(&db, "metricsEndpointPort", &DEFAULT_METRICS_PORT.to_string())
+        .await
+        .parse()
+        .unwrap_or(DEFAULT_METRICS_PORT)
+}
+
+async fn start_endpoint(state: &AppState, port: Option<u16>) -> Result<MetricsEndpointStatus, String> {
+    let port = match port {
+        Some(port) => port,
+        None => configured_port(state).await,
+    };
+
+    let mut slot = state.metrics_endpoint.lock().await;
+    if let Some(existing) = slot.take() {
+        existing.stop().await;
+    }
+
+    let sources = MetricsSources {
+        registry: state.metrics.clone(),
+        usage: state.context_usage.clone(),
+        db: state.db.clone(),
+    };
+    let endpoint = MetricsEndpoint::start(sources, port).await?;
+    let status = MetricsEndpointStatus {
+        running: true,
+        port: endpoint.port,
+        url: Some(endpoint.url()),
+    };
+    *slot = Some(endpoint);
+    drop(slot);
+
+    let db = state.db.lock().await;
+    write_setting(&db, "metricsEndpointEnabled", "true").await?;
+    write_setting(&db, "metricsEndpointPort", &status.port.to_string()).await?;
+
+    Ok(status)
+}
+
+/// Start the Prometheus endpoint on launch if it was running when the app
+/// last quit.
+pub async fn restore_metrics_endpoint(state: &AppState) {
+    let enabled = {
+        let db = state.db.lock().await;
+        read_setting(&db, "metricsEndpointEnabled", "false").await == "true"
+    };
+
+    if enabled {
+        if let Err(e) = start_endpoint(state, None).await {
+            eprintln!("[Relay] Failed to start metrics endpoint: {}", e);
+        }
+    }
+}
+
+/// Latency percentiles and error rates over the last `window_secs` (five
+/// minutes by default, at most an hour), per server and per method/tool.
+#[tauri::command]
+pub async fn get_request_metrics(
+    state: State<'_, AppState>,
+    window_secs: Option<u64>,
+    server_id: Option<String>,
+) -> Result<MetricsSnapshot, String> {
+    let registry = state.metrics.lock().await;
+    Ok(registry.snapshot(
+        window_secs.unwrap_or(metrics::DEFAULT_WINDOW_SECS),
+        server_id.as_deref(),
+    ))
+}
+
+/// Serve metrics in Prometheus text format on localhost.
+#[tauri::command]
+pub async fn start_metrics_endpoint(
+    state: State<'_, AppState>,
+    port: Option<u16>,
+) -> Result<MetricsEndpointStatus, String> {
+    start_endpoint(&state, port).await
+}
+
+#[tauri::command]
+pub async fn stop_metrics_endpoint(state: State<'_, AppState>) -> Result<(), String> {
+    if let Some(endpoint) = state.metrics_endpoint.lock().await.take() {
+        endpoint.stop().await;
+    }
+
+    let db = state.db.lock().await;
+    write_setting(&db, "metricsEndpointEnabled", "false").await
+}
+
+#[tauri::command]
+pub async fn get_metrics_endpoint_status(state: State<'_, AppState>) -> Result<MetricsEndpointStatus, String> {
+    if let Some(endpoint) = state.metrics_endpoint.lock().await.as_ref() {
+        return Ok(MetricsEndpointStatus {
+            running: true,
+            port: endpoint.port,
+            url: Some(endpoint.url()),
+        });
+    }
+
+    Ok(MetricsEndpointStatus {
+        running: false,
+        port: configured_port(&state).await,
+        url: None,
+    })
+}
@@ -1,9 +1,19 @@
+pub mod approvals;
+pub mod audit;
+pub mod bridges;
 pub mod config;
 pub mod conflicts;
 pub mod diagnostics;
+pub mod gateway;
 pub mod marketplace;
 pub mod mcp;
+pub mod metrics;
 pub mod profiles;
+pub mod regression;
 pub mod servers;
+pub mod sessions;
 pub mod settings;
+pub mod toolsets;
+pub mod traffic;
 pub mod updates;
+pub mod usage;
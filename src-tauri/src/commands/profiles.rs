@@ -137,6 +137,7 @@ pub async fn set_active_profile(
         }
     }
 
-    super::servers::sync_servers(state, app).await?;
+    super::servers::sync_servers(state, app.clone()).await?;
+    super::config::reload_gateways(&app);
     Ok(profile_id)
 }
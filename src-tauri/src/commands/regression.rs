@@ -0,0 +1,345 @@
+use crate::commands::mcp::{load_server, InspectorSession};
+use crate::proxy::captured_requests;
+use crate::state::AppState;
+use crate::utils::redaction;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegressionReplayInput {
+    /// Server whose captured traffic should be replayed. Ignored when
+    /// `requests` is provided.
+    pub source_server_id: Option<String>,
+    /// Explicit JSON-RPC requests to replay instead of captured traffic.
+    pub requests: Option<Vec<Value>>,
+    pub baseline_server_id: String,
+    pub candidate_server_id: String,
+    /// Dotted paths to skip when diffing, e.g. `result._meta` or
+    /// `**.timestamp`. `*` matches one segment, `**` any number.
+    #[serde(default)]
+    pub ignore_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub baseline: Option<Value>,
+    pub candidate: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolSchemaChange {
+    pub tool: String,
+    pub changes: Vec<JsonChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseDiff {
+    pub index: usize,
+    pub method: String,
+    pub params: Value,
+    pub changes: Vec<JsonChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegressionReport {
+    pub baseline_server_id: String,
+    pub candidate_server_id: String,
+    pub replayed: usize,
+    pub identical: usize,
+    pub tools_added: Vec<String>,
+    pub tools_removed: Vec<String>,
+    pub schema_changes: Vec<ToolSchemaChange>,
+    pub response_diffs: Vec<ResponseDiff>,
+}
+
+fn join_path(base: &str, segment: &str) -> String {
+    if base.is_empty() {
+        segment.to_string()
+    } else {
+        format!("{}.{}", base, segment)
+    }
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => true, // pattern is a prefix of the path: ignore the whole subtree
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((head, rest)) => match path.split_first() {
+            Some((segment, path_rest)) => {
+                (*head == "*" || head == segment) && segments_match(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+fn is_ignored(path: &str, ignore_paths: &[String]) -> bool {
+    let path_segments: Vec<&str> = path.split('.').collect();
+    ignore_paths.iter().any(|pattern| {
+        let pattern_segments: Vec<&str> = pattern.split('.').collect();
+        segments_match(&pattern_segments, &path_segments)
+    })
+}
+
+/// Strings holding serialized JSON (typical for MCP text content) are diffed
+/// structurally so that ignore paths can reach inside them.
+fn embedded_json(value: &Value) -> Option<Value> {
+    let text = value.as_str()?.trim();
+    if !(text.starts_with('{') || text.starts_with('[')) {
+        return None;
+    }
+    serde_json::from_str(text).ok()
+}
+
+fn diff_values(
+    path: &str,
+    baseline: &Value,
+    candidate: &Value,
+    ignore_paths: &[String],
+    changes: &mut Vec<JsonChange>,
+) {
+    if !path.is_empty() && is_ignored(path, ignore_paths) {
+        return;
+    }
+
+    match (baseline, candidate) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, a_value) in a {
+                let child = join_path(path, key);
+                match b.get(key) {
+                    Some(b_value) => diff_values(&child, a_value, b_value, ignore_paths, changes),
+                    None if !is_ignored(&child, ignore_paths) => changes.push(JsonChange {
+                        path: child,
+                        kind: ChangeKind::Removed,
+                        baseline: Some(a_value.clone()),
+                        candidate: None,
+                    }),
+                    None => {}
+                }
+            }
+            for (key, b_value) in b {
+                let child = join_path(path, key);
+                if !a.contains_key(key) && !is_ignored(&child, ignore_paths) {
+                    changes.push(JsonChange {
+                        path: child,
+                        kind: ChangeKind::Added,
+                        baseline: None,
+                        candidate: Some(b_value.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for index in 0..a.len().max(b.len()) {
+                let child = join_path(path, &index.to_string());
+                match (a.get(index), b.get(index)) {
+                    (Some(a_value), Some(b_value)) => {
+                        diff_values(&child, a_value, b_value, ignore_paths, changes)
+                    }
+                    (Some(a_value), None) if !is_ignored(&child, ignore_paths) => {
+                        changes.push(JsonChange {
+                            path: child,
+                            kind: ChangeKind::Removed,
+                            baseline: Some(a_value.clone()),
+                            candidate: None,
+                        })
+                    }
+                    (None, Some(b_value)) if !is_ignored(&child, ignore_paths) => {
+                        changes.push(JsonChange {
+                            path: child,
+                            kind: ChangeKind::Added,
+                            baseline: None,
+                            candidate: Some(b_value.clone()),
+                        })
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ if baseline == candidate => {}
+        _ => {
+            if let (Some(a), Some(b)) = (embedded_json(baseline), embedded_json(candidate)) {
+                diff_values(path, &a, &b, ignore_paths, changes);
+                return;
+            }
+            changes.push(JsonChange {
+                path: path.to_string(),
+                kind: ChangeKind::Changed,
+                baseline: Some(baseline.clone()),
+                candidate: Some(candidate.clone()),
+            });
+        }
+    }
+}
+
+/// Everything in a response except the envelope (`jsonrpc`, `id`).
+fn response_body(response: &Value) -> Value {
+    let mut body = response.clone();
+    if let Some(obj) = body.as_object_mut() {
+        obj.remove("jsonrpc");
+        obj.remove("id");
+    }
+    body
+}
+
+fn tools_by_name(tools_response: &Value) -> BTreeMap<String, Value> {
+    tools_response
+        .pointer("/result/tools")
+        .and_then(Value::as_array)
+        .map(|tools| {
+            tools
+                .iter()
+                .filter_map(|tool| {
+                    let name = tool.get("name")?.as_str()?.to_string();
+                    Some((name, tool.clone()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+struct ReplayResult {
+    tools: Value,
+    responses: Vec<Result<Value, String>>,
+}
+
+async fn replay(
+    state: &AppState,
+    app: &AppHandle,
+    server_id: &str,
+    requests: &[Value],
+) -> Result<ReplayResult, String> {
+    let server = load_server(state, server_id).await?;
+    let mut session = InspectorSession::connect(state, app, &server).await?;
+
+    let tools = match session.request(state, app, "tools/list", json!({}), 10).await {
+        Ok(tools) => tools,
+        Err(e) => {
+            session.close().await;
+            return Err(format!("tools/list failed on {}: {}", server.name, e));
+        }
+    };
+
+    let mut responses = Vec::with_capacity(requests.len());
+    for request in requests {
+        let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = request.get("params").cloned().unwrap_or(json!({}));
+        responses.push(session.request(state, app, method, params, 30).await);
+    }
+
+    session.close().await;
+    Ok(ReplayResult { tools, responses })
+}
+
+fn outcome_value(outcome: &Result<Value, String>) -> Value {
+    match outcome {
+        Ok(response) => response_body(response),
+        Err(message) => json!({ "transportError": message }),
+    }
+}
+
+/// Replay a set of requests against two servers (typically the old and new
+/// build of the same server) and report how their tools and responses differ.
+#[tauri::command]
+pub async fn replay_regression(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    input: RegressionReplayInput,
+) -> Result<RegressionReport, String> {
+    let requests = match (&input.requests, &input.source_server_id) {
+        (Some(requests), _) => requests.clone(),
+        (None, Some(source)) => captured_requests(&state, source).await,
+        (None, None) => return Err("Provide either captured traffic or explicit requests".to_string()),
+    };
+
+    let requests: Vec<Value> = requests
+        .into_iter()
+        .filter(|r| r.get("method").and_then(Value::as_str).is_some())
+        .collect();
+
+    // `requests` is collected before replaying: replay traffic is itself
+    // captured and must not feed back into this run.
+    let baseline = replay(&state, &app, &input.baseline_server_id, &requests).await?;
+    let candidate = replay(&state, &app, &input.candidate_server_id, &requests).await?;
+
+    let baseline_tools = tools_by_name(&baseline.tools);
+    let candidate_tools = tools_by_name(&candidate.tools);
+
+    let tools_added = candidate_tools
+        .keys()
+        .filter(|name| !baseline_tools.contains_key(*name))
+        .cloned()
+        .collect();
+    let tools_removed = baseline_tools
+        .keys()
+        .filter(|name| !candidate_tools.contains_key(*name))
+        .cloned()
+        .collect();
+
+    let mut schema_changes = Vec::new();
+    for (name, baseline_tool) in &baseline_tools {
+        let Some(candidate_tool) = candidate_tools.get(name) else {
+            continue;
+        };
+        let mut changes = Vec::new();
+        diff_values("", baseline_tool, candidate_tool, &input.ignore_paths, &mut changes);
+        if !changes.is_empty() {
+            schema_changes.push(ToolSchemaChange {
+                tool: name.clone(),
+                changes,
+            });
+        }
+    }
+
+    let mut response_diffs = Vec::new();
+    let mut identical = 0;
+    for (index, request) in requests.iter().enumerate() {
+        let mut changes = Vec::new();
+        diff_values(
+            "",
+            &outcome_value(&baseline.responses[index]),
+            &outcome_value(&candidate.responses[index]),
+            &input.ignore_paths,
+            &mut changes,
+        );
+
+        if changes.is_empty() {
+            identical += 1;
+        } else {
+            response_diffs.push(ResponseDiff {
+                index,
+                method: request
+                    .get("method")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                // Replay used the real arguments; the report shows them masked.
+                params: redaction::redacted(request.get("params").unwrap_or(&Value::Null)),
+                changes,
+            });
+        }
+    }
+
+    Ok(RegressionReport {
+        baseline_server_id: input.baseline_server_id,
+        candidate_server_id: input.candidate_server_id,
+        replayed: requests.len(),
+        identical,
+        tools_added,
+        tools_removed,
+        schema_changes,
+        response_diffs,
+    })
+}
@@ -1,4 +1,6 @@
 use tauri::{State, AppHandle};
+use crate::commands::bridges;
+use crate::commands::config::reload_gateways;
 use crate::commands::profiles::get_active_profile_id_from_db;
 use crate::state::AppState;
 use crate::models::server::{Server, CreateServerInput, UpdateServerInput};
@@ -120,6 +122,7 @@ pub async fn get_server(state: State<'_, AppState>, id: String) -> Result<Value,
 #[tauri::command]
 pub async fn create_server(
     state: State<'_, AppState>,
+    app: AppHandle,
     input: CreateServerInput,
 ) -> Result<Server, String> {
     let db = state.db.lock().await;
@@ -164,10 +167,48 @@ pub async fn create_server(
     let source = if input.marketplace_id.is_some() { "marketplace" } else { "local" };
     let transport = input.transport.unwrap_or_else(|| "stdio".to_string());
     let url = input.url;
+    let response_policy = input
+        .response_policy
+        .map(|policy| serde_json::to_string(&policy))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let tool_filter = input
+        .tool_filter
+        .map(|filter| serde_json::to_string(&filter))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let tool_overrides = input
+        .tool_overrides
+        .map(|overrides| serde_json::to_string(&overrides))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let approval_policy = input
+        .approval_policy
+        .map(|policy| serde_json::to_string(&policy))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let cache_policy = input
+        .cache_policy
+        .map(|policy| serde_json::to_string(&policy))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    if let Some(policy) = &input.rate_limit {
+        policy.validate()?;
+    }
+    let rate_limit = input
+        .rate_limit
+        .map(|policy| serde_json::to_string(&policy))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let transforms = input
+        .transforms
+        .map(|policy| serde_json::to_string(&policy))
+        .transpose()
+        .map_err(|e| e.to_string())?;
 
     sqlx::query(
-        "INSERT INTO servers (id, name, description, command, args, env, secrets, enabled, category, profile_id, source, marketplace_id, transport, url, created_at, updated_at)
-         VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?, ?, ?, ?, ?, ?)"
+        "INSERT INTO servers (id, name, description, command, args, env, secrets, enabled, category, profile_id, source, marketplace_id, transport, url, response_policy, tool_filter, tool_overrides, alias, approval_policy, cache_policy, rate_limit, transforms, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(&id)
     .bind(&input.name)
@@ -182,17 +223,28 @@ pub async fn create_server(
     .bind(&input.marketplace_id)
     .bind(&transport)
     .bind(&url)
+    .bind(&response_policy)
+    .bind(&tool_filter)
+    .bind(&tool_overrides)
+    .bind(&input.alias)
+    .bind(&approval_policy)
+    .bind(&cache_policy)
+    .bind(&rate_limit)
+    .bind(&transforms)
     .bind(&now)
     .bind(&now)
     .execute(&*db)
     .await
     .map_err(|e| format!("Failed to create server: {}", e))?;
 
-    sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE id = ?")
+    let server = sqlx::query_as::<_, Server>("SELECT * FROM servers WHERE id = ?")
         .bind(&id)
         .fetch_one(&*db)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    reload_gateways(&app);
+    Ok(server)
 }
 
 #[tauri::command]
@@ -236,11 +288,57 @@ pub async fn update_server(
     if let Some(cat) = input.category { server.category = cat; }
     if let Some(transport) = input.transport { server.transport = Some(transport); }
     if let Some(url) = input.url { server.url = Some(url); }
+    if let Some(policy) = input.response_policy {
+        server.response_policy = policy
+            .map(|policy| serde_json::to_string(&policy))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(filter) = input.tool_filter {
+        server.tool_filter = filter
+            .map(|filter| serde_json::to_string(&filter))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(overrides) = input.tool_overrides {
+        server.tool_overrides = overrides
+            .map(|overrides| serde_json::to_string(&overrides))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(alias) = input.alias { server.alias = Some(alias); }
+    if let Some(policy) = input.approval_policy {
+        server.approval_policy = policy
+            .map(|policy| serde_json::to_string(&policy))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(policy) = input.cache_policy {
+        server.cache_policy = policy
+            .map(|policy| serde_json::to_string(&policy))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(policy) = input.rate_limit {
+        if let Some(policy) = &policy {
+            policy.validate()?;
+        }
+        server.rate_limit = policy
+            .map(|policy| serde_json::to_string(&policy))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(policy) = input.transforms {
+        server.transforms = policy
+            .map(|policy| serde_json::to_string(&policy))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+    }
 
     server.updated_at = chrono::Utc::now().to_rfc3339();
 
     sqlx::query(
-        "UPDATE servers SET name = ?, description = ?, command = ?, args = ?, env = ?, secrets = ?, enabled = ?, category = ?, transport = ?, url = ?, updated_at = ? WHERE id = ? AND profile_id = ?"
+        "UPDATE servers SET name = ?, description = ?, command = ?, args = ?, env = ?, secrets = ?, enabled = ?, category = ?, transport = ?, url = ?, response_policy = ?, tool_filter = ?, tool_overrides = ?, alias = ?, approval_policy = ?, cache_policy = ?, rate_limit = ?, transforms = ?, updated_at = ? WHERE id = ? AND profile_id = ?"
     )
     .bind(&server.name)
     .bind(&server.description)
@@ -252,6 +350,14 @@ pub async fn update_server(
     .bind(&server.category)
     .bind(&server.transport)
     .bind(&server.url)
+    .bind(&server.response_policy)
+    .bind(&server.tool_filter)
+    .bind(&server.tool_overrides)
+    .bind(&server.alias)
+    .bind(&server.approval_policy)
+    .bind(&server.cache_policy)
+    .bind(&server.rate_limit)
+    .bind(&server.transforms)
     .bind(&server.updated_at)
     .bind(&server.id)
     .bind(&active_profile)
@@ -269,16 +375,17 @@ pub async fn update_server(
         let args: Vec<String> = serde_json::from_str(&server.args).unwrap_or_default();
         let envs: std::collections::HashMap<String, String> = serde_json::from_str(&server.env).unwrap_or_default();
         let secrets: Vec<String> = serde_json::from_str(&server.secrets).unwrap_or_default();
-        if let Ok(proc) = spawn_server(server.id.clone(), server.name.clone(), server.command.clone(), args, envs, secrets, app).await {
+        if let Ok(proc) = spawn_server(server.id.clone(), server.name.clone(), server.command.clone(), args, envs, secrets, app.clone()).await {
             processes.insert(server.id.clone(), proc);
         }
     }
 
+    reload_gateways(&app);
     Ok(server)
 }
 
 #[tauri::command]
-pub async fn delete_server(state: State<'_, AppState>, id: String) -> Result<(), String> {
+pub async fn delete_server(state: State<'_, AppState>, app: AppHandle, id: String) -> Result<(), String> {
     let db = state.db.lock().await;
     let active_profile = get_active_profile_id_from_db(&*db).await?;
 
@@ -305,7 +412,11 @@ pub async fn delete_server(state: State<'_, AppState>, id: String) -> Result<(),
     if let Some(mut proc) = processes.remove(&id) {
         let _ = proc.child.kill().await;
     }
+    drop(processes);
+    drop(db);
+    bridges::stop(&state, &id, true).await?;
 
+    reload_gateways(&app);
     Ok(())
 }
 
@@ -335,7 +446,7 @@ pub async fn toggle_server(state: State<'_, AppState>, app: AppHandle, id: Strin
         let args: Vec<String> = serde_json::from_str(&server.args).unwrap_or_default();
         let envs: std::collections::HashMap<String, String> = serde_json::from_str(&server.env).unwrap_or_default();
         let secrets: Vec<String> = serde_json::from_str(&server.secrets).unwrap_or_default();
-        if let Ok(proc) = spawn_server(server.id.clone(), server.name.clone(), server.command.clone(), args, envs, secrets, app).await {
+        if let Ok(proc) = spawn_server(server.id.clone(), server.name.clone(), server.command.clone(), args, envs, secrets, app.clone()).await {
             processes.insert(server.id.clone(), proc);
         }
     } else {
@@ -344,5 +455,6 @@ pub async fn toggle_server(state: State<'_, AppState>, app: AppHandle, id: Strin
         }
     }
 
+    reload_gateways(&app);
     Ok(server)
 }
@@ -0,0 +1,249 @@
+use crate::commands::approvals::show_main_window;
+use crate::gateway::approval::ApprovalRequest;
+use crate::gateway::control::{self, ControlCommand, GatewayEvent};
+use crate::gateway::UpstreamSummary;
+use crate::proxy::{record_cache_hit, record_gateway_traffic, record_limit_event, record_policy_hit, record_transform};
+use crate::state::AppState;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader};
+use tokio::sync::mpsc;
+
+/// A `relay --gateway` process connected over the control channel.
+#[derive(Debug, Clone, Serialize)]
+pub struct GatewaySessionInfo {
+    pub instance_id: String,
+    pub pid: u32,
+    pub config_path: String,
+    pub started_at: String,
+    /// `None` until the client has initialized.
+    pub client: Option<String>,
+    pub upstreams: Vec<UpstreamSummary>,
+    pub active_toolsets: Vec<String>,
+}
+
+pub struct GatewaySession {
+    pub info: GatewaySessionInfo,
+    pub commands: mpsc::UnboundedSender<ControlCommand>,
+    /// Calls of this instance waiting for the user, by request id.
+    pub pending_approvals: HashMap<String, ApprovalRequest>,
+}
+
+async fn emit_sessions(app: &AppHandle) {
+    let state: State<AppState> = app.state();
+    let sessions: Vec<GatewaySessionInfo> = state
+        .gateway_sessions
+        .lock()
+        .await
+        .values()
+        .map(|session| session.info.clone())
+        .collect();
+    let _ = app.emit("gateway-sessions-changed", &sessions);
+}
+
+/// Serve one gateway instance until it disconnects.
+async fn serve_session<S: AsyncRead + AsyncWrite + Send + 'static>(stream: S, app: AppHandle) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    let Ok(Some(line)) = lines.next_line().await else {
+        return;
+    };
+    let Some(GatewayEvent::Register {
+        instance_id,
+        pid,
+        config_path,
+        started_at,
+    }) = control::read_line(&line)
+    else {
+        eprintln!("[Relay] Gateway did not register on the control channel");
+        return;
+    };
+
+    let (command_tx, mut command_rx) = mpsc::unbounded_channel::<ControlCommand>();
+    let writer_task = tokio::spawn(async move {
+        while let Some(command) = command_rx.recv().await {
+            if control::write_message(&mut writer, &command).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    {
+        let state: State<AppState> = app.state();
+        state.gateway_sessions.lock().await.insert(
+            instance_id.clone(),
+            GatewaySession {
+                info: GatewaySessionInfo {
+                    instance_id: instance_id.clone(),
+                    pid,
+                    config_path,
+                    started_at,
+                    client: None,
+                    upstreams: Vec::new(),
+                    active_toolsets: Vec::new(),
+                },
+                commands: command_tx,
+                pending_approvals: HashMap::new(),
+            },
+        );
+    }
+    emit_sessions(&app).await;
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Some(event) = control::read_line::<GatewayEvent>(&line) else {
+            continue;
+        };
+        let state: State<AppState> = app.state();
+        match event {
+            GatewayEvent::Register { .. } => {}
+            GatewayEvent::Status {
+                client,
+                upstreams,
+                active_toolsets,
+            } => {
+                if let Some(session) = state.gateway_sessions.lock().await.get_mut(&instance_id) {
+                    session.info.client = client;
+                    session.info.upstreams = upstreams;
+                    session.info.active_toolsets = active_toolsets;
+                }
+                emit_sessions(&app).await;
+            }
+            GatewayEvent::Request(sample) => state.metrics.lock().await.record(sample),
+            GatewayEvent::Traffic(sample) => record_gateway_traffic(&state, &app, &sample).await,
+            GatewayEvent::CacheHit(hit) => record_cache_hit(&state, &app, &hit).await,
+            GatewayEvent::PolicyHit(hit) => {
+                record_policy_hit(&state, &app, &hit).await;
+            }
+            GatewayEvent::Limit(event) => record_limit_event(&state, &app, &event).await,
+            GatewayEvent::Transform(report) => record_transform(&state, &app, &report).await,
+            GatewayEvent::ApprovalRequested(request) => {
+                if let Some(session) = state.gateway_sessions.lock().await.get_mut(&instance_id) {
+                    session.pending_approvals.insert(request.id.clone(), request.clone());
+                }
+                show_main_window(&app);
+                let _ = app.emit("tool-approval-request", &request);
+            }
+            // The instance logs its own decisions; this only updates the
+            // prompt.
+            GatewayEvent::ApprovalDecided(decision) => {
+                if let Some(session) = state.gateway_sessions.lock().await.get_mut(&instance_id) {
+                    session.pending_approvals.remove(&decision.request.id);
+                }
+                let _ = app.emit("tool-approval-decided", &decision);
+            }
+        }
+    }
+
+    writer_task.abort();
+    {
+        let state: State<AppState> = app.state();
+        state.gateway_sessions.lock().await.remove(&instance_id);
+    }
+    emit_sessions(&app).await;
+}
+
+#[cfg(not(windows))]
+async fn listen(endpoint: std::path::PathBuf, app: AppHandle) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    // The socket is bound in a directory only the user can enter, so it is
+    // never reachable by others, not even before it could be chmodded.
+    if let Some(parent) = endpoint.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        std::fs::set_permissions(parent, std::fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to restrict {}: {}", parent.display(), e))?;
+    }
+    if endpoint.exists() {
+        if tokio::net::UnixStream::connect(&endpoint).await.is_ok() {
+            return Err(format!("Another Relay instance is already listening on {}", endpoint.display()));
+        }
+        // Left behind if the app did not exit cleanly.
+        let _ = std::fs::remove_file(&endpoint);
+    }
+    let listener = tokio::net::UnixListener::bind(&endpoint)
+        .map_err(|e| format!("Failed to bind {}: {}", endpoint.display(), e))?;
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(serve_session(stream, app.clone()));
+            }
+            Err(e) => eprintln!("[Relay] Control channel accept failed: {}", e),
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn listen(endpoint: std::path::PathBuf, app: AppHandle) -> Result<(), String> {
+    use crate::gateway::pipe_security::CurrentUserOnly;
+    use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+
+    let mut security = CurrentUserOnly::new().map_err(|e| format!("Failed to secure {}: {}", endpoint.display(), e))?;
+    let mut create = |first: bool| -> Result<NamedPipeServer, String> {
+        // SAFETY: `security` outlives every call.
+        unsafe {
+            ServerOptions::new()
+                .first_pipe_instance(first)
+                .create_with_security_attributes_raw(&endpoint, security.as_ptr())
+        }
+        .map_err(|e| format!("Failed to create {}: {}", endpoint.display(), e))
+    };
+
+    let mut server = create(true)?;
+
+    loop {
+        server
+            .connect()
+            .await
+            .map_err(|e| format!("Control channel accept failed: {}", e))?;
+        // Open the next instance before handing this one off, so there is
+        // always one waiting.
+        let connected = server;
+        server = create(false)?;
+        tokio::spawn(serve_session(connected, app.clone()));
+    }
+}
+
+/// Accept gateway instances on the control channel for as long as the app
+/// runs.
+pub fn start_control_listener(app: &AppHandle) {
+    let Some(endpoint) = control::control_endpoint() else {
+        eprintln!("[Relay] No data directory; gateway sessions will not be tracked");
+        return;
+    };
+    let app = app.clone();
+    tokio::spawn(async move {
+        if let Err(e) = listen(endpoint, app).await {
+            eprintln!("[Relay] Gateway control channel stopped: {}", e);
+        }
+    });
+}
+
+/// Gateway instances currently connected to the app.
+#[tauri::command]
+pub async fn list_gateway_sessions(state: State<'_, AppState>) -> Result<Vec<GatewaySessionInfo>, String> {
+    let sessions = state.gateway_sessions.lock().await;
+    let mut sessions: Vec<GatewaySessionInfo> = sessions.values().map(|session| session.info.clone()).collect();
+    sessions.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    Ok(sessions)
+}
+
+/// Send a command to one gateway instance. Delivery is not acknowledged;
+/// its effect shows up in the next `gateway-sessions-changed` event.
+#[tauri::command]
+pub async fn send_gateway_command(
+    state: State<'_, AppState>,
+    instance_id: String,
+    command: ControlCommand,
+) -> Result<(), String> {
+    let sessions = state.gateway_sessions.lock().await;
+    let session = sessions
+        .get(&instance_id)
+        .ok_or_else(|| format!("Gateway session {} is not connected", instance_id))?;
+    session
+        .commands
+        .send(command)
+        .map_err(|_| format!("Gateway session {} is closing", instance_id))
+}
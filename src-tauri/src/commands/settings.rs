@@ -1,6 +1,36 @@
 use tauri::State;
+use sqlx::SqlitePool;
 use crate::state::AppState;
+use crate::gateway::namespace::NamespaceConfig;
 use crate::models::settings::AppSettings;
+use crate::utils::tokenizer::Tokenizer;
+
+/// Read a single setting, falling back to `default` when it is missing.
+pub async fn read_setting(db: &SqlitePool, key: &str, default: &str) -> String {
+    sqlx::query_scalar::<_, String>("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(db)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// Insert or update a single setting.
+pub async fn write_setting(db: &SqlitePool, key: &str, value: &str) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO settings (key, value, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+    )
+    .bind(key)
+    .bind(value)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(db)
+    .await
+    .map_err(|e| format!("Failed to update setting {}: {}", key, e))?;
+
+    Ok(())
+}
 
 #[tauri::command]
 pub async fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
@@ -16,9 +46,12 @@ pub async fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, Str
         .await
         .unwrap_or_else(|_| "false".to_string());
 
+    let tokenizer = read_setting(&db, "tokenizer", Tokenizer::default().as_setting()).await;
+
     Ok(AppSettings {
         theme,
         auto_export: auto_export == "true",
+        tokenizer,
     })
 }
 
@@ -41,6 +74,10 @@ pub async fn update_settings(
         .await
         .map_err(|e| format!("Failed to update autoExport: {}", e))?;
 
+    let tokenizer = Tokenizer::from_setting(&settings.tokenizer);
+    write_setting(&db, "tokenizer", tokenizer.as_setting()).await?;
+    *state.tokenizer.lock().await = tokenizer;
+
     Ok(settings)
 }
 
@@ -61,6 +98,22 @@ pub async fn update_setting(
     key: String,
     value: String,
 ) -> Result<(), String> {
+    match key.as_str() {
+        "toolNamespaceSeparator" => NamespaceConfig {
+            separator: value.clone(),
+            ..Default::default()
+        }
+        .validate()?,
+        "toolNameMaxLength" => NamespaceConfig {
+            max_length: value
+                .parse()
+                .map_err(|_| format!("Invalid tool name length limit: {}", value))?,
+            ..Default::default()
+        }
+        .validate()?,
+        _ => {}
+    }
+
     let db = state.db.lock().await;
 
     sqlx::query("UPDATE settings SET value = ? WHERE key = ?")
@@ -70,5 +123,16 @@ pub async fn update_setting(
         .await
         .map_err(|e| format!("Failed to update setting: {}", e))?;
 
+    if key == "tokenizer" {
+        *state.tokenizer.lock().await = Tokenizer::from_setting(&value);
+    }
+
     Ok(())
 }
+
+/// Load the configured tokenizer into app state.
+pub async fn load_tokenizer(state: &AppState) {
+    let db = state.db.lock().await;
+    let value = read_setting(&db, "tokenizer", Tokenizer::default().as_setting()).await;
+    *state.tokenizer.lock().await = Tokenizer::from_setting(&value);
+}
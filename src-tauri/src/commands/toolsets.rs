@@ -0,0 +1,158 @@
+use crate::commands::config::reload_gateways;
+use crate::gateway::toolsets::ToolsetEntry;
+use crate::models::toolset::{CreateToolsetInput, Toolset, UpdateToolsetInput};
+use crate::state::AppState;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, State};
+
+async fn ensure_unique_name(db: &SqlitePool, name: &str, id: Option<&str>) -> Result<(), String> {
+    let existing: Option<String> = sqlx::query_scalar("SELECT id FROM toolsets WHERE name = ?")
+        .bind(name)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| format!("Failed to check toolset name: {}", e))?;
+
+    match existing {
+        Some(existing) if Some(existing.as_str()) != id => Err(format!("A toolset named '{}' already exists", name)),
+        _ => Ok(()),
+    }
+}
+
+fn encode_tools(tools: &[ToolsetEntry]) -> Result<String, String> {
+    serde_json::to_string(tools).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_toolsets(state: State<'_, AppState>) -> Result<Vec<Toolset>, String> {
+    let db = state.db.lock().await;
+
+    sqlx::query_as::<_, Toolset>("SELECT * FROM toolsets ORDER BY name")
+        .fetch_all(&*db)
+        .await
+        .map_err(|e| format!("Failed to fetch toolsets: {}", e))
+}
+
+#[tauri::command]
+pub async fn create_toolset(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    input: CreateToolsetInput,
+) -> Result<Toolset, String> {
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Toolset name is required".to_string());
+    }
+
+    let db = state.db.lock().await;
+    ensure_unique_name(&db, &name, None).await?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let toolset = Toolset {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        description: input.description,
+        tools: encode_tools(&input.tools)?,
+        active: input.active.unwrap_or(false),
+        created_at: now.clone(),
+        updated_at: now,
+    };
+
+    sqlx::query(
+        "INSERT INTO toolsets (id, name, description, tools, active, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&toolset.id)
+    .bind(&toolset.name)
+    .bind(&toolset.description)
+    .bind(&toolset.tools)
+    .bind(toolset.active)
+    .bind(&toolset.created_at)
+    .bind(&toolset.updated_at)
+    .execute(&*db)
+    .await
+    .map_err(|e| format!("Failed to create toolset: {}", e))?;
+
+    reload_gateways(&app);
+    Ok(toolset)
+}
+
+#[tauri::command]
+pub async fn update_toolset(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    input: UpdateToolsetInput,
+) -> Result<Toolset, String> {
+    let db = state.db.lock().await;
+
+    let mut toolset = sqlx::query_as::<_, Toolset>("SELECT * FROM toolsets WHERE id = ?")
+        .bind(&input.id)
+        .fetch_one(&*db)
+        .await
+        .map_err(|e| format!("Toolset not found: {}", e))?;
+
+    if let Some(name) = input.name {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err("Toolset name is required".to_string());
+        }
+        ensure_unique_name(&db, &name, Some(&toolset.id)).await?;
+        toolset.name = name;
+    }
+    if let Some(description) = input.description { toolset.description = Some(description); }
+    if let Some(tools) = input.tools {
+        toolset.tools = encode_tools(&tools)?;
+    }
+    if let Some(active) = input.active { toolset.active = active; }
+    toolset.updated_at = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query("UPDATE toolsets SET name = ?, description = ?, tools = ?, active = ?, updated_at = ? WHERE id = ?")
+        .bind(&toolset.name)
+        .bind(&toolset.description)
+        .bind(&toolset.tools)
+        .bind(toolset.active)
+        .bind(&toolset.updated_at)
+        .bind(&toolset.id)
+        .execute(&*db)
+        .await
+        .map_err(|e| format!("Failed to update toolset: {}", e))?;
+
+    reload_gateways(&app);
+    Ok(toolset)
+}
+
+/// Activate or deactivate a toolset. Running gateways apply the change from
+/// their config; to switch a toolset in one running gateway only, send it a
+/// `set_toolset` command.
+#[tauri::command]
+pub async fn set_toolset_active(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    id: String,
+    active: bool,
+) -> Result<Toolset, String> {
+    update_toolset(
+        state,
+        app,
+        UpdateToolsetInput {
+            id,
+            name: None,
+            description: None,
+            tools: None,
+            active: Some(active),
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_toolset(state: State<'_, AppState>, app: AppHandle, id: String) -> Result<(), String> {
+    let db = state.db.lock().await;
+
+    sqlx::query("DELETE FROM toolsets WHERE id = ?")
+        .bind(&id)
+        .execute(&*db)
+        .await
+        .map_err(|e| format!("Failed to delete toolset: {}", e))?;
+
+    reload_gateways(&app);
+    Ok(())
+}
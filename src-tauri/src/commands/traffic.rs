@@ -0,0 +1,192 @@
+use crate::proxy::{CapturedMessage, TrafficCapture, TrafficDirection};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+
+/// Latency at which a paired message counts as slow, unless overridden.
+const DEFAULT_SLOW_THRESHOLD_MS: u64 = 1000;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TrafficFilter {
+    /// Method prefix, e.g. `tools/` or `tools/call`. Responses match by the
+    /// method of their request.
+    pub method: Option<String>,
+    #[serde(default)]
+    pub errors_only: bool,
+    #[serde(default)]
+    pub slow_only: bool,
+    pub slow_threshold_ms: Option<u64>,
+}
+
+impl TrafficFilter {
+    fn matches(&self, message: &CapturedMessage) -> bool {
+        if let Some(prefix) = self.method.as_deref().filter(|p| !p.is_empty()) {
+            if !message.method.as_deref().unwrap_or("").starts_with(prefix) {
+                return false;
+            }
+        }
+        if self.errors_only && !message.is_error {
+            return false;
+        }
+        if self.slow_only {
+            let threshold = self.slow_threshold_ms.unwrap_or(DEFAULT_SLOW_THRESHOLD_MS);
+            if message.latency_ms.is_none_or(|latency| latency < threshold) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrafficPage {
+    /// Messages matching the filter, across all pages.
+    pub total: usize,
+    /// Newest first.
+    pub messages: Vec<CapturedMessage>,
+}
+
+/// One side of an exchange in the export.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarMessage {
+    direction: TrafficDirection,
+    method: Option<String>,
+    id: Option<Value>,
+    timestamp: String,
+    body_size: u64,
+    payload: Value,
+}
+
+impl From<&CapturedMessage> for HarMessage {
+    fn from(message: &CapturedMessage) -> Self {
+        Self {
+            direction: message.direction,
+            method: message.method.clone(),
+            id: message.id.clone(),
+            timestamp: message.timestamp.clone(),
+            body_size: message.bytes,
+            payload: message.payload.clone(),
+        }
+    }
+}
+
+/// A request and its response, or a lone notification.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarEntry {
+    started_date_time: String,
+    /// Latency in milliseconds; -1 when no response was seen.
+    time: i64,
+    server_id: String,
+    is_error: bool,
+    /// Absent for a response whose request was already evicted.
+    request: Option<HarMessage>,
+    response: Option<HarMessage>,
+}
+
+/// Group captured messages into exchanges: a request with its response, or
+/// an unpaired message on its own.
+fn har_entries(capture: &TrafficCapture, filter: &TrafficFilter) -> Vec<HarEntry> {
+    capture
+        .messages
+        .iter()
+        .filter(|m| filter.matches(m))
+        .filter_map(|m| {
+            let is_response = m.payload.get("method").is_none();
+            let (request, response) = match m.paired_seq.and_then(|seq| capture.get(seq)) {
+                // Exported together with its request.
+                Some(_) if is_response => return None,
+                Some(response) => (Some(m), Some(response)),
+                None if is_response => (None, Some(m)),
+                None => (Some(m), None),
+            };
+            Some(HarEntry {
+                started_date_time: m.timestamp.clone(),
+                time: m.latency_ms.map(|ms| ms as i64).unwrap_or(-1),
+                server_id: m.server_id.clone(),
+                is_error: m.is_error,
+                request: request.map(HarMessage::from),
+                response: response.map(HarMessage::from),
+            })
+        })
+        .collect()
+}
+
+/// Captured JSON-RPC messages for a server, newest first.
+#[tauri::command]
+pub async fn get_traffic_capture(
+    state: State<'_, AppState>,
+    server_id: String,
+    filter: Option<TrafficFilter>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<TrafficPage, String> {
+    let filter = filter.unwrap_or_default();
+    let capture_map = state.traffic_capture.lock().await;
+    let Some(capture) = capture_map.get(&server_id) else {
+        return Ok(TrafficPage {
+            total: 0,
+            messages: Vec::new(),
+        });
+    };
+
+    let matching: Vec<&CapturedMessage> = capture.messages.iter().rev().filter(|m| filter.matches(m)).collect();
+    Ok(TrafficPage {
+        total: matching.len(),
+        messages: matching
+            .into_iter()
+            .skip(offset.unwrap_or(0))
+            .take(limit.unwrap_or(100))
+            .cloned()
+            .collect(),
+    })
+}
+
+/// Drop captured traffic for one server, or for all of them.
+#[tauri::command]
+pub async fn clear_traffic_capture(state: State<'_, AppState>, server_id: Option<String>) -> Result<(), String> {
+    let mut capture_map = state.traffic_capture.lock().await;
+    match server_id {
+        Some(server_id) => {
+            if let Some(capture) = capture_map.get_mut(&server_id) {
+                capture.messages.clear();
+            }
+        }
+        None => capture_map.values_mut().for_each(|capture| capture.messages.clear()),
+    }
+    Ok(())
+}
+
+/// Write matching exchanges to `path` as HAR-style JSON (`log.entries`).
+/// Payloads are stored redacted. Returns the number of entries written.
+#[tauri::command]
+pub async fn export_traffic_capture(
+    state: State<'_, AppState>,
+    server_id: String,
+    filter: Option<TrafficFilter>,
+    path: String,
+) -> Result<usize, String> {
+    let filter = filter.unwrap_or_default();
+    let entries = {
+        let capture_map = state.traffic_capture.lock().await;
+        capture_map
+            .get(&server_id)
+            .map(|capture| har_entries(capture, &filter))
+            .unwrap_or_default()
+    };
+
+    let har = serde_json::json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "Relay", "version": env!("CARGO_PKG_VERSION") },
+            "comment": "JSON-RPC traffic; each entry is a request with its response",
+            "entries": entries,
+        }
+    });
+    let content = serde_json::to_string_pretty(&har).map_err(|e| format!("Failed to serialize capture: {}", e))?;
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+
+    Ok(entries.len())
+}
@@ -0,0 +1,306 @@
+use crate::commands::config::load_gateway_config;
+use crate::commands::gateway::inspection_gateway;
+use crate::commands::profiles::get_active_profile_id_from_db;
+use crate::commands::settings::read_setting;
+use crate::proxy::{flush_usage_samples, ToolUsageStats};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+
+/// Default token budget for the tool definitions of the active profile.
+const DEFAULT_TOOL_CONTEXT_BUDGET: u64 = 20_000;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGranularity {
+    #[default]
+    Hour,
+    Day,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsageHistoryQuery {
+    /// Inclusive RFC 3339 start of the range.
+    pub from: String,
+    /// Exclusive RFC 3339 end of the range.
+    pub to: String,
+    #[serde(default)]
+    pub granularity: UsageGranularity,
+    /// Restrict to one server; all servers when omitted.
+    pub server_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct UsageBucket {
+    pub server_id: String,
+    pub bucket: String,
+    pub bytes_in: i64,
+    pub bytes_out: i64,
+    pub tokens_in: i64,
+    pub tokens_out: i64,
+    pub messages_in: i64,
+    pub messages_out: i64,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct UsageTotal {
+    pub server_id: String,
+    pub server_name: Option<String>,
+    pub bytes_in: i64,
+    pub bytes_out: i64,
+    pub tokens_in: i64,
+    pub tokens_out: i64,
+    pub total_tokens: i64,
+    pub messages_in: i64,
+    pub messages_out: i64,
+}
+
+/// Normalize an RFC 3339 timestamp to the UTC form used for bucket keys so
+/// the range can be compared as text.
+fn to_bucket_time(value: &str) -> Result<String, String> {
+    let parsed = chrono::DateTime::parse_from_rfc3339(value)
+        .map_err(|e| format!("Invalid timestamp {}: {}", value, e))?;
+    Ok(parsed
+        .with_timezone(&chrono::Utc)
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string())
+}
+
+const DIRECTION_COLUMNS: &str = "
+    SUM(CASE WHEN direction = 'inbound' THEN bytes ELSE 0 END) AS bytes_in,
+    SUM(CASE WHEN direction = 'outbound' THEN bytes ELSE 0 END) AS bytes_out,
+    SUM(CASE WHEN direction = 'inbound' THEN tokens ELSE 0 END) AS tokens_in,
+    SUM(CASE WHEN direction = 'outbound' THEN tokens ELSE 0 END) AS tokens_out,
+    SUM(CASE WHEN direction = 'inbound' THEN messages ELSE 0 END) AS messages_in,
+    SUM(CASE WHEN direction = 'outbound' THEN messages ELSE 0 END) AS messages_out";
+
+#[tauri::command]
+pub async fn get_context_usage_history(
+    state: State<'_, AppState>,
+    query: UsageHistoryQuery,
+) -> Result<Vec<UsageBucket>, String> {
+    flush_usage_samples(&state).await;
+    let from = to_bucket_time(&query.from)?;
+    let to = to_bucket_time(&query.to)?;
+
+    let bucket_expr = match query.granularity {
+        UsageGranularity::Hour => "bucket",
+        UsageGranularity::Day => "substr(bucket, 1, 10) || 'T00:00:00Z'",
+    };
+
+    let sql = format!(
+        "SELECT server_id, {} AS bucket, {}
+         FROM context_usage_samples
+         WHERE bucket >= ? AND bucket < ? AND (? IS NULL OR server_id = ?)
+         GROUP BY server_id, 2
+         ORDER BY 2, server_id",
+        bucket_expr, DIRECTION_COLUMNS
+    );
+
+    let db = state.db.lock().await;
+
+    sqlx::query_as::<_, UsageBucket>(&sql)
+        .bind(&from)
+        .bind(&to)
+        .bind(&query.server_id)
+        .bind(&query.server_id)
+        .fetch_all(&*db)
+        .await
+        .map_err(|e| format!("Failed to fetch usage history: {}", e))
+}
+
+/// Per-server totals over a range, heaviest context consumers first.
+#[tauri::command]
+pub async fn get_context_usage_totals(
+    state: State<'_, AppState>,
+    from: String,
+    to: String,
+) -> Result<Vec<UsageTotal>, String> {
+    flush_usage_samples(&state).await;
+    let from = to_bucket_time(&from)?;
+    let to = to_bucket_time(&to)?;
+
+    let sql = format!(
+        "SELECT u.server_id, s.name AS server_name, {},
+            SUM(u.tokens) AS total_tokens
+         FROM context_usage_samples u
+         LEFT JOIN servers s ON s.id = u.server_id
+         WHERE u.bucket >= ? AND u.bucket < ?
+         GROUP BY u.server_id
+         ORDER BY total_tokens DESC",
+        DIRECTION_COLUMNS
+    );
+
+    let db = state.db.lock().await;
+
+    sqlx::query_as::<_, UsageTotal>(&sql)
+        .bind(&from)
+        .bind(&to)
+        .fetch_all(&*db)
+        .await
+        .map_err(|e| format!("Failed to fetch usage totals: {}", e))
+}
+
+/// Per-tool call statistics for this session, most expensive tools first.
+#[tauri::command]
+pub async fn get_tool_usage(
+    state: State<'_, AppState>,
+    server_id: Option<String>,
+) -> Result<Vec<ToolUsageStats>, String> {
+    let tool_map = state.tool_usage.lock().await;
+
+    let mut stats: Vec<ToolUsageStats> = tool_map
+        .iter()
+        .filter(|(id, _)| server_id.as_ref().is_none_or(|wanted| wanted == *id))
+        .flat_map(|(_, tools)| tools.values().cloned())
+        .collect();
+    stats.sort_by_key(|s| std::cmp::Reverse(s.total_tokens));
+
+    Ok(stats)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolDefinitionCost {
+    pub name: String,
+    pub name_tokens: u64,
+    pub description_tokens: u64,
+    pub schema_tokens: u64,
+    pub total_tokens: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerToolListCost {
+    pub server_id: String,
+    pub server_name: String,
+    pub tool_count: usize,
+    pub total_tokens: u64,
+    /// Most expensive first.
+    pub tools: Vec<ToolDefinitionCost>,
+    /// Set when the server could not be reached; its cost is then unknown.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolListCostReport {
+    pub profile_id: String,
+    pub tokenizer: String,
+    pub budget: u64,
+    pub total_tokens: u64,
+    pub over_budget: bool,
+    /// Most expensive first.
+    pub servers: Vec<ServerToolListCost>,
+    pub warnings: Vec<String>,
+}
+
+/// Connect to every enabled server in the active profile and measure how many
+/// tokens its tool definitions (name, description, inputSchema) add to the
+/// model's context before the conversation starts.
+#[tauri::command]
+pub async fn get_tool_list_cost(state: State<'_, AppState>) -> Result<ToolListCostReport, String> {
+    let (profile_id, config, budget) = {
+        let db = state.db.lock().await;
+        let profile_id = get_active_profile_id_from_db(&db).await?;
+        let config = load_gateway_config(&db).await?;
+        let budget = read_setting(&db, "toolContextBudget", &DEFAULT_TOOL_CONTEXT_BUDGET.to_string())
+            .await
+            .parse()
+            .unwrap_or(DEFAULT_TOOL_CONTEXT_BUDGET);
+        (profile_id, config, budget)
+    };
+    let tokenizer = *state.tokenizer.lock().await;
+
+    let mut tools_by_server = inspection_gateway(&state).await?.upstream_tools().await;
+
+    let mut servers = Vec::new();
+    for server in &config.servers {
+        let Some(tools) = tools_by_server.remove(&server.id) else {
+            servers.push(ServerToolListCost {
+                server_id: server.id.clone(),
+                server_name: server.name.clone(),
+                tool_count: 0,
+                total_tokens: 0,
+                tools: Vec::new(),
+                error: Some("Failed to connect".to_string()),
+            });
+            continue;
+        };
+
+        let mut costs: Vec<ToolDefinitionCost> = tools
+            .iter()
+            .map(|tool| {
+                let name = tool.get("name").and_then(Value::as_str).unwrap_or_default();
+                let description = tool.get("description").and_then(Value::as_str).unwrap_or_default();
+                let schema = tool
+                    .get("inputSchema")
+                    .map(|schema| schema.to_string())
+                    .unwrap_or_default();
+
+                let name_tokens = tokenizer.count(name);
+                let description_tokens = tokenizer.count(description);
+                let schema_tokens = tokenizer.count(&schema);
+                ToolDefinitionCost {
+                    name: name.to_string(),
+                    name_tokens,
+                    description_tokens,
+                    schema_tokens,
+                    total_tokens: name_tokens + description_tokens + schema_tokens,
+                }
+            })
+            .collect();
+        costs.sort_by_key(|cost| std::cmp::Reverse(cost.total_tokens));
+
+        servers.push(ServerToolListCost {
+            server_id: server.id.clone(),
+            server_name: server.name.clone(),
+            tool_count: costs.len(),
+            total_tokens: costs.iter().map(|cost| cost.total_tokens).sum(),
+            tools: costs,
+            error: None,
+        });
+    }
+    servers.sort_by_key(|server| std::cmp::Reverse(server.total_tokens));
+
+    let total_tokens: u64 = servers.iter().map(|server| server.total_tokens).sum();
+    let over_budget = total_tokens > budget;
+
+    let mut warnings = Vec::new();
+    if over_budget {
+        let heaviest: Vec<String> = servers
+            .iter()
+            .take(3)
+            .filter(|server| server.total_tokens > 0)
+            .map(|server| format!("{} ({} tokens)", server.server_name, server.total_tokens))
+            .collect();
+        warnings.push(format!(
+            "Tool definitions use {} tokens, {} over the budget of {}. Largest: {}",
+            total_tokens,
+            total_tokens - budget,
+            budget,
+            heaviest.join(", ")
+        ));
+    }
+    for server in &servers {
+        if server.error.is_some() {
+            warnings.push(format!(
+                "{} could not be reached; its tool definitions are not counted",
+                server.server_name
+            ));
+        } else if budget > 0 && server.total_tokens * 2 > budget {
+            warnings.push(format!(
+                "{} alone uses more than half of the budget ({} tokens)",
+                server.server_name, server.total_tokens
+            ));
+        }
+    }
+
+    Ok(ToolListCostReport {
+        profile_id,
+        tokenizer: tokenizer.as_setting().to_string(),
+        budget,
+        total_tokens,
+        over_budget,
+        servers,
+        warnings,
+    })
+}
@@ -21,12 +21,53 @@ pub async fn init_db() -> Result<SqlitePool, sqlx::Error> {
         .execute(&pool)
         .await?;
 
+    sqlx::raw_sql(include_str!("../migrations/002_gateway_clients.sql"))
+        .execute(&pool)
+        .await?;
+
+    sqlx::raw_sql(include_str!("../migrations/003_context_usage.sql"))
+        .execute(&pool)
+        .await?;
+
+    sqlx::raw_sql(include_str!("../migrations/004_approval_decisions.sql"))
+        .execute(&pool)
+        .await?;
+
+    sqlx::raw_sql(include_str!("../migrations/005_audit_log.sql"))
+        .execute(&pool)
+        .await?;
+
+    sqlx::raw_sql(include_str!("../migrations/006_toolsets.sql"))
+        .execute(&pool)
+        .await?;
+
+    sqlx::raw_sql(include_str!("../migrations/007_bridges.sql"))
+        .execute(&pool)
+        .await?;
+
     ensure_profile_schema(&pool).await?;
     ensure_server_columns(&pool).await?;
 
     Ok(pool)
 }
 
+/// Open the app database from another process (the stdio gateway) without
+/// running migrations. `None` when the app has never created it.
+pub async fn connect_existing() -> Option<SqlitePool> {
+    let db_path = get_db_path();
+    if !db_path.exists() {
+        return None;
+    }
+
+    let db_url = format!("sqlite:{}?mode=rw", db_path.to_string_lossy());
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect(&db_url)
+        .await
+        .map_err(|e| eprintln!("[Relay] Failed to open database: {}", e))
+        .ok()
+}
+
 fn get_db_path() -> PathBuf {
     let data_dir = dirs::data_dir()
         .expect("Could not determine data directory")
@@ -41,6 +82,14 @@ async fn ensure_server_columns(pool: &SqlitePool) -> Result<(), sqlx::Error> {
         ("previous_config", "TEXT"),
         ("transport", "TEXT DEFAULT 'stdio'"),
         ("url", "TEXT"),
+        ("response_policy", "TEXT"),
+        ("tool_filter", "TEXT"),
+        ("tool_overrides", "TEXT"),
+        ("alias", "TEXT"),
+        ("approval_policy", "TEXT"),
+        ("cache_policy", "TEXT"),
+        ("rate_limit", "TEXT"),
+        ("transforms", "TEXT"),
     ];
 
     for (col, col_type) in &columns {
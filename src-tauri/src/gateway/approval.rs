@@ -0,0 +1,218 @@
+//! Human-in-the-loop approval for tool calls. The gateway holds a call until
+//! whoever subscribed to approval requests (the desktop app, directly or over
+//! the control channel) resolves it. With no subscriber the call is refused.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot, Mutex};
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalMode {
+    AlwaysAllow,
+    AskEveryTime,
+    /// Ask on the first call in a client session, then allow.
+    AskOncePerSession,
+    Deny,
+}
+
+impl ApprovalMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AlwaysAllow => "always_allow",
+            Self::AskEveryTime => "ask_every_time",
+            Self::AskOncePerSession => "ask_once_per_session",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+/// Per-server approval policy: a server-wide mode plus per-tool modes keyed
+/// by the upstream tool name. Without either, tools annotated
+/// `destructiveHint: true` ask every time and everything else is allowed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalPolicy {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<ApprovalMode>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tools: HashMap<String, ApprovalMode>,
+}
+
+impl ApprovalPolicy {
+    /// Whether the policy leaves every tool to the default.
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.tools.is_empty()
+    }
+}
+
+/// The mode that applies to `tool`, given its upstream definition if known.
+pub fn mode_for(policy: Option<&ApprovalPolicy>, tool: &str, definition: Option<&Value>) -> ApprovalMode {
+    if let Some(mode) = policy.and_then(|policy| policy.tools.get(tool).or(policy.default.as_ref())) {
+        return *mode;
+    }
+
+    let destructive = definition
+        .and_then(|tool| tool.pointer("/annotations/destructiveHint"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    if destructive {
+        ApprovalMode::AskEveryTime
+    } else {
+        ApprovalMode::AlwaysAllow
+    }
+}
+
+/// A call waiting for a decision.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub id: String,
+    pub server_id: String,
+    pub server_name: String,
+    pub tool: String,
+    pub arguments: Value,
+    pub client: String,
+    pub session_id: String,
+    pub mode: ApprovalMode,
+    pub requested_at: String,
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalOutcome {
+    Approved,
+    Denied,
+    TimedOut,
+    /// Nobody was listening for approval requests, so the call was refused.
+    NoApprover,
+}
+
+impl ApprovalOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Approved => "approved",
+            Self::Denied => "denied",
+            Self::TimedOut => "timed_out",
+            Self::NoApprover => "no_approver",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalDecision {
+    pub request: ApprovalRequest,
+    pub outcome: ApprovalOutcome,
+    pub decided_at: String,
+}
+
+pub struct ApprovalBroker {
+    timeout: Duration,
+    pending: Mutex<HashMap<String, (ApprovalRequest, oneshot::Sender<bool>)>>,
+    /// (session, server id, tool) approved under `AskOncePerSession`.
+    session_grants: Mutex<HashSet<(String, String, String)>>,
+    request_tx: broadcast::Sender<ApprovalRequest>,
+    decision_tx: broadcast::Sender<ApprovalDecision>,
+}
+
+impl ApprovalBroker {
+    pub fn new(timeout_secs: u64) -> Self {
+        let (request_tx, _) = broadcast::channel(64);
+        let (decision_tx, _) = broadcast::channel(256);
+        Self {
+            timeout: Duration::from_secs(timeout_secs),
+            pending: Mutex::new(HashMap::new()),
+            session_grants: Mutex::new(HashSet::new()),
+            request_tx,
+            decision_tx,
+        }
+    }
+
+    pub fn timeout_secs(&self) -> u64 {
+        self.timeout.as_secs()
+    }
+
+    pub fn subscribe_requests(&self) -> broadcast::Receiver<ApprovalRequest> {
+        self.request_tx.subscribe()
+    }
+
+    pub fn subscribe_decisions(&self) -> broadcast::Receiver<ApprovalDecision> {
+        self.decision_tx.subscribe()
+    }
+
+    /// Whether a session already approved this tool.
+    pub async fn granted(&self, session_id: &str, server_id: &str, tool: &str) -> bool {
+        self.session_grants.lock().await.contains(&(
+            session_id.to_string(),
+            server_id.to_string(),
+            tool.to_string(),
+        ))
+    }
+
+    /// Ask for a decision and wait for it, up to the timeout.
+    pub async fn request(&self, request: ApprovalRequest) -> ApprovalOutcome {
+        let (respond, decision) = oneshot::channel();
+        self.pending
+            .lock()
+            .await
+            .insert(request.id.clone(), (request.clone(), respond));
+
+        let outcome = if self.request_tx.send(request.clone()).is_err() {
+            ApprovalOutcome::NoApprover
+        } else {
+            match tokio::time::timeout(self.timeout, decision).await {
+                Ok(Ok(true)) => ApprovalOutcome::Approved,
+                Ok(Ok(false)) | Ok(Err(_)) => ApprovalOutcome::Denied,
+                Err(_) => ApprovalOutcome::TimedOut,
+            }
+        };
+        self.pending.lock().await.remove(&request.id);
+
+        if outcome == ApprovalOutcome::Approved && request.mode == ApprovalMode::AskOncePerSession {
+            self.session_grants.lock().await.insert((
+                request.session_id.clone(),
+                request.server_id.clone(),
+                request.tool.clone(),
+            ));
+        }
+
+        self.record(request, outcome);
+        outcome
+    }
+
+    /// Log a decision made without asking (deny policies).
+    pub fn record(&self, request: ApprovalRequest, outcome: ApprovalOutcome) {
+        let _ = self.decision_tx.send(ApprovalDecision {
+            request,
+            outcome,
+            decided_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    /// Deliver the user's answer. Returns false when the request is no longer
+    /// pending (already answered or timed out).
+    pub async fn resolve(&self, id: &str, approved: bool) -> bool {
+        match self.pending.lock().await.remove(id) {
+            Some((_, respond)) => respond.send(approved).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Requests still waiting, e.g. for a window that opened after they were
+    /// sent.
+    pub async fn pending(&self) -> Vec<ApprovalRequest> {
+        let mut pending: Vec<ApprovalRequest> = self
+            .pending
+            .lock()
+            .await
+            .values()
+            .map(|(request, _)| request.clone())
+            .collect();
+        pending.sort_by(|a, b| a.requested_at.cmp(&b.requested_at));
+        pending
+    }
+}
@@ -0,0 +1,177 @@
+//! Opt-in per-server caching of tool results, for tools that return the same
+//! result for the same arguments (documentation lookups, schema
+//! introspection, ...).
+
+use super::filter::glob_match;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_TTL_SECS: u64 = 300;
+pub const DEFAULT_MAX_ENTRIES: usize = 256;
+
+/// Which of a server's tools have their results cached, and for how long.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachePolicy {
+    /// Upstream tool name globs; `*` caches every tool the annotations allow.
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_secs: Option<u64>,
+    /// Entries kept for this server; the least recently used go first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_entries: Option<usize>,
+}
+
+impl CachePolicy {
+    pub fn covers(&self, tool: &str) -> bool {
+        self.tools.iter().any(|pattern| glob_match(pattern, tool))
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_secs.unwrap_or(DEFAULT_TTL_SECS))
+    }
+
+    fn max_entries(&self) -> usize {
+        self.max_entries.unwrap_or(DEFAULT_MAX_ENTRIES).max(1)
+    }
+}
+
+/// Whether a tool's annotations allow caching its results. Missing hints
+/// take the MCP defaults (not read-only, destructive, not idempotent), so
+/// only read-only tools, or tools explicitly marked non-destructive and
+/// idempotent, are cached.
+pub fn annotations_allow(definition: Option<&Value>) -> bool {
+    let hint = |name: &str| {
+        definition
+            .and_then(|tool| tool.get("annotations"))
+            .and_then(|annotations| annotations.get(name))
+            .and_then(Value::as_bool)
+    };
+    if hint("readOnlyHint") == Some(true) {
+        return true;
+    }
+    hint("destructiveHint") == Some(false) && hint("idempotentHint") == Some(true)
+}
+
+/// Arguments as JSON with object keys sorted, so key order does not matter.
+fn canonical_json(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (index, key) in keys.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                canonical_json(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                canonical_json(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+/// A tool result served from the cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheHit {
+    pub server_id: String,
+    /// Upstream tool name.
+    pub tool: String,
+}
+
+struct CacheEntry {
+    tool: String,
+    result: Value,
+    expires: Instant,
+    last_used: Instant,
+}
+
+/// Cached results by server id, then by tool and canonical arguments.
+#[derive(Default)]
+pub struct ResponseCache {
+    servers: HashMap<String, HashMap<String, CacheEntry>>,
+}
+
+impl ResponseCache {
+    fn key(tool: &str, arguments: &Value) -> String {
+        let mut key = format!("{}\n", tool);
+        canonical_json(arguments, &mut key);
+        key
+    }
+
+    pub fn get(&mut self, server_id: &str, tool: &str, arguments: &Value) -> Option<Value> {
+        let entries = self.servers.get_mut(server_id)?;
+        let key = Self::key(tool, arguments);
+        let now = Instant::now();
+        match entries.get_mut(&key) {
+            Some(entry) if entry.expires > now => {
+                entry.last_used = now;
+                Some(entry.result.clone())
+            }
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&mut self, server_id: &str, policy: &CachePolicy, tool: &str, arguments: &Value, result: Value) {
+        let entries = self.servers.entry(server_id.to_string()).or_default();
+        let now = Instant::now();
+        entries.retain(|_, entry| entry.expires > now);
+
+        let key = Self::key(tool, arguments);
+        while !entries.contains_key(&key) && entries.len() >= policy.max_entries() {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => break,
+            };
+        }
+
+        entries.insert(
+            key,
+            CacheEntry {
+                tool: tool.to_string(),
+                result,
+                expires: now + policy.ttl(),
+                last_used: now,
+            },
+        );
+    }
+
+    /// Drop cached results for a server (or every server), optionally only
+    /// for one tool. Returns how many were dropped.
+    pub fn invalidate(&mut self, server_id: Option<&str>, tool: Option<&str>) -> usize {
+        let mut dropped = 0;
+        for (id, entries) in self.servers.iter_mut() {
+            if server_id.is_some_and(|server_id| server_id != id) {
+                continue;
+            }
+            let before = entries.len();
+            entries.retain(|_, entry| tool.is_some_and(|tool| tool != entry.tool));
+            dropped += before - entries.len();
+        }
+        dropped
+    }
+}
@@ -0,0 +1,187 @@
+use super::approval::ApprovalPolicy;
+use super::cache::CachePolicy;
+use super::filter::ToolFilter;
+use super::limits::RateLimitPolicy;
+use super::namespace::NamespaceConfig;
+use super::overrides::ToolOverrides;
+use super::policy::ResponsePolicy;
+use super::search::ToolMode;
+use super::toolsets::ToolsetConfig;
+use super::transforms::TransformPolicy;
+use crate::models::server::Server;
+use crate::telemetry::TelemetryConfig;
+use crate::utils::tokenizer::Tokenizer;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// One upstream server as written to `relay.json` by the export commands.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GatewayServerConfig {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// Keyring entries (by key name) to inject into the environment.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secrets: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    /// Short prefix for this server's tools; the server name when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_policy: Option<ResponsePolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_filter: Option<ToolFilter>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_overrides: Option<ToolOverrides>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_policy: Option<ApprovalPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_policy: Option<CachePolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimitPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transforms: Option<TransformPolicy>,
+}
+
+impl GatewayServerConfig {
+    pub fn from_server(server: &Server) -> Self {
+        Self {
+            id: server.id.clone(),
+            name: server.name.clone(),
+            command: server.command.clone(),
+            args: serde_json::from_str(&server.args).unwrap_or_default(),
+            env: serde_json::from_str(&server.env).unwrap_or_default(),
+            secrets: serde_json::from_str(&server.secrets).unwrap_or_default(),
+            transport: server.transport.clone(),
+            url: server.url.clone(),
+            disabled: !server.enabled,
+            alias: server.alias.clone().filter(|alias| !alias.trim().is_empty()),
+            response_policy: server
+                .response_policy
+                .as_deref()
+                .and_then(|policy| serde_json::from_str(policy).ok()),
+            tool_filter: server
+                .tool_filter
+                .as_deref()
+                .and_then(|filter| serde_json::from_str(filter).ok()),
+            tool_overrides: server
+                .tool_overrides
+                .as_deref()
+                .and_then(|overrides| serde_json::from_str(overrides).ok()),
+            approval_policy: server
+                .approval_policy
+                .as_deref()
+                .and_then(|policy| serde_json::from_str(policy).ok()),
+            cache_policy: server
+                .cache_policy
+                .as_deref()
+                .and_then(|policy| serde_json::from_str(policy).ok()),
+            rate_limit: server
+                .rate_limit
+                .as_deref()
+                .and_then(|policy| serde_json::from_str(policy).ok()),
+            transforms: server
+                .transforms
+                .as_deref()
+                .and_then(|policy| serde_json::from_str(policy).ok()),
+        }
+    }
+
+    pub fn namespace_prefix(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn is_remote(&self) -> bool {
+        !matches!(self.transport.as_deref(), None | Some("stdio") | Some(""))
+    }
+
+    /// Names of the call policies in effect on this server, which only the
+    /// gateway enforces.
+    pub fn call_policies(&self) -> Vec<&'static str> {
+        [
+            ("tool filter", self.tool_filter.as_ref().is_some_and(|p| !p.is_empty())),
+            ("approval policy", self.approval_policy.as_ref().is_some_and(|p| !p.is_empty())),
+            ("rate limit", self.rate_limit.as_ref().is_some_and(|p| !p.is_empty())),
+            ("response policy", self.response_policy.as_ref().is_some_and(|p| !p.is_empty())),
+            ("transforms", self.transforms.as_ref().is_some_and(|p| !p.is_empty())),
+        ]
+        .into_iter()
+        .filter_map(|(name, set)| set.then_some(name))
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GatewayConfig {
+    #[serde(default)]
+    pub servers: Vec<GatewayServerConfig>,
+    /// Tokenizer used to measure responses against policies.
+    #[serde(default)]
+    pub tokenizer: Tokenizer,
+    #[serde(default)]
+    pub namespace: NamespaceConfig,
+    /// How long a call waits for approval before it is refused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_timeout_secs: Option<u64>,
+    /// Where to export spans of gateway requests; none when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<TelemetryConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub toolsets: Vec<ToolsetConfig>,
+}
+
+impl GatewayConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read gateway config {}: {}", path.display(), e))?;
+        let config: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse gateway config {}: {}", path.display(), e))?;
+        config.namespace.validate()?;
+        for server in &config.servers {
+            if let Some(policy) = &server.rate_limit {
+                policy.validate().map_err(|e| format!("{}: {}", server.name, e))?;
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Tool mode for `relay --gateway`: `--tool-mode <full|search>`, full by
+/// default.
+pub fn resolve_tool_mode(args: &[String]) -> Result<ToolMode, String> {
+    let Some(index) = args.iter().position(|a| a == "--tool-mode") else {
+        return Ok(ToolMode::Full);
+    };
+    let value = args.get(index + 1).map(String::as_str).unwrap_or("");
+    ToolMode::parse(value).ok_or_else(|| format!("Unknown tool mode '{}': expected full or search", value))
+}
+
+/// Resolve the config path for `relay --gateway`: `--config <path>`, then
+/// `RELAY_CONFIG_PATH`, then `relay.json` in the app data directory.
+pub fn resolve_config_path(args: &[String]) -> Option<PathBuf> {
+    if let Some(index) = args.iter().position(|a| a == "--config") {
+        if let Some(path) = args.get(index + 1) {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    if let Ok(path) = std::env::var("RELAY_CONFIG_PATH") {
+        if !path.is_empty() {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    // Matches Tauri's app_data_dir for the `com.emt.relay` identifier.
+    dirs::data_dir().map(|dir| dir.join("com.emt.relay").join("relay.json"))
+}
@@ -0,0 +1,351 @@
+//! Control channel between the desktop app and `relay --gateway` instances.
+//! The app listens on a per-user local socket (a named pipe on Windows);
+//! each instance connects, registers, reports its upstreams and traffic as
+//! newline-delimited JSON, and accepts commands back.
+
+use super::approval::{ApprovalDecision, ApprovalPolicy, ApprovalRequest};
+use super::cache::CacheHit;
+use super::config::GatewayConfig;
+use super::filter::ToolFilter;
+use super::limits::{LimitEvent, RateLimitPolicy};
+use super::policy::{PolicyHit, ResponsePolicy};
+use super::transforms::{TransformPolicy, TransformReport};
+use super::{Gateway, TrafficSample, UpstreamSummary};
+use crate::metrics::RequestSample;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{broadcast, watch};
+
+/// How often an instance retries when the app is not running.
+const RECONNECT_SECS: u64 = 10;
+
+/// Sent by a gateway instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GatewayEvent {
+    Register {
+        instance_id: String,
+        pid: u32,
+        config_path: String,
+        started_at: String,
+    },
+    Status {
+        /// `clientInfo.name` from the client's `initialize`.
+        client: Option<String>,
+        upstreams: Vec<UpstreamSummary>,
+        #[serde(default)]
+        active_toolsets: Vec<String>,
+    },
+    Request(RequestSample),
+    Traffic(TrafficSample),
+    CacheHit(CacheHit),
+    PolicyHit(PolicyHit),
+    Limit(LimitEvent),
+    Transform(TransformReport),
+    /// A call waiting for the user; answered with `resolve_approval`.
+    ApprovalRequested(ApprovalRequest),
+    ApprovalDecided(ApprovalDecision),
+}
+
+/// Sent by the app to one instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Re-read the instance's config file.
+    Reload,
+    /// Shut down one upstream until the next reload.
+    DisconnectUpstream { server_id: String },
+    /// Drop cached tool results, for one server and tool or everything.
+    ClearCache {
+        #[serde(default)]
+        server_id: Option<String>,
+        #[serde(default)]
+        tool: Option<String>,
+    },
+    /// Replace a server's policies and reconnect it, until the next reload.
+    SetPolicy(Box<PolicyUpdate>),
+    /// Activate or deactivate a toolset (by name or id) in this instance.
+    SetToolset { toolset: String, active: bool },
+    /// The user's answer to an `approval_requested` event.
+    ResolveApproval { request_id: String, approved: bool },
+}
+
+/// New policies for one server. Omitted policies are left as they are;
+/// `null` clears one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyUpdate {
+    pub server_id: String,
+    #[serde(default, with = "crate::utils::patch", skip_serializing_if = "Option::is_none")]
+    pub response_policy: Option<Option<ResponsePolicy>>,
+    #[serde(default, with = "crate::utils::patch", skip_serializing_if = "Option::is_none")]
+    pub tool_filter: Option<Option<ToolFilter>>,
+    #[serde(default, with = "crate::utils::patch", skip_serializing_if = "Option::is_none")]
+    pub approval_policy: Option<Option<ApprovalPolicy>>,
+    #[serde(default, with = "crate::utils::patch", skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<Option<RateLimitPolicy>>,
+    #[serde(default, with = "crate::utils::patch", skip_serializing_if = "Option::is_none")]
+    pub transforms: Option<Option<TransformPolicy>>,
+}
+
+/// Per-user endpoint the app listens on.
+pub fn control_endpoint() -> Option<PathBuf> {
+    #[cfg(windows)]
+    {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        Some(PathBuf::from(format!(r"\\.\pipe\relay-gateway-control-{}", user)))
+    }
+    #[cfg(not(windows))]
+    {
+        // In a directory of its own next to relay.json, which the app keeps
+        // private to the user.
+        dirs::data_dir().map(|dir| dir.join("com.emt.relay").join("control").join("gateway-control.sock"))
+    }
+}
+
+pub fn read_line<T: for<'de> Deserialize<'de>>(line: &str) -> Option<T> {
+    match serde_json::from_str(line) {
+        Ok(message) => Some(message),
+        Err(e) => {
+            eprintln!("[Relay] Ignoring control message: {}", e);
+            None
+        }
+    }
+}
+
+pub async fn write_message<W: AsyncWrite + Unpin, T: Serialize>(writer: &mut W, message: &T) -> std::io::Result<()> {
+    let line = serde_json::to_string(message).map_err(std::io::Error::other)?;
+    writer.write_all(format!("{}\n", line).as_bytes()).await?;
+    writer.flush().await
+}
+
+#[cfg(not(windows))]
+async fn open(endpoint: &Path) -> std::io::Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(endpoint).await
+}
+
+#[cfg(windows)]
+async fn open(endpoint: &Path) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    let pipe = tokio::net::windows::named_pipe::ClientOptions::new().open(endpoint)?;
+    super::pipe_security::check_server(&pipe)?;
+    Ok(pipe)
+}
+
+/// Connects a running gateway to the app. Keeps retrying while the app is
+/// not running; dropping the handle stops it.
+pub struct ControlClient {
+    client: watch::Sender<Option<String>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl ControlClient {
+    pub fn spawn(gateway: Arc<Gateway>, config_path: PathBuf, session_id: String) -> Self {
+        let (client, client_rx) = watch::channel(None);
+        let task = tokio::spawn(async move {
+            let Some(endpoint) = control_endpoint() else {
+                return;
+            };
+            let instance_id = uuid::Uuid::new_v4().to_string();
+            let started_at = chrono::Utc::now().to_rfc3339();
+
+            loop {
+                if let Ok(stream) = open(&endpoint).await {
+                    let register = GatewayEvent::Register {
+                        instance_id: instance_id.clone(),
+                        pid: std::process::id(),
+                        config_path: config_path.to_string_lossy().to_string(),
+                        started_at: started_at.clone(),
+                    };
+                    if let Err(e) = session(stream, &gateway, &config_path, &session_id, register, client_rx.clone()).await {
+                        eprintln!("[Relay] Control channel closed: {}", e);
+                    }
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(RECONNECT_SECS)).await;
+            }
+        });
+        Self { client, task }
+    }
+
+    /// Report the client name once it has initialized.
+    pub fn set_client(&self, name: &str) {
+        self.client.send_replace(Some(name.to_string()));
+    }
+}
+
+impl Drop for ControlClient {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn status(gateway: &Gateway, session_id: &str, client: &watch::Receiver<Option<String>>) -> GatewayEvent {
+    let client = client.borrow().clone();
+    GatewayEvent::Status {
+        client,
+        upstreams: gateway.connected_servers().await,
+        active_toolsets: gateway.active_toolsets(session_id).await,
+    }
+}
+
+/// One connection to the app: report until it goes away, applying its
+/// commands.
+async fn session<S: AsyncRead + AsyncWrite>(
+    stream: S,
+    gateway: &Arc<Gateway>,
+    config_path: &Path,
+    session_id: &str,
+    register: GatewayEvent,
+    mut client: watch::Receiver<Option<String>>,
+) -> std::io::Result<()> {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut commands = BufReader::new(reader).lines();
+    let mut notifications = gateway.subscribe();
+    let mut requests = gateway.subscribe_request_samples();
+    let mut traffic = gateway.subscribe_traffic();
+    let mut cache_hits = gateway.subscribe_cache_hits();
+    let mut policy_hits = gateway.subscribe_policy_hits();
+    let mut limit_events = gateway.subscribe_limit_events();
+    let mut transforms = gateway.subscribe_transforms();
+    // Subscribed only while connected, so calls needing approval are not
+    // held when the app cannot answer.
+    let mut approval_requests = gateway.approvals().subscribe_requests();
+    let mut approval_decisions = gateway.approvals().subscribe_decisions();
+
+    write_message(&mut writer, &register).await?;
+    write_message(&mut writer, &status(gateway, session_id, &client).await).await?;
+
+    loop {
+        let event = tokio::select! {
+            line = commands.next_line() => {
+                let Some(line) = line? else {
+                    return Ok(());
+                };
+                if let Some(command) = read_line::<ControlCommand>(&line) {
+                    if let Err(e) = apply(gateway, config_path, session_id, command).await {
+                        eprintln!("[Relay] {}", e);
+                    }
+                }
+                continue;
+            }
+            changed = client.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+                status(gateway, session_id, &client).await
+            }
+            notification = notifications.recv() => match notification {
+                Ok(notification)
+                    if notification.is_for(session_id)
+                        && notification.message.get("method").and_then(Value::as_str)
+                            == Some("notifications/tools/list_changed") =>
+                {
+                    status(gateway, session_id, &client).await
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            sample = requests.recv() => match sample {
+                Ok(sample) => GatewayEvent::Request(sample),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            sample = traffic.recv() => match sample {
+                Ok(sample) => GatewayEvent::Traffic(sample),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            hit = cache_hits.recv() => match hit {
+                Ok(hit) => GatewayEvent::CacheHit(hit),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            hit = policy_hits.recv() => match hit {
+                Ok(hit) => GatewayEvent::PolicyHit(hit),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            event = limit_events.recv() => match event {
+                Ok(event) => GatewayEvent::Limit(event),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            report = transforms.recv() => match report {
+                Ok(report) => GatewayEvent::Transform(report),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            request = approval_requests.recv() => match request {
+                Ok(request) => GatewayEvent::ApprovalRequested(request),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            decision = approval_decisions.recv() => match decision {
+                Ok(decision) => GatewayEvent::ApprovalDecided(decision),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+        };
+        write_message(&mut writer, &event).await?;
+    }
+}
+
+async fn apply(gateway: &Gateway, config_path: &Path, session_id: &str, command: ControlCommand) -> Result<(), String> {
+    match command {
+        ControlCommand::Reload => {
+            let config = GatewayConfig::load(config_path)?;
+            gateway.reload(config).await;
+        }
+        ControlCommand::DisconnectUpstream { server_id } => {
+            if !gateway.disconnect_upstream(&server_id).await {
+                return Err(format!("Server {} is not connected", server_id));
+            }
+        }
+        ControlCommand::ClearCache { server_id, tool } => {
+            gateway.clear_cache(server_id.as_deref(), tool.as_deref()).await;
+        }
+        ControlCommand::SetPolicy(update) => {
+            let PolicyUpdate {
+                server_id,
+                response_policy,
+                tool_filter,
+                approval_policy,
+                rate_limit,
+                transforms,
+            } = *update;
+            let mut server = gateway
+                .server_config(&server_id)
+                .await
+                .ok_or_else(|| format!("Server {} is not connected", server_id))?;
+            if let Some(policy) = response_policy {
+                server.response_policy = policy;
+            }
+            if let Some(filter) = tool_filter {
+                server.tool_filter = filter;
+            }
+            if let Some(policy) = approval_policy {
+                server.approval_policy = policy;
+            }
+            if let Some(policy) = rate_limit {
+                if let Some(policy) = &policy {
+                    policy.validate()?;
+                }
+                server.rate_limit = policy;
+            }
+            if let Some(policy) = transforms {
+                server.transforms = policy;
+            }
+            gateway.replace_server(server).await?;
+        }
+        ControlCommand::SetToolset { toolset, active } => {
+            gateway.set_toolset_active(session_id, &toolset, active).await?;
+        }
+        ControlCommand::ResolveApproval { request_id, approved } => {
+            if !gateway.approvals().resolve(&request_id, approved).await {
+                return Err(format!("Approval request {} is no longer pending", request_id));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Per-server tool filters: which of a server's tools the gateway exposes.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterMode {
+    /// Expose only tools matching a pattern.
+    Allow,
+    /// Expose every tool except those matching a pattern.
+    #[default]
+    Deny,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolFilter {
+    #[serde(default)]
+    pub mode: FilterMode,
+    /// Tool name globs; `*` matches any run of characters, `?` one character.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Only expose tools annotated with `readOnlyHint: true`.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// Case-sensitive glob match supporting `*` and `?`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+impl ToolFilter {
+    /// Whether the filter lets every tool through.
+    pub fn is_empty(&self) -> bool {
+        self.mode == FilterMode::Deny && self.patterns.is_empty() && !self.read_only
+    }
+
+    /// Whether the definition has to be looked at, rather than just the name.
+    pub fn needs_definition(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn allows_name(&self, name: &str) -> bool {
+        let matched = self.patterns.iter().any(|pattern| glob_match(pattern, name));
+        match self.mode {
+            FilterMode::Allow => matched,
+            FilterMode::Deny => !matched,
+        }
+    }
+
+    /// Whether a tool from `tools/list` passes the filter.
+    pub fn allows(&self, tool: &Value) -> bool {
+        let name = tool.get("name").and_then(Value::as_str).unwrap_or("");
+        if self.read_only {
+            let read_only = tool
+                .pointer("/annotations/readOnlyHint")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            if !read_only {
+                return false;
+            }
+        }
+        self.allows_name(name)
+    }
+}
@@ -0,0 +1,335 @@
+//! Streamable HTTP transport for the gateway, hosted by the desktop app on
+//! localhost so URL-based clients can connect without launching a process.
+//! Clients exported in tool search mode connect to `/mcp?tool_mode=search`.
+
+use super::search::ToolMode;
+use super::{ClientSession, Gateway};
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, oneshot, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+
+/// Sessions unused for this long are ended.
+const SESSION_IDLE_SECS: u64 = 30 * 60;
+const SWEEP_INTERVAL_SECS: u64 = 60;
+
+/// Bearer token -> client name.
+pub type ClientTokens = Arc<RwLock<HashMap<String, String>>>;
+
+#[derive(Deserialize)]
+struct McpQuery {
+    #[serde(default)]
+    tool_mode: ToolMode,
+}
+
+struct HttpSession {
+    client: String,
+    last_seen: Instant,
+}
+
+struct HttpState {
+    gateway: Arc<Gateway>,
+    tokens: ClientTokens,
+    sessions: Mutex<HashMap<String, HttpSession>>,
+}
+
+impl HttpState {
+    /// Check that the caller's session exists and belongs to it.
+    async fn session_id<'a>(&self, session_id: Option<&'a str>, client: &str) -> Result<&'a str, Response> {
+        let Some(session_id) = session_id else {
+            return Err((StatusCode::BAD_REQUEST, "Missing mcp-session-id header; send initialize first").into_response());
+        };
+
+        match self.sessions.lock().await.get_mut(session_id) {
+            Some(session) if session.client == client => {
+                session.last_seen = Instant::now();
+                Ok(session_id)
+            }
+            _ => Err((StatusCode::NOT_FOUND, "Unknown session").into_response()),
+        }
+    }
+
+    /// End sessions unused for `SESSION_IDLE_SECS`, releasing what the
+    /// gateway holds for them.
+    async fn end_idle_sessions(&self) {
+        let mut expired = Vec::new();
+        self.sessions.lock().await.retain(|id, session| {
+            let idle = session.last_seen.elapsed() >= Duration::from_secs(SESSION_IDLE_SECS);
+            if idle {
+                expired.push(id.clone());
+            }
+            !idle
+        });
+        for session_id in expired {
+            self.gateway.end_session(&session_id).await;
+        }
+    }
+}
+
+pub struct HttpGatewayServer {
+    pub port: u16,
+    pub gateway: Arc<Gateway>,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+    sweep: JoinHandle<()>,
+}
+
+impl HttpGatewayServer {
+    pub async fn start(gateway: Arc<Gateway>, tokens: ClientTokens, port: u16) -> Result<Self, String> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .await
+            .map_err(|e| format!("Failed to bind 127.0.0.1:{}: {}", port, e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| e.to_string())?
+            .port();
+
+        let state = Arc::new(HttpState {
+            gateway: Arc::clone(&gateway),
+            tokens,
+            sessions: Mutex::new(HashMap::new()),
+        });
+
+        let sweep = {
+            let state = Arc::downgrade(&state);
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(SWEEP_INTERVAL_SECS));
+                loop {
+                    interval.tick().await;
+                    let Some(state) = state.upgrade() else {
+                        break;
+                    };
+                    state.end_idle_sessions().await;
+                }
+            })
+        };
+
+        let router = Router::new()
+            .route("/mcp", post(handle_post).get(handle_get).delete(handle_delete))
+            .with_state(state);
+
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let _ = axum::serve(listener, router)
+                .with_graceful_shutdown(async {
+                    let _ = shutdown_rx.await;
+                })
+                .await;
+        });
+
+        Ok(Self {
+            port,
+            gateway,
+            shutdown,
+            task,
+            sweep,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}/mcp", self.port)
+    }
+
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.task.await;
+        self.sweep.abort();
+        self.gateway.shutdown().await;
+    }
+}
+
+fn is_local_origin(origin: &str) -> bool {
+    let host = origin
+        .split("://")
+        .nth(1)
+        .unwrap_or(origin)
+        .split('/')
+        .next()
+        .unwrap_or("");
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+/// Resolve the calling client from its bearer token. Browser requests from
+/// non-local origins are refused to guard against DNS rebinding.
+pub(crate) async fn authorize(tokens: &ClientTokens, headers: &HeaderMap) -> Result<String, Response> {
+    if let Some(origin) = headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()) {
+        if !is_local_origin(origin) {
+            return Err((StatusCode::FORBIDDEN, "Origin not allowed").into_response());
+        }
+    }
+
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .unwrap_or("");
+
+    match tokens.read().await.get(token) {
+        Some(client) if !token.is_empty() => Ok(client.clone()),
+        _ => Err((StatusCode::UNAUTHORIZED, "Missing or invalid bearer token").into_response()),
+    }
+}
+
+async fn handle_post(
+    State(state): State<Arc<HttpState>>,
+    Query(query): Query<McpQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let client = match authorize(&state.tokens, &headers).await {
+        Ok(client) => client,
+        Err(response) => return response,
+    };
+
+    let payload = match serde_json::from_str::<Value>(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            let error = json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": -32700, "message": format!("Parse error: {}", e) }
+            });
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    let is_batch = payload.is_array();
+    let messages = match payload {
+        Value::Array(batch) => batch,
+        single => vec![single],
+    };
+
+    let starts_session = messages
+        .iter()
+        .any(|m| m.get("method").and_then(Value::as_str) == Some("initialize"));
+
+    let session = if starts_session {
+        ClientSession::new(&client, query.tool_mode)
+    } else {
+        let session_id = headers.get("mcp-session-id").and_then(|v| v.to_str().ok());
+        match state.session_id(session_id, &client).await {
+            Ok(id) => ClientSession {
+                id: id.to_string(),
+                client: client.clone(),
+                tool_mode: query.tool_mode,
+            },
+            Err(response) => return response,
+        }
+    };
+
+    let mut responses = Vec::new();
+    for message in messages {
+        if let Some(response) = state.gateway.handle_message(message, &session).await {
+            responses.push(response);
+        }
+    }
+
+    if responses.is_empty() {
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    let body = if is_batch {
+        Value::Array(responses)
+    } else {
+        responses.remove(0)
+    };
+    let mut response = Json(body).into_response();
+
+    if starts_session {
+        state
+            .sessions
+            .lock()
+            .await
+            .insert(
+                session.id.clone(),
+                HttpSession {
+                    client,
+                    last_seen: Instant::now(),
+                },
+            );
+        if let Ok(value) = HeaderValue::from_str(&session.id) {
+            response.headers_mut().insert("mcp-session-id", value);
+        }
+    }
+
+    response
+}
+
+/// Server-to-client stream carrying gateway notifications (list changes,
+/// progress, logging) for the caller's session.
+async fn handle_get(State(state): State<Arc<HttpState>>, headers: HeaderMap) -> Response {
+    let client = match authorize(&state.tokens, &headers).await {
+        Ok(client) => client,
+        Err(response) => return response,
+    };
+
+    let accepts_sse = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.contains("text/event-stream"))
+        .unwrap_or(false);
+    if !accepts_sse {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+
+    let session_id = headers.get("mcp-session-id").and_then(|v| v.to_str().ok());
+    let session = match state.session_id(session_id, &client).await {
+        Ok(session) => session.to_string(),
+        Err(response) => return response,
+    };
+
+    let receiver = state.gateway.subscribe();
+    let stream = futures_util::stream::unfold(receiver, move |mut receiver| {
+        let session = session.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(notification) if notification.is_for(&session) => {
+                        let event = Event::default().event("message").data(notification.message.to_string());
+                        return Some((Ok::<Event, Infallible>(event), receiver));
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+async fn handle_delete(State(state): State<Arc<HttpState>>, headers: HeaderMap) -> Response {
+    let client = match authorize(&state.tokens, &headers).await {
+        Ok(client) => client,
+        Err(response) => return response,
+    };
+
+    let session_id = headers.get("mcp-session-id").and_then(|v| v.to_str().ok());
+    let session_id = match state.session_id(session_id, &client).await {
+        Ok(session_id) => session_id,
+        Err(response) => return response,
+    };
+    state.sessions.lock().await.remove(session_id);
+    state.gateway.end_session(session_id).await;
+
+    StatusCode::OK.into_response()
+}
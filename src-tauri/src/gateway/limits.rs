@@ -0,0 +1,327 @@
+//! Per-server and per-tool rate limits (token buckets) and concurrency caps
+//! for tool calls routed through the gateway.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+
+/// JSON-RPC error code for calls refused by a limit.
+pub const LIMIT_ERROR_CODE: i64 = -32029;
+pub const DEFAULT_MAX_WAIT_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallLimits {
+    /// Sustained rate; unlimited when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calls_per_minute: Option<u32>,
+    /// Calls allowed back to back before the rate applies. Defaults to
+    /// `callsPerMinute`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverLimit {
+    /// Refuse the call with a JSON-RPC error.
+    #[default]
+    Reject,
+    /// Hold the call until it fits, up to `maxWaitSecs`.
+    Queue,
+}
+
+/// Per-server limits: server-wide limits plus per-tool limits keyed by the
+/// upstream tool name. A call has to fit both.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitPolicy {
+    #[serde(flatten)]
+    pub defaults: CallLimits,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tools: HashMap<String, CallLimits>,
+    #[serde(default)]
+    pub over_limit: OverLimit,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_wait_secs: Option<u64>,
+}
+
+impl CallLimits {
+    pub fn is_empty(&self) -> bool {
+        self.calls_per_minute.is_none() && self.max_concurrent.is_none()
+    }
+}
+
+impl RateLimitPolicy {
+    /// Whether no tool is limited.
+    pub fn is_empty(&self) -> bool {
+        self.defaults.is_empty() && self.tools.values().all(CallLimits::is_empty)
+    }
+
+    /// Check no rate is zero, which would allow a single call and then
+    /// block the tool for good.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.defaults.calls_per_minute == Some(0) {
+            return Err("Invalid rate limit: callsPerMinute must be at least 1".to_string());
+        }
+        for (tool, limits) in &self.tools {
+            if limits.calls_per_minute == Some(0) {
+                return Err(format!("Invalid rate limit for {}: callsPerMinute must be at least 1", tool));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a limit did to a call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "outcome")]
+pub enum LimitOutcome {
+    /// Held until it fit.
+    Queued { wait_ms: u64 },
+    /// Refused.
+    Rejected { reason: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitEvent {
+    pub server_id: String,
+    /// Upstream tool name.
+    pub tool: String,
+    #[serde(flatten)]
+    pub outcome: LimitOutcome,
+}
+
+struct Bucket {
+    tokens: f64,
+    capacity: f64,
+    per_sec: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(calls_per_minute: u32, burst: Option<u32>) -> Self {
+        let capacity = burst.unwrap_or(calls_per_minute).max(1) as f64;
+        Self {
+            tokens: capacity,
+            capacity,
+            per_sec: calls_per_minute as f64 / 60.0,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Refill, then say how long until a token is available; `None` if one
+    /// is.
+    fn wait(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            return None;
+        }
+        if self.per_sec <= 0.0 {
+            return Some(Duration::MAX);
+        }
+        Some(Duration::from_secs_f64((1.0 - self.tokens) / self.per_sec))
+    }
+
+    /// Take a token `wait` said is available.
+    fn take(&mut self) {
+        self.tokens -= 1.0;
+    }
+}
+
+/// State for one set of limits: a server, or one tool of a server.
+struct Limiter {
+    limits: CallLimits,
+    bucket: Option<Bucket>,
+    slots: Option<Arc<Semaphore>>,
+}
+
+impl Limiter {
+    fn new(limits: &CallLimits) -> Self {
+        Self {
+            limits: limits.clone(),
+            bucket: limits.calls_per_minute.map(|rate| Bucket::new(rate, limits.burst)),
+            slots: limits.max_concurrent.map(|max| Arc::new(Semaphore::new(max.max(1)))),
+        }
+    }
+}
+
+/// A refused call.
+#[derive(Debug, Clone)]
+pub struct LimitError {
+    pub reason: String,
+    pub retry_after: Option<Duration>,
+}
+
+impl LimitError {
+    pub fn to_rpc_error(&self) -> Value {
+        let mut error = json!({ "code": LIMIT_ERROR_CODE, "message": self.reason });
+        if let Some(retry_after) = self.retry_after.filter(|d| *d != Duration::MAX) {
+            error["data"] = json!({ "retryAfterMs": retry_after.as_millis() as u64 });
+        }
+        error
+    }
+}
+
+/// Held for the duration of a call; frees its concurrency slots on drop.
+pub struct CallPermit {
+    _slots: Vec<OwnedSemaphorePermit>,
+    /// Time spent queued.
+    pub waited: Duration,
+}
+
+/// Limiters keyed by server id and optional tool name.
+#[derive(Default)]
+pub struct CallLimiter {
+    limiters: Mutex<HashMap<(String, Option<String>), Limiter>>,
+}
+
+impl CallLimiter {
+    /// Wait for, or refuse, a call to `tool` on `server_id` under `policy`.
+    pub async fn acquire(
+        &self,
+        server_id: &str,
+        server_name: &str,
+        tool: &str,
+        policy: &RateLimitPolicy,
+    ) -> Result<CallPermit, LimitError> {
+        let started = Instant::now();
+        let max_wait = match policy.over_limit {
+            OverLimit::Reject => Duration::ZERO,
+            OverLimit::Queue => Duration::from_secs(policy.max_wait_secs.unwrap_or(DEFAULT_MAX_WAIT_SECS)),
+        };
+        let deadline = started + max_wait;
+
+        let mut scopes = Vec::new();
+        if let Some(limits) = policy.tools.get(tool) {
+            scopes.push((Some(tool.to_string()), limits, format!("{}/{}", server_name, tool)));
+        }
+        scopes.push((None, &policy.defaults, server_name.to_string()));
+
+        // Every scope is checked before any takes a token or a slot, so a
+        // call refused by one scope uses up nothing in the others.
+        loop {
+            let blocked = {
+                let mut limiters = self.limiters.lock().await;
+                match try_acquire(&mut limiters, server_id, &scopes) {
+                    Ok(slots) => {
+                        return Ok(CallPermit {
+                            _slots: slots,
+                            waited: started.elapsed(),
+                        });
+                    }
+                    Err(blocked) => blocked,
+                }
+            };
+
+            match blocked {
+                Blocked::Rate { wait, .. } if Instant::now().checked_add(wait).is_some_and(|ready| ready <= deadline) => {
+                    tokio::time::sleep(wait).await;
+                }
+                Blocked::Rate { wait, label, rate } => {
+                    return Err(LimitError {
+                        reason: format!("Rate limit exceeded for {}: at most {} calls per minute", label, rate),
+                        retry_after: Some(wait),
+                    });
+                }
+                Blocked::Busy { slots, label, max } => {
+                    // Wait for a slot to free up, then check every scope
+                    // again.
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    let freed = !remaining.is_zero()
+                        && tokio::time::timeout(remaining, slots.acquire_owned())
+                            .await
+                            .is_ok_and(|permit| permit.is_ok());
+                    if !freed {
+                        return Err(LimitError {
+                            reason: format!("Too many concurrent calls to {}: at most {} at a time", label, max),
+                            retry_after: None,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Why a call cannot start yet.
+enum Blocked<'a> {
+    /// A rate limit has no token for `wait`.
+    Rate { wait: Duration, label: &'a str, rate: u32 },
+    /// A concurrency cap has no free slot.
+    Busy { slots: Arc<Semaphore>, label: &'a str, max: usize },
+}
+
+/// Start a call if every scope allows it, taking a token from each rate
+/// limit and a slot from each concurrency cap; otherwise take nothing and
+/// say what blocks it, preferring the longest rate wait.
+fn try_acquire<'a>(
+    limiters: &mut HashMap<(String, Option<String>), Limiter>,
+    server_id: &str,
+    scopes: &'a [(Option<String>, &CallLimits, String)],
+) -> Result<Vec<OwnedSemaphorePermit>, Blocked<'a>> {
+    let mut blocked: Option<Blocked> = None;
+    for (tool, limits, label) in scopes {
+        let limiter = limiter_for(limiters, server_id, tool, limits);
+        let (Some(bucket), Some(rate)) = (limiter.bucket.as_mut(), limits.calls_per_minute) else {
+            continue;
+        };
+        if let Some(wait) = bucket.wait() {
+            if !matches!(blocked, Some(Blocked::Rate { wait: longest, .. }) if longest >= wait) {
+                blocked = Some(Blocked::Rate { wait, label, rate });
+            }
+        }
+    }
+    if let Some(blocked) = blocked {
+        return Err(blocked);
+    }
+
+    let mut slots = Vec::new();
+    for (tool, limits, label) in scopes {
+        let Some(semaphore) = limiter_for(limiters, server_id, tool, limits).slots.clone() else {
+            continue;
+        };
+        match semaphore.clone().try_acquire_owned() {
+            Ok(permit) => slots.push(permit),
+            Err(_) => {
+                return Err(Blocked::Busy {
+                    slots: semaphore,
+                    label,
+                    max: limits.max_concurrent.unwrap_or_default(),
+                });
+            }
+        }
+    }
+
+    for (tool, limits, _) in scopes {
+        if let Some(bucket) = limiter_for(limiters, server_id, tool, limits).bucket.as_mut() {
+            bucket.take();
+        }
+    }
+    Ok(slots)
+}
+
+/// The limiter for a scope, rebuilt when its limits changed.
+fn limiter_for<'a>(
+    limiters: &'a mut HashMap<(String, Option<String>), Limiter>,
+    server_id: &str,
+    tool: &Option<String>,
+    limits: &CallLimits,
+) -> &'a mut Limiter {
+    let limiter = limiters
+        .entry((server_id.to_string(), tool.clone()))
+        .or_insert_with(|| Limiter::new(limits));
+    if limiter.limits != *limits {
+        *limiter = Limiter::new(limits);
+    }
+    limiter
+}
@@ -0,0 +1,1806 @@
+//! Aggregating MCP gateway, run as `relay --gateway`.
+//!
+//! Clients launch the Relay binary in gateway mode; it reads the exported
+//! `relay.json`, connects to every configured upstream server, and exposes
+//! their tools, resources and prompts as a single MCP server over stdio.
+
+pub mod approval;
+pub mod cache;
+pub mod config;
+pub mod control;
+pub mod filter;
+pub mod http;
+pub mod limits;
+pub mod namespace;
+pub mod overrides;
+#[cfg(windows)]
+pub mod pipe_security;
+pub mod policy;
+pub mod search;
+pub mod toolsets;
+pub mod transforms;
+mod upstream;
+
+use crate::audit::{CallStatus, ToolCallRecord};
+use crate::metrics::RequestSample;
+use crate::proxy::TrafficDirection;
+use crate::telemetry::{Span, SpanContext, SpanKind};
+use crate::utils::redaction;
+use crate::utils::tokenizer::{context_text, Tokenizer};
+use approval::{ApprovalBroker, ApprovalMode, ApprovalOutcome, ApprovalRequest};
+use cache::{CacheHit, ResponseCache};
+use limits::{CallLimiter, CallPermit, LimitEvent, LimitOutcome};
+use config::{GatewayConfig, GatewayServerConfig};
+use namespace::{NameRequest, NamespaceConfig, RoutingTable};
+use policy::PolicyHit;
+use search::{ToolIndex, ToolMode};
+use toolsets::Toolsets;
+use transforms::TransformReport;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
+use tokio::task::JoinSet;
+use upstream::{Upstream, UpstreamNotification};
+
+const MAX_LIST_PAGES: usize = 50;
+/// How often `relay --gateway` checks its config file for changes.
+const CONFIG_POLL_SECS: u64 = 2;
+
+tokio::task_local! {
+    /// Span of the client request being handled, parent of upstream spans.
+    static CLIENT_SPAN: SpanContext;
+}
+
+fn rpc_error(code: i64, message: impl Into<String>) -> Value {
+    json!({ "code": code, "message": message.into() })
+}
+
+/// Split an upstream response into its result or its error object.
+fn into_result(response: Value) -> Result<Value, Value> {
+    if let Some(error) = response.get("error") {
+        return Err(error.clone());
+    }
+    Ok(response.get("result").cloned().unwrap_or(json!({})))
+}
+
+/// The File System server refuses to start without an allowed directory;
+/// grant the filesystem root when none was configured.
+fn apply_server_defaults(server: &mut GatewayServerConfig) {
+    if server.name == "File System" {
+        let has_path = server
+            .args
+            .iter()
+            .any(|arg| !arg.starts_with('-') && !arg.starts_with('@'));
+
+        if !has_path {
+            let root = if cfg!(windows) { "C:\\" } else { "/" };
+            eprintln!("[Relay] Auto-configuring File System access to {}", root);
+            server.args.push(root.to_string());
+        }
+    }
+}
+
+/// Connect to servers concurrently; those that fail are logged and left
+/// out. Returns the connections in config order.
+async fn connect_all(
+    servers: Vec<GatewayServerConfig>,
+    notification_tx: &mpsc::UnboundedSender<UpstreamNotification>,
+) -> Vec<Arc<Upstream>> {
+    let mut connecting = JoinSet::new();
+    for (index, server) in servers.into_iter().enumerate() {
+        let notification_tx = notification_tx.clone();
+        connecting.spawn(async move {
+            eprintln!("[Relay] Connecting to {}...", server.name);
+            let name = server.name.clone();
+            (index, name, Upstream::connect(server, notification_tx).await)
+        });
+    }
+
+    let mut connected = Vec::new();
+    while let Some(joined) = connecting.join_next().await {
+        match joined {
+            Ok((index, name, Ok(upstream))) => {
+                eprintln!("[Relay] Connected to {}", name);
+                connected.push((index, upstream));
+            }
+            Ok((_, name, Err(e))) => {
+                eprintln!("[Relay] Failed to connect to {}: {}", name, e);
+            }
+            Err(e) => eprintln!("[Relay] Connection task failed: {}", e),
+        }
+    }
+    connected.sort_by_key(|(index, _)| *index);
+    connected.into_iter().map(|(_, upstream)| upstream).collect()
+}
+
+/// Size of one message exchanged with an upstream, for context accounting.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TrafficSample {
+    pub server_id: String,
+    pub direction: TrafficDirection,
+    pub bytes: u64,
+    pub tokens: u64,
+    /// On the response to a `tools/call`: the call it completes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call: Option<CallSample>,
+}
+
+/// A completed tool call, for the per-tool usage breakdown.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CallSample {
+    pub tool: String,
+    pub request_bytes: u64,
+    pub request_tokens: u64,
+    pub latency_ms: u64,
+    pub is_error: bool,
+}
+
+/// A connected upstream, as reported to the app.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UpstreamSummary {
+    pub id: String,
+    pub name: String,
+    pub remote: bool,
+}
+
+/// A tool as the client sees it, and where it is routed.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ExposedTool {
+    pub exposed_name: String,
+    pub server_id: String,
+    pub server_name: String,
+    /// Name on the upstream server.
+    pub tool_name: String,
+}
+
+/// The client connection a message arrived on.
+#[derive(Debug, Clone)]
+pub struct ClientSession {
+    pub id: String,
+    /// Client name (HTTP bearer token owner, or `stdio`).
+    pub client: String,
+    pub tool_mode: ToolMode,
+}
+
+impl ClientSession {
+    pub fn new(client: &str, tool_mode: ToolMode) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            client: client.to_string(),
+            tool_mode,
+        }
+    }
+}
+
+/// A notification for connected clients.
+#[derive(Debug, Clone)]
+pub struct ClientNotification {
+    /// The session it concerns; `None` for every session.
+    pub session_id: Option<String>,
+    pub message: Value,
+}
+
+impl ClientNotification {
+    fn all(message: Value) -> Self {
+        Self { session_id: None, message }
+    }
+
+    pub fn is_for(&self, session_id: &str) -> bool {
+        self.session_id.as_deref().is_none_or(|id| id == session_id)
+    }
+}
+
+pub struct Gateway {
+    upstreams: RwLock<Vec<Arc<Upstream>>>,
+    resource_routes: Mutex<HashMap<String, String>>,
+    /// Unfiltered tool definitions per server id, from the last `tools/list`.
+    tool_definitions: Mutex<HashMap<String, Vec<Value>>>,
+    namespace: RwLock<NamespaceConfig>,
+    tool_routes: RwLock<RoutingTable>,
+    prompt_routes: RwLock<RoutingTable>,
+    /// Search indexes over exposed tools, built on first use in search mode,
+    /// keyed by the ids of the active toolsets they were built for.
+    tool_index: Mutex<HashMap<Vec<String>, Arc<ToolIndex>>>,
+    /// Toolsets as configured; each session starts from these.
+    toolsets: RwLock<Toolsets>,
+    /// Toolsets of sessions that switched one, by session id.
+    session_toolsets: RwLock<HashMap<String, Toolsets>>,
+    client_tx: broadcast::Sender<ClientNotification>,
+    /// Progress tokens handed to upstreams for calls in flight, with the
+    /// session and the client's own token they stand for.
+    progress_tokens: Mutex<HashMap<String, (String, Value)>>,
+    tokenizer: Tokenizer,
+    policy_tx: broadcast::Sender<PolicyHit>,
+    transform_tx: broadcast::Sender<TransformReport>,
+    approvals: ApprovalBroker,
+    call_tx: broadcast::Sender<ToolCallRecord>,
+    sample_tx: broadcast::Sender<RequestSample>,
+    traffic_tx: broadcast::Sender<TrafficSample>,
+    span_tx: broadcast::Sender<Span>,
+    response_cache: Mutex<ResponseCache>,
+    cache_tx: broadcast::Sender<CacheHit>,
+    limiter: CallLimiter,
+    limit_tx: broadcast::Sender<LimitEvent>,
+    /// Handed to upstreams connected on reload.
+    notification_tx: mpsc::UnboundedSender<UpstreamNotification>,
+    /// Reloads run one at a time.
+    reload_lock: Mutex<()>,
+}
+
+impl Gateway {
+    /// Connect to every enabled server in the config. Servers that fail to
+    /// connect are logged and left out.
+    pub async fn start(config: GatewayConfig) -> Arc<Self> {
+        let (client_tx, _) = broadcast::channel(256);
+        let (policy_tx, _) = broadcast::channel(256);
+        let (transform_tx, _) = broadcast::channel(256);
+        let (call_tx, _) = broadcast::channel(256);
+        let (sample_tx, _) = broadcast::channel(1024);
+        let (traffic_tx, _) = broadcast::channel(1024);
+        let (span_tx, _) = broadcast::channel(1024);
+        let (cache_tx, _) = broadcast::channel(256);
+        let (limit_tx, _) = broadcast::channel(256);
+        let tokenizer = config.tokenizer;
+        let namespace = config.namespace.clone();
+        let toolsets = Toolsets::new(config.toolsets.clone());
+        let approval_timeout_secs = config
+            .approval_timeout_secs
+            .unwrap_or(approval::DEFAULT_TIMEOUT_SECS);
+        let (notification_tx, mut notification_rx) =
+            mpsc::unbounded_channel::<UpstreamNotification>();
+
+        let servers = config
+            .servers
+            .into_iter()
+            .filter(|server| !server.disabled)
+            .map(|mut server| {
+                apply_server_defaults(&mut server);
+                server
+            })
+            .collect();
+        let connected = connect_all(servers, &notification_tx).await;
+
+        let gateway = Arc::new(Self {
+            upstreams: RwLock::new(connected),
+            resource_routes: Mutex::new(HashMap::new()),
+            tool_definitions: Mutex::new(HashMap::new()),
+            namespace: RwLock::new(namespace),
+            tool_routes: RwLock::new(RoutingTable::default()),
+            prompt_routes: RwLock::new(RoutingTable::default()),
+            tool_index: Mutex::new(HashMap::new()),
+            toolsets: RwLock::new(toolsets),
+            session_toolsets: RwLock::new(HashMap::new()),
+            client_tx,
+            progress_tokens: Mutex::new(HashMap::new()),
+            tokenizer,
+            policy_tx,
+            transform_tx,
+            approvals: ApprovalBroker::new(approval_timeout_secs),
+            call_tx,
+            sample_tx,
+            traffic_tx,
+            span_tx,
+            response_cache: Mutex::new(ResponseCache::default()),
+            cache_tx,
+            limiter: CallLimiter::default(),
+            limit_tx,
+            notification_tx,
+            reload_lock: Mutex::new(()),
+        });
+
+        let forward_tx = gateway.client_tx.clone();
+        let weak = Arc::downgrade(&gateway);
+        tokio::spawn(async move {
+            while let Some(notification) = notification_rx.recv().await {
+                let mut message = notification.message;
+                let method = message
+                    .get("method")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string();
+                // Cancellations reference upstream request ids, which mean
+                // nothing to the client.
+                if method == "notifications/cancelled" {
+                    continue;
+                }
+                let Some(gateway) = weak.upgrade() else {
+                    break;
+                };
+                let session_id = if method == "notifications/progress" {
+                    // Only the session that made the call hears its progress,
+                    // under its own token; progress for anything else is
+                    // dropped.
+                    let token = message.pointer("/params/progressToken").map(Value::to_string);
+                    let route = match token {
+                        Some(token) => gateway.progress_tokens.lock().await.get(&token).cloned(),
+                        None => None,
+                    };
+                    let Some((session_id, token)) = route else {
+                        continue;
+                    };
+                    message["params"]["progressToken"] = token;
+                    Some(session_id)
+                } else {
+                    None
+                };
+                // Routes, annotations and the search index all come from the
+                // listing that just went stale.
+                if method == "notifications/tools/list_changed" {
+                    gateway.tool_definitions.lock().await.clear();
+                    *gateway.tool_routes.write().await = RoutingTable::default();
+                    gateway.tool_index.lock().await.clear();
+                }
+                let _ = forward_tx.send(ClientNotification { session_id, message });
+            }
+        });
+
+        gateway
+    }
+
+    /// Apply a new config while running: servers that were added, enabled
+    /// or changed are (re)connected, removed or disabled ones are shut down,
+    /// toolsets are updated, and clients are told their lists changed.
+    /// Tokenizer and approval timeout changes need a restart. Returns
+    /// whether anything changed.
+    pub async fn reload(&self, config: GatewayConfig) -> bool {
+        let _reloading = self.reload_lock.lock().await;
+
+        let wanted: Vec<GatewayServerConfig> = config
+            .servers
+            .into_iter()
+            .filter(|server| !server.disabled)
+            .map(|mut server| {
+                apply_server_defaults(&mut server);
+                server
+            })
+            .collect();
+        let wanted_json: Vec<Option<Value>> = wanted.iter().map(|server| serde_json::to_value(server).ok()).collect();
+
+        let current = self.upstreams.read().await.clone();
+        let (kept, stale): (Vec<Arc<Upstream>>, Vec<Arc<Upstream>>) = current
+            .into_iter()
+            .partition(|upstream| wanted_json.contains(&serde_json::to_value(&upstream.config).ok()));
+        let to_connect: Vec<GatewayServerConfig> = wanted
+            .iter()
+            .filter(|server| !kept.iter().any(|upstream| upstream.config.id == server.id))
+            .cloned()
+            .collect();
+
+        let namespace_changed = {
+            let mut namespace = self.namespace.write().await;
+            let changed = serde_json::to_value(&*namespace).ok() != serde_json::to_value(&config.namespace).ok();
+            *namespace = config.namespace;
+            changed
+        };
+
+        let toolsets_changed = {
+            let mut toolsets = self.toolsets.write().await;
+            let mut changed = toolsets.update(config.toolsets.clone());
+            for session in self.session_toolsets.write().await.values_mut() {
+                changed |= session.update(config.toolsets.clone());
+            }
+            changed
+        };
+
+        if stale.is_empty() && to_connect.is_empty() && !namespace_changed {
+            if toolsets_changed {
+                self.tools_changed().await;
+            }
+            return toolsets_changed;
+        }
+
+        for upstream in &stale {
+            eprintln!("[Relay] Disconnecting {}", upstream.config.name);
+            upstream.shutdown().await;
+        }
+        let connected = connect_all(to_connect, &self.notification_tx).await;
+
+        // Keep the config order.
+        let mut upstreams = Vec::new();
+        for server in &wanted {
+            if let Some(upstream) = kept
+                .iter()
+                .chain(connected.iter())
+                .find(|upstream| upstream.config.id == server.id)
+            {
+                upstreams.push(Arc::clone(upstream));
+            }
+        }
+        *self.upstreams.write().await = upstreams;
+        self.lists_changed().await;
+
+        eprintln!(
+            "[Relay] Reloaded config: {} disconnected, {} connected",
+            stale.len(),
+            connected.len()
+        );
+        true
+    }
+
+    /// Shut down one upstream until the next reload. Returns false if it was
+    /// not connected.
+    pub async fn disconnect_upstream(&self, server_id: &str) -> bool {
+        let _reloading = self.reload_lock.lock().await;
+        let removed = {
+            let mut upstreams = self.upstreams.write().await;
+            let index = upstreams.iter().position(|upstream| upstream.config.id == server_id);
+            index.map(|index| upstreams.remove(index))
+        };
+        let Some(upstream) = removed else {
+            return false;
+        };
+
+        eprintln!("[Relay] Disconnecting {}", upstream.config.name);
+        upstream.shutdown().await;
+        self.lists_changed().await;
+        true
+    }
+
+    /// Reconnect one upstream with a changed config (e.g. new policies),
+    /// until the next reload. The old connection stays up if the new one
+    /// fails.
+    pub async fn replace_server(&self, mut server: GatewayServerConfig) -> Result<(), String> {
+        let _reloading = self.reload_lock.lock().await;
+        apply_server_defaults(&mut server);
+        let name = server.name.clone();
+        let id = server.id.clone();
+        let upstream = Upstream::connect(server, self.notification_tx.clone())
+            .await
+            .map_err(|e| format!("Failed to connect to {}: {}", name, e))?;
+
+        let previous = {
+            let mut upstreams = self.upstreams.write().await;
+            match upstreams.iter().position(|existing| existing.config.id == id) {
+                Some(index) => Some(std::mem::replace(&mut upstreams[index], upstream)),
+                None => {
+                    upstreams.push(upstream);
+                    None
+                }
+            }
+        };
+        if let Some(previous) = previous {
+            previous.shutdown().await;
+        }
+        self.lists_changed().await;
+        Ok(())
+    }
+
+    /// Config of a connected upstream.
+    pub async fn server_config(&self, server_id: &str) -> Option<GatewayServerConfig> {
+        self.upstream_by_id(server_id)
+            .await
+            .map(|upstream| upstream.config.clone())
+    }
+
+    /// Drop routes and caches, which are rebuilt on the next list or call,
+    /// and tell clients to list again.
+    async fn lists_changed(&self) {
+        self.tool_definitions.lock().await.clear();
+        self.tool_index.lock().await.clear();
+        self.response_cache.lock().await.invalidate(None, None);
+        self.resource_routes.lock().await.clear();
+        *self.tool_routes.write().await = RoutingTable::default();
+        *self.prompt_routes.write().await = RoutingTable::default();
+
+        for method in [
+            "notifications/tools/list_changed",
+            "notifications/resources/list_changed",
+            "notifications/prompts/list_changed",
+        ] {
+            let _ = self
+                .client_tx
+                .send(ClientNotification::all(json!({ "jsonrpc": "2.0", "method": method })));
+        }
+    }
+
+    /// Drop tool routes and the search index, and tell clients to list tools
+    /// again.
+    async fn tools_changed(&self) {
+        *self.tool_routes.write().await = RoutingTable::default();
+        self.tool_index.lock().await.clear();
+        let _ = self.client_tx.send(ClientNotification::all(
+            json!({ "jsonrpc": "2.0", "method": "notifications/tools/list_changed" }),
+        ));
+    }
+
+    /// Toolsets in effect for a session.
+    async fn toolsets_for(&self, session_id: &str) -> Toolsets {
+        let defaults = self.toolsets.read().await;
+        match self.session_toolsets.read().await.get(session_id) {
+            Some(toolsets) => toolsets.clone(),
+            None => defaults.clone(),
+        }
+    }
+
+    /// Activate or deactivate a toolset by name or id for one session.
+    /// Returns whether anything changed.
+    pub async fn set_toolset_active(&self, session_id: &str, name: &str, active: bool) -> Result<bool, String> {
+        let changed = {
+            let defaults = self.toolsets.read().await;
+            let mut sessions = self.session_toolsets.write().await;
+            let toolsets = sessions
+                .entry(session_id.to_string())
+                .or_insert_with(|| defaults.clone());
+            toolsets.set_active(name, active)?
+        };
+        if changed {
+            let _ = self.client_tx.send(ClientNotification {
+                session_id: Some(session_id.to_string()),
+                message: json!({ "jsonrpc": "2.0", "method": "notifications/tools/list_changed" }),
+            });
+        }
+        Ok(changed)
+    }
+
+    /// Names of the toolsets active for a session.
+    pub async fn active_toolsets(&self, session_id: &str) -> Vec<String> {
+        self.toolsets_for(session_id).await.active_names()
+    }
+
+    /// Forget a closed session's toolsets.
+    pub async fn end_session(&self, session_id: &str) {
+        self.session_toolsets.write().await.remove(session_id);
+    }
+
+    /// `tools/list` output of each connected upstream after its tool filter,
+    /// keyed by server id.
+    pub async fn upstream_tools(&self) -> HashMap<String, Vec<Value>> {
+        let mut tools: HashMap<String, Vec<Value>> = self
+            .upstreams
+            .read()
+            .await
+            .iter()
+            .map(|upstream| (upstream.config.id.clone(), Vec::new()))
+            .collect();
+
+        for (upstream, tool) in self.visible_tools().await {
+            tools.entry(upstream.config.id.clone()).or_default().push(tool);
+        }
+
+        tools
+    }
+
+    /// Tool results cut down or rejected by a response policy.
+    pub fn subscribe_policy_hits(&self) -> broadcast::Receiver<PolicyHit> {
+        self.policy_tx.subscribe()
+    }
+
+    /// Tool results rewritten by a server's transforms.
+    pub fn subscribe_transforms(&self) -> broadcast::Receiver<TransformReport> {
+        self.transform_tx.subscribe()
+    }
+
+    /// Every `tools/call` routed to an upstream, for the audit log.
+    pub fn subscribe_tool_calls(&self) -> broadcast::Receiver<ToolCallRecord> {
+        self.call_tx.subscribe()
+    }
+
+    /// Latency and outcome of every request sent to an upstream.
+    pub fn subscribe_request_samples(&self) -> broadcast::Receiver<RequestSample> {
+        self.sample_tx.subscribe()
+    }
+
+    /// Size of every request and response exchanged with an upstream.
+    pub fn subscribe_traffic(&self) -> broadcast::Receiver<TrafficSample> {
+        self.traffic_tx.subscribe()
+    }
+
+    /// Every tool call answered from the response cache.
+    pub fn subscribe_cache_hits(&self) -> broadcast::Receiver<CacheHit> {
+        self.cache_tx.subscribe()
+    }
+
+    /// Every tool call queued or refused by a rate or concurrency limit.
+    pub fn subscribe_limit_events(&self) -> broadcast::Receiver<LimitEvent> {
+        self.limit_tx.subscribe()
+    }
+
+    /// Drop cached tool results for a server (or all of them), optionally
+    /// only for one upstream tool. Returns how many were dropped.
+    pub async fn clear_cache(&self, server_id: Option<&str>, tool: Option<&str>) -> usize {
+        self.response_cache.lock().await.invalidate(server_id, tool)
+    }
+
+    /// A span for every client request and every upstream request it makes.
+    pub fn subscribe_spans(&self) -> broadcast::Receiver<Span> {
+        self.span_tx.subscribe()
+    }
+
+    pub async fn connected_servers(&self) -> Vec<UpstreamSummary> {
+        self.upstreams
+            .read()
+            .await
+            .iter()
+            .map(|upstream| UpstreamSummary {
+                id: upstream.config.id.clone(),
+                name: upstream.config.name.clone(),
+                remote: upstream.config.is_remote(),
+            })
+            .collect()
+    }
+
+    /// Tool calls waiting for approval, and how they were decided.
+    pub fn approvals(&self) -> &ApprovalBroker {
+        &self.approvals
+    }
+
+    /// Notifications addressed to connected clients.
+    pub fn subscribe(&self) -> broadcast::Receiver<ClientNotification> {
+        self.client_tx.subscribe()
+    }
+
+    pub async fn shutdown(&self) {
+        for upstream in self.upstreams.write().await.drain(..) {
+            upstream.shutdown().await;
+        }
+    }
+
+    /// Handle one message from the client. Returns the response for
+    /// requests, `None` for notifications and responses.
+    pub async fn handle_message(&self, message: Value, session: &ClientSession) -> Option<Value> {
+        let id = message.get("id").cloned()?;
+        let method = message.get("method").and_then(Value::as_str)?.to_string();
+        let params = message.get("params").cloned().unwrap_or(json!({}));
+
+        let outcome = if self.span_tx.receiver_count() > 0 {
+            self.handle_traced(&method, params, session).await
+        } else {
+            self.handle_request(&method, params, session).await
+        };
+        Some(match outcome {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+        })
+    }
+
+    /// Handle a request inside a server span, continuing the client's trace
+    /// when it sends a `traceparent` in `_meta`.
+    async fn handle_traced(&self, method: &str, params: Value, session: &ClientSession) -> Result<Value, Value> {
+        let remote_parent = params
+            .pointer("/_meta/traceparent")
+            .and_then(Value::as_str)
+            .and_then(SpanContext::from_traceparent);
+        let context = SpanContext::child_of(remote_parent.as_ref());
+        let mut span = Span::start(method, SpanKind::Server, context.clone(), remote_parent.as_ref());
+        span.set("rpc.system", "jsonrpc");
+        span.set("rpc.method", method);
+        span.set("relay.client", session.client.as_str());
+        if let Some(tool) = params.get("name").and_then(Value::as_str).filter(|_| method == "tools/call") {
+            // Name the tool behind `invoke_tool` rather than the meta-tool.
+            let invoked = params.pointer("/arguments/name").and_then(Value::as_str);
+            match invoked.filter(|_| session.tool_mode == ToolMode::Search && tool == search::INVOKE_TOOL) {
+                Some(invoked) => span.set("mcp.tool.name", invoked),
+                None => span.set("mcp.tool.name", tool),
+            }
+        }
+        let request = json!({ "method": method, "params": &params });
+        span.set("relay.tokens.request", self.tokenizer.count(&context_text(&request)));
+
+        let outcome = CLIENT_SPAN.scope(context, self.handle_request(method, params, session)).await;
+
+        let error = match &outcome {
+            Ok(result) => {
+                let response = json!({ "result": result });
+                span.set("relay.tokens.response", self.tokenizer.count(&context_text(&response)));
+                result
+                    .get("isError")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false)
+                    .then(|| "Tool returned an error".to_string())
+            }
+            Err(error) => {
+                if let Some(code) = error.get("code").and_then(Value::as_i64) {
+                    span.set("rpc.jsonrpc.error_code", code as u64);
+                }
+                Some(error.get("message").and_then(Value::as_str).unwrap_or("Error").to_string())
+            }
+        };
+        span.set("relay.status", if error.is_some() { "error" } else { "ok" });
+        let _ = self.span_tx.send(span.finish(error));
+        outcome
+    }
+
+    async fn handle_request(
+        &self,
+        method: &str,
+        params: Value,
+        session: &ClientSession,
+    ) -> Result<Value, Value> {
+        match method {
+            "initialize" => Ok(self.initialize_result(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => match session.tool_mode {
+                ToolMode::Full => self.list_tools(session).await,
+                ToolMode::Search => self.list_meta_tools(session).await,
+            },
+            "tools/call" if self.is_toolset_call(&params).await => self.call_toolset_tool(params, session).await,
+            "tools/call" => match session.tool_mode {
+                ToolMode::Full => self.call_tool(params, session).await,
+                ToolMode::Search => self.call_meta_tool(params, session).await,
+            },
+            "resources/list" => self.list_resources().await,
+            "resources/templates/list" => {
+                let templates = self
+                    .aggregate("resources", "resources/templates/list", "resourceTemplates")
+                    .await;
+                Ok(json!({
+                    "resourceTemplates": templates.into_iter().map(|(_, t)| t).collect::<Vec<_>>()
+                }))
+            }
+            "resources/read" => self.read_resource(params).await,
+            "prompts/list" => self.list_prompts().await,
+            "prompts/get" => self.get_prompt(params).await,
+            "logging/setLevel" => {
+                for upstream in self.upstreams.read().await.iter() {
+                    if upstream.supports("logging") {
+                        let _ = self.request_upstream(upstream, "logging/setLevel", params.clone()).await;
+                    }
+                }
+                Ok(json!({}))
+            }
+            _ => Err(rpc_error(-32601, format!("Method not found: {}", method))),
+        }
+    }
+
+    fn initialize_result(&self, params: &Value) -> Value {
+        let protocol_version = params
+            .get("protocolVersion")
+            .and_then(Value::as_str)
+            .unwrap_or(upstream::PROTOCOL_VERSION);
+
+        json!({
+            "protocolVersion": protocol_version,
+            "capabilities": {
+                "tools": { "listChanged": true },
+                "resources": { "listChanged": true },
+                "prompts": { "listChanged": true },
+                "logging": {}
+            },
+            "serverInfo": {
+                "name": "Relay Gateway",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        })
+    }
+
+    /// Send a request to an upstream, recording its latency and outcome.
+    async fn request_upstream(&self, upstream: &Upstream, method: &str, params: Value) -> Result<Value, String> {
+        let tool = (method == "tools/call")
+            .then(|| params.get("name").and_then(Value::as_str).map(str::to_string))
+            .flatten();
+        // Only measured when someone is listening: token counting is not free.
+        let measure = self.traffic_tx.receiver_count() > 0;
+        let request_size = measure.then(|| {
+            let request = json!({ "method": method, "params": &params });
+            self.send_traffic(upstream, TrafficDirection::Outbound, &request, None)
+        });
+
+        let span = (self.span_tx.receiver_count() > 0).then(|| {
+            let parent = CLIENT_SPAN.try_with(SpanContext::clone).ok();
+            let context = SpanContext::child_of(parent.as_ref());
+            let mut span = Span::start(method, SpanKind::Client, context, parent.as_ref());
+            span.set("rpc.system", "jsonrpc");
+            span.set("rpc.method", method);
+            span.set("relay.server.id", upstream.config.id.as_str());
+            span.set("relay.server.name", upstream.config.name.as_str());
+            if let Some(tool) = &tool {
+                span.set("mcp.tool.name", tool.as_str());
+            }
+            let request = json!({ "method": method, "params": &params });
+            span.set("relay.tokens.request", self.tokenizer.count(&context_text(&request)));
+            span
+        });
+
+        let started = std::time::Instant::now();
+        let response = upstream.request(method, params).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let is_error = match &response {
+            Ok(response) => {
+                response.get("error").is_some()
+                    || response
+                        .pointer("/result/isError")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false)
+            }
+            Err(_) => true,
+        };
+        if let (Some((request_bytes, request_tokens)), Ok(response)) = (request_size, &response) {
+            let call = tool.clone().map(|tool| CallSample {
+                tool,
+                request_bytes,
+                request_tokens,
+                latency_ms,
+                is_error,
+            });
+            self.send_traffic(upstream, TrafficDirection::Inbound, response, call);
+        }
+        if let Some(mut span) = span {
+            let error = match &response {
+                Ok(response) => {
+                    span.set("relay.tokens.response", self.tokenizer.count(&context_text(response)));
+                    response
+                        .pointer("/error/message")
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                        .or_else(|| is_error.then(|| "Tool returned an error".to_string()))
+                }
+                Err(e) => Some(e.clone()),
+            };
+            span.set("relay.status", if is_error { "error" } else { "ok" });
+            let _ = self.span_tx.send(span.finish(error));
+        }
+        let _ = self.sample_tx.send(RequestSample {
+            server_id: upstream.config.id.clone(),
+            method: method.to_string(),
+            tool,
+            latency_ms,
+            is_error,
+        });
+
+        response
+    }
+
+    /// Report a message's size; returns it as (bytes, tokens).
+    fn send_traffic(
+        &self,
+        upstream: &Upstream,
+        direction: TrafficDirection,
+        message: &Value,
+        call: Option<CallSample>,
+    ) -> (u64, u64) {
+        let bytes = message.to_string().len() as u64;
+        let tokens = self.tokenizer.count(&context_text(message));
+        let _ = self.traffic_tx.send(TrafficSample {
+            server_id: upstream.config.id.clone(),
+            direction,
+            bytes,
+            tokens,
+            call,
+        });
+        (bytes, tokens)
+    }
+
+    /// Every page of a list method from one upstream.
+    async fn list_upstream(&self, upstream: &Upstream, method: &str, key: &str) -> Vec<Value> {
+        let mut items = Vec::new();
+        let mut cursor: Option<Value> = None;
+
+        for _page in 0..MAX_LIST_PAGES {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+
+            let result = match self.request_upstream(upstream, method, params).await.map(into_result) {
+                Ok(Ok(result)) => result,
+                Ok(Err(error)) => {
+                    eprintln!("[Relay] {} failed for {}: {}", method, upstream.config.name, error);
+                    break;
+                }
+                Err(e) => {
+                    eprintln!("[Relay] {} failed for {}: {}", method, upstream.config.name, e);
+                    break;
+                }
+            };
+
+            if let Some(page) = result.get(key).and_then(Value::as_array) {
+                items.extend(page.iter().cloned());
+            }
+
+            cursor = result.get("nextCursor").filter(|c| !c.is_null()).cloned();
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        items
+    }
+
+    /// Collect a paginated list (`tools`, `resources`, ...) from every
+    /// upstream advertising `capability`, tagged with the upstream it came from.
+    async fn aggregate(
+        &self,
+        capability: &str,
+        method: &str,
+        key: &str,
+    ) -> Vec<(Arc<Upstream>, Value)> {
+        let upstreams = self.upstreams.read().await.clone();
+        let mut items = Vec::new();
+
+        for upstream in upstreams {
+            if !upstream.supports(capability) {
+                continue;
+            }
+            let page = self.list_upstream(&upstream, method, key).await;
+            items.extend(page.into_iter().map(|item| (Arc::clone(&upstream), item)));
+        }
+
+        items
+    }
+
+    /// Tools of every upstream that pass its tool filter, with overrides
+    /// applied. Also refreshes the definitions used to check calls against
+    /// read-only filters.
+    async fn visible_tools(&self) -> Vec<(Arc<Upstream>, Value)> {
+        let tools = self.aggregate("tools", "tools/list", "tools").await;
+
+        let mut definitions: HashMap<String, Vec<Value>> = HashMap::new();
+        for (upstream, tool) in &tools {
+            definitions
+                .entry(upstream.config.id.clone())
+                .or_default()
+                .push(tool.clone());
+        }
+        *self.tool_definitions.lock().await = definitions;
+
+        tools
+            .into_iter()
+            .filter(|(upstream, tool)| {
+                upstream
+                    .config
+                    .tool_filter
+                    .as_ref()
+                    .is_none_or(|filter| filter.allows(tool))
+            })
+            .map(|(upstream, mut tool)| {
+                if let Some(tool_overrides) = &upstream.config.tool_overrides {
+                    overrides::apply(tool_overrides, &mut tool);
+                }
+                (upstream, tool)
+            })
+            .collect()
+    }
+
+    /// Overrides whose upstream tool has disappeared, as (server id, tool).
+    pub async fn stale_overrides(&self) -> Vec<(String, String)> {
+        self.visible_tools().await;
+        let definitions = self.tool_definitions.lock().await;
+
+        let mut stale = Vec::new();
+        for upstream in self.upstreams.read().await.iter() {
+            let Some(tool_overrides) = &upstream.config.tool_overrides else {
+                continue;
+            };
+            let tools = definitions
+                .get(&upstream.config.id)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for tool in overrides::stale(tool_overrides, tools) {
+                stale.push((upstream.config.id.clone(), tool.to_string()));
+            }
+        }
+        stale
+    }
+
+    /// Upstream definition of `tool_name`, from the last `tools/list` or
+    /// fetched now.
+    async fn tool_definition(&self, upstream: &Arc<Upstream>, tool_name: &str) -> Option<Value> {
+        let cached = self
+            .tool_definitions
+            .lock()
+            .await
+            .get(&upstream.config.id)
+            .cloned();
+        let definitions = match cached {
+            Some(definitions) => definitions,
+            None => self.list_upstream(upstream, "tools/list", "tools").await,
+        };
+
+        definitions
+            .into_iter()
+            .find(|tool| tool.get("name").and_then(Value::as_str) == Some(tool_name))
+    }
+
+    /// Whether the upstream's filter lets `tool_name` through.
+    async fn tool_allowed(&self, upstream: &Arc<Upstream>, tool_name: &str) -> bool {
+        let Some(filter) = &upstream.config.tool_filter else {
+            return true;
+        };
+        if !filter.needs_definition() {
+            return filter.allows_name(tool_name);
+        }
+
+        self.tool_definition(upstream, tool_name)
+            .await
+            .is_some_and(|tool| filter.allows(&tool))
+    }
+
+    /// Apply the upstream's approval policy to a call, asking the approver
+    /// and waiting when the policy says so. Returns the outcome when approval
+    /// was needed, and the reason on refusal.
+    async fn check_approval(
+        &self,
+        upstream: &Arc<Upstream>,
+        tool_name: &str,
+        arguments: &Value,
+        session: &ClientSession,
+    ) -> Result<Option<ApprovalOutcome>, (ApprovalOutcome, String)> {
+        let definition = self.tool_definition(upstream, tool_name).await;
+        let mode = approval::mode_for(
+            upstream.config.approval_policy.as_ref(),
+            tool_name,
+            definition.as_ref(),
+        );
+
+        if mode == ApprovalMode::AlwaysAllow {
+            return Ok(None);
+        }
+        if mode == ApprovalMode::AskOncePerSession
+            && self
+                .approvals
+                .granted(&session.id, &upstream.config.id, tool_name)
+                .await
+        {
+            return Ok(Some(ApprovalOutcome::Approved));
+        }
+
+        let request = ApprovalRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            server_id: upstream.config.id.clone(),
+            server_name: upstream.config.name.clone(),
+            tool: tool_name.to_string(),
+            arguments: redaction::redacted(arguments),
+            client: session.client.clone(),
+            session_id: session.id.clone(),
+            mode,
+            requested_at: chrono::Utc::now().to_rfc3339(),
+            timeout_secs: self.approvals.timeout_secs(),
+        };
+
+        if mode == ApprovalMode::Deny {
+            self.approvals.record(request, ApprovalOutcome::Denied);
+            return Err((
+                ApprovalOutcome::Denied,
+                format!("Relay policy denies calls to {}.", tool_name),
+            ));
+        }
+
+        let outcome = self.approvals.request(request).await;
+        let reason = match outcome {
+            ApprovalOutcome::Approved => return Ok(Some(outcome)),
+            ApprovalOutcome::Denied => format!("The user denied this call to {}.", tool_name),
+            ApprovalOutcome::TimedOut => format!(
+                "No approval for {} within {} seconds; the call was not made.",
+                tool_name,
+                self.approvals.timeout_secs()
+            ),
+            // A `relay --gateway` instance with the app closed: nobody can
+            // answer, and a call that needs approval is never made without it.
+            ApprovalOutcome::NoApprover => format!(
+                "{} requires approval in the Relay app, which is not running or not connected to this gateway. Open Relay to approve it, or change the server's approval policy.",
+                tool_name
+            ),
+        };
+        Err((outcome, reason))
+    }
+
+    /// Visible tools with their exposed names, limited to those `toolsets`
+    /// allow when given; also rebuilds the tool routing table. Names are
+    /// assigned over every visible tool, so they stay the same whichever
+    /// toolsets a session has active. Each entry is (upstream, tool as
+    /// exposed).
+    async fn exposed_tools(&self, toolsets: Option<&Toolsets>) -> Vec<(Arc<Upstream>, Value)> {
+        let tools = self.visible_tools().await;
+        let allowed: Vec<bool> = tools
+            .iter()
+            .map(|(upstream, tool)| {
+                let Some(toolsets) = toolsets else {
+                    return true;
+                };
+                let name = tool.get("name").and_then(Value::as_str).unwrap_or("");
+                let tool_name = match &upstream.config.tool_overrides {
+                    Some(tool_overrides) => overrides::upstream_name(tool_overrides, name),
+                    None => Some(name.to_string()),
+                };
+                tool_name.is_some_and(|tool_name| toolsets.allows(&upstream.config.id, &tool_name))
+            })
+            .collect();
+
+        let requests: Vec<NameRequest> = tools
+            .iter()
+            .map(|(upstream, tool)| NameRequest {
+                server_id: &upstream.config.id,
+                prefix: upstream.config.namespace_prefix(),
+                name: tool.get("name").and_then(Value::as_str).unwrap_or(""),
+            })
+            .collect();
+        let (names, table) = namespace::assign(&*self.namespace.read().await, &requests);
+        *self.tool_routes.write().await = table;
+
+        tools
+            .into_iter()
+            .zip(names)
+            .zip(allowed)
+            .filter(|(_, allowed)| *allowed)
+            .map(|(((upstream, mut tool), exposed_name), _)| {
+                let description = tool.get("description").and_then(Value::as_str).unwrap_or("");
+                let exposed_description = format!("[{}] {}", upstream.config.name, description);
+                tool["name"] = json!(exposed_name);
+                tool["description"] = json!(exposed_description);
+                (upstream, tool)
+            })
+            .collect()
+    }
+
+    /// Final exposed tool names with the server and upstream tool behind each.
+    pub async fn exposed_tool_names(&self) -> Vec<ExposedTool> {
+        let tools = self.exposed_tools(None).await;
+        let routes = self.tool_routes.read().await;
+
+        tools
+            .iter()
+            .filter_map(|(upstream, tool)| {
+                let exposed_name = tool.get("name")?.as_str()?.to_string();
+                let (_, local_name) = routes.resolve(&exposed_name)?;
+                let tool_name = upstream
+                    .config
+                    .tool_overrides
+                    .as_ref()
+                    .and_then(|tool_overrides| overrides::upstream_name(tool_overrides, local_name))
+                    .unwrap_or_else(|| local_name.clone());
+                Some(ExposedTool {
+                    exposed_name,
+                    server_id: upstream.config.id.clone(),
+                    server_name: upstream.config.name.clone(),
+                    tool_name,
+                })
+            })
+            .collect()
+    }
+
+    async fn list_tools(&self, session: &ClientSession) -> Result<Value, Value> {
+        let toolsets = self.toolsets_for(&session.id).await;
+        let mut tools: Vec<Value> = self
+            .exposed_tools(Some(&toolsets))
+            .await
+            .into_iter()
+            .map(|(_, tool)| tool)
+            .collect();
+        if !toolsets.is_empty() {
+            tools.extend(toolsets::meta_tools());
+        }
+
+        Ok(json!({ "tools": tools }))
+    }
+
+    /// The search index over the tools a session can see, rebuilt after
+    /// lists change.
+    async fn tool_index(&self, session: &ClientSession) -> Arc<ToolIndex> {
+        let toolsets = self.toolsets_for(&session.id).await;
+        let key = toolsets.active_ids();
+        if let Some(index) = self.tool_index.lock().await.get(&key) {
+            return Arc::clone(index);
+        }
+        let tools = self
+            .exposed_tools(Some(&toolsets))
+            .await
+            .into_iter()
+            .map(|(_, tool)| tool)
+            .collect();
+        let index = Arc::new(ToolIndex::build(tools));
+        self.tool_index.lock().await.insert(key, Arc::clone(&index));
+        index
+    }
+
+    async fn list_meta_tools(&self, session: &ClientSession) -> Result<Value, Value> {
+        let index = self.tool_index(session).await;
+        let mut tools = search::meta_tools(index.tool_count());
+        if !self.toolsets.read().await.is_empty() {
+            tools.extend(toolsets::meta_tools());
+        }
+        Ok(json!({ "tools": tools }))
+    }
+
+    async fn is_toolset_call(&self, params: &Value) -> bool {
+        params
+            .get("name")
+            .and_then(Value::as_str)
+            .is_some_and(toolsets::is_meta_tool)
+            && !self.toolsets.read().await.is_empty()
+    }
+
+    async fn call_toolset_tool(&self, params: Value, session: &ClientSession) -> Result<Value, Value> {
+        let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+        let text = |text: String, is_error: bool| {
+            json!({
+                "content": [{ "type": "text", "text": text }],
+                "isError": is_error
+            })
+        };
+        if name == toolsets::LIST_TOOLSETS {
+            let summary = self.toolsets_for(&session.id).await.summary();
+            return Ok(text(serde_json::to_string_pretty(&summary).unwrap_or_default(), false));
+        }
+
+        let toolset = params
+            .pointer("/arguments/name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| rpc_error(-32602, format!("{} requires a \"name\" argument", name)))?;
+        let active = name == toolsets::ENABLE_TOOLSET;
+        match self.set_toolset_active(&session.id, toolset, active).await {
+            Ok(changed) => {
+                let state = if active { "active" } else { "inactive" };
+                let message = if changed {
+                    format!("Toolset {} is now {}; the tool list has been updated.", toolset, state)
+                } else {
+                    format!("Toolset {} was already {}.", toolset, state)
+                };
+                Ok(text(message, false))
+            }
+            Err(e) => Ok(text(e, true)),
+        }
+    }
+
+    /// `tools/call` in search mode. Exposed tools stay callable by name, so
+    /// clients that remember a tool need not go through `invoke_tool`.
+    async fn call_meta_tool(&self, params: Value, session: &ClientSession) -> Result<Value, Value> {
+        let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+        if !search::is_meta_tool(name) {
+            return self.call_tool(params, session).await;
+        }
+
+        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+        let text = |text: String, is_error: bool| {
+            json!({
+                "content": [{ "type": "text", "text": text }],
+                "isError": is_error
+            })
+        };
+        let required = |key: &str| {
+            arguments
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| rpc_error(-32602, format!("{} requires a \"{}\" argument", name, key)))
+        };
+
+        match name {
+            search::SEARCH_TOOLS => {
+                let query = required("query")?;
+                let limit = arguments
+                    .get("limit")
+                    .and_then(Value::as_u64)
+                    .map(|limit| limit as usize)
+                    .unwrap_or(search::DEFAULT_RESULTS);
+                let hits = self.tool_index(session).await.search(&query, limit);
+                if hits.is_empty() {
+                    return Ok(text(format!("No tools match \"{}\".", query), false));
+                }
+                let hits = serde_json::to_string_pretty(&hits).unwrap_or_default();
+                Ok(text(hits, false))
+            }
+            search::DESCRIBE_TOOL => {
+                let tool_name = required("name")?;
+                match self.tool_index(session).await.get(&tool_name) {
+                    Some(tool) => Ok(text(serde_json::to_string_pretty(tool).unwrap_or_default(), false)),
+                    None => Ok(text(format!("Unknown tool {}; use {} to find tools.", tool_name, search::SEARCH_TOOLS), true)),
+                }
+            }
+            _ => {
+                let tool_name = required("name")?;
+                let mut forwarded = json!({ "name": tool_name, "arguments": arguments.get("arguments").cloned().unwrap_or(json!({})) });
+                if let Some(meta) = params.get("_meta") {
+                    forwarded["_meta"] = meta.clone();
+                }
+                self.call_tool(forwarded, session).await
+            }
+        }
+    }
+
+    async fn upstream_by_id(&self, server_id: &str) -> Option<Arc<Upstream>> {
+        self.upstreams
+            .read()
+            .await
+            .iter()
+            .find(|upstream| upstream.config.id == server_id)
+            .cloned()
+    }
+
+    /// Look up an exposed tool name, listing tools first if the client called
+    /// without listing.
+    async fn route_tool(&self, exposed_name: &str) -> Option<(Arc<Upstream>, String)> {
+        if self.tool_routes.read().await.is_empty() {
+            self.exposed_tools(None).await;
+        }
+        let (server_id, local_name) = self.tool_routes.read().await.resolve(exposed_name).cloned()?;
+        Some((self.upstream_by_id(&server_id).await?, local_name))
+    }
+
+    async fn call_tool(&self, mut params: Value, session: &ClientSession) -> Result<Value, Value> {
+        let exposed_name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string();
+
+        let not_found = || {
+            rpc_error(
+                -32602,
+                format!("Tool {} not found in any active server.", exposed_name),
+            )
+        };
+
+        let (upstream, local_name) = self.route_tool(&exposed_name).await.ok_or_else(not_found)?;
+        let tool_name = match &upstream.config.tool_overrides {
+            Some(tool_overrides) => {
+                overrides::upstream_name(tool_overrides, &local_name).ok_or_else(not_found)?
+            }
+            None => local_name,
+        };
+
+        if !self.tool_allowed(&upstream, &tool_name).await
+            || !self.toolsets_for(&session.id).await.allows(&upstream.config.id, &tool_name)
+        {
+            return Err(not_found());
+        }
+
+        let started = std::time::Instant::now();
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+
+        let (outcome, status, approval) = match self
+            .check_approval(&upstream, &tool_name, &arguments, session)
+            .await
+        {
+            Ok(approval) => {
+                let (outcome, status) = match self.cached_result(&upstream, &tool_name, &arguments).await {
+                    Some(result) => (Ok(result), CallStatus::Ok),
+                    None => match self.limit_call(&upstream, &tool_name).await {
+                        Ok(_permit) => {
+                            params["name"] = json!(tool_name);
+                            let progress = self.relay_progress(&mut params, session).await;
+                            let (outcome, status) = self.forward_tool_call(&upstream, &tool_name, params).await;
+                            if let Some(token) = progress {
+                                self.progress_tokens.lock().await.remove(&token);
+                            }
+                            if let (Ok(result), CallStatus::Ok) = (&outcome, status) {
+                                self.cache_result(&upstream, &tool_name, &arguments, result).await;
+                            }
+                            (outcome, status)
+                        }
+                        Err(error) => (Err(error), CallStatus::Throttled),
+                    },
+                };
+                (outcome, status, approval)
+            }
+            Err((approval, reason)) => {
+                let refusal = json!({
+                    "content": [{ "type": "text", "text": reason }],
+                    "isError": true
+                });
+                (Ok(refusal), CallStatus::Denied, Some(approval))
+            }
+        };
+
+        let _ = self.call_tx.send(ToolCallRecord {
+            timestamp,
+            client: session.client.clone(),
+            server_id: upstream.config.id.clone(),
+            server_name: upstream.config.name.clone(),
+            tool: tool_name,
+            arguments,
+            status,
+            duration_ms: started.elapsed().as_millis() as u64,
+            approval: approval.map(|outcome| outcome.as_str().to_string()),
+        });
+
+        outcome
+    }
+
+    /// Swap the client's progress token for one of the gateway's, so
+    /// progress from the upstream reaches only this session and tokens from
+    /// different sessions cannot collide. Returns the key to release once
+    /// the call is done.
+    async fn relay_progress(&self, params: &mut Value, session: &ClientSession) -> Option<String> {
+        let token = params.pointer_mut("/_meta/progressToken")?;
+        let relayed = json!(format!("relay-{}", uuid::Uuid::new_v4().simple()));
+        let client_token = std::mem::replace(token, relayed.clone());
+        let key = relayed.to_string();
+        self.progress_tokens
+            .lock()
+            .await
+            .insert(key.clone(), (session.id.clone(), client_token));
+        Some(key)
+    }
+
+    /// Whether the upstream's cache policy covers `tool_name` and its
+    /// annotations allow caching.
+    async fn cacheable(&self, upstream: &Arc<Upstream>, tool_name: &str) -> bool {
+        let Some(policy) = &upstream.config.cache_policy else {
+            return false;
+        };
+        if !policy.covers(tool_name) {
+            return false;
+        }
+        let definition = self.tool_definition(upstream, tool_name).await;
+        cache::annotations_allow(definition.as_ref())
+    }
+
+    async fn cached_result(&self, upstream: &Arc<Upstream>, tool_name: &str, arguments: &Value) -> Option<Value> {
+        if !self.cacheable(upstream, tool_name).await {
+            return None;
+        }
+        let result = self
+            .response_cache
+            .lock()
+            .await
+            .get(&upstream.config.id, tool_name, arguments)?;
+        let _ = self.cache_tx.send(CacheHit {
+            server_id: upstream.config.id.clone(),
+            tool: tool_name.to_string(),
+        });
+        Some(result)
+    }
+
+    async fn cache_result(&self, upstream: &Arc<Upstream>, tool_name: &str, arguments: &Value, result: &Value) {
+        let Some(policy) = &upstream.config.cache_policy else {
+            return;
+        };
+        if !self.cacheable(upstream, tool_name).await {
+            return;
+        }
+        self.response_cache
+            .lock()
+            .await
+            .insert(&upstream.config.id, policy, tool_name, arguments, result.clone());
+    }
+
+    /// Wait for the upstream's rate and concurrency limits to admit a call,
+    /// or refuse it. The permit is held until the call completes.
+    async fn limit_call(&self, upstream: &Arc<Upstream>, tool_name: &str) -> Result<Option<CallPermit>, Value> {
+        let Some(policy) = &upstream.config.rate_limit else {
+            return Ok(None);
+        };
+        let outcome = self
+            .limiter
+            .acquire(&upstream.config.id, &upstream.config.name, tool_name, policy)
+            .await;
+
+        let event = match &outcome {
+            Ok(permit) if permit.waited.is_zero() => None,
+            Ok(permit) => Some(LimitOutcome::Queued {
+                wait_ms: permit.waited.as_millis() as u64,
+            }),
+            Err(error) => {
+                eprintln!("[Relay] {}", error.reason);
+                Some(LimitOutcome::Rejected {
+                    reason: error.reason.clone(),
+                })
+            }
+        };
+        if let Some(outcome) = event {
+            let _ = self.limit_tx.send(LimitEvent {
+                server_id: upstream.config.id.clone(),
+                tool: tool_name.to_string(),
+                outcome,
+            });
+        }
+
+        outcome.map(Some).map_err(|error| error.to_rpc_error())
+    }
+
+    /// Send an approved call upstream and enforce the response policy.
+    async fn forward_tool_call(
+        &self,
+        upstream: &Arc<Upstream>,
+        tool_name: &str,
+        params: Value,
+    ) -> (Result<Value, Value>, CallStatus) {
+        let response = match self.request_upstream(upstream, "tools/call", params).await {
+            Ok(response) => response,
+            Err(e) => return (Err(rpc_error(-32603, e)), CallStatus::Error),
+        };
+        let mut result = match into_result(response) {
+            Ok(result) => result,
+            Err(error) => return (Err(error), CallStatus::Error),
+        };
+
+        // Transforms run first, so response budgets apply to what the
+        // client will actually receive.
+        if let Some(policy) = &upstream.config.transforms {
+            let (id, name) = (&upstream.config.id, &upstream.config.name);
+            if let Some(report) = transforms::apply(policy, id, name, tool_name, &mut result, self.tokenizer).await {
+                let _ = self.transform_tx.send(report);
+            }
+        }
+
+        let mut rejected = false;
+        if let Some(policy) = &upstream.config.response_policy {
+            if let Some(hit) = policy::apply(policy, &upstream.config.id, tool_name, &mut result, self.tokenizer) {
+                eprintln!(
+                    "[Relay] Response policy hit for {}/{}: {} -> {} tokens",
+                    upstream.config.name, tool_name, hit.tokens_before, hit.tokens_after
+                );
+                rejected = hit.rejected;
+                let _ = self.policy_tx.send(hit);
+            }
+        }
+
+        let status = if rejected {
+            CallStatus::Rejected
+        } else if result.get("isError").and_then(Value::as_bool).unwrap_or(false) {
+            CallStatus::Error
+        } else {
+            CallStatus::Ok
+        };
+        (Ok(result), status)
+    }
+
+    async fn list_resources(&self) -> Result<Value, Value> {
+        let resources = self.aggregate("resources", "resources/list", "resources").await;
+
+        let mut routes = self.resource_routes.lock().await;
+        let resources: Vec<Value> = resources
+            .into_iter()
+            .map(|(upstream, resource)| {
+                if let Some(uri) = resource.get("uri").and_then(Value::as_str) {
+                    routes.insert(uri.to_string(), upstream.config.id.clone());
+                }
+                resource
+            })
+            .collect();
+
+        Ok(json!({ "resources": resources }))
+    }
+
+    async fn read_resource(&self, params: Value) -> Result<Value, Value> {
+        let uri = params.get("uri").and_then(Value::as_str).unwrap_or("");
+        let routed = self.resource_routes.lock().await.get(uri).cloned();
+        let upstreams = self.upstreams.read().await.clone();
+
+        // Ask the server that listed the URI first, then everyone else
+        // (templated resources are never listed).
+        let ordered = upstreams
+            .iter()
+            .filter(|u| Some(&u.config.id) == routed.as_ref())
+            .chain(
+                upstreams
+                    .iter()
+                    .filter(|u| Some(&u.config.id) != routed.as_ref() && u.supports("resources")),
+            );
+
+        for upstream in ordered {
+            if let Ok(Ok(result)) = self
+                .request_upstream(upstream, "resources/read", params.clone())
+                .await
+                .map(into_result)
+            {
+                return Ok(result);
+            }
+        }
+
+        Err(rpc_error(-32002, format!("Resource not found: {}", uri)))
+    }
+
+    async fn list_prompts(&self) -> Result<Value, Value> {
+        let prompts = self.aggregate("prompts", "prompts/list", "prompts").await;
+
+        let requests: Vec<NameRequest> = prompts
+            .iter()
+            .map(|(upstream, prompt)| NameRequest {
+                server_id: &upstream.config.id,
+                prefix: upstream.config.namespace_prefix(),
+                name: prompt.get("name").and_then(Value::as_str).unwrap_or(""),
+            })
+            .collect();
+        let (names, table) = namespace::assign(&*self.namespace.read().await, &requests);
+        *self.prompt_routes.write().await = table;
+
+        let prompts: Vec<Value> = prompts
+            .into_iter()
+            .zip(names)
+            .map(|((_, mut prompt), exposed_name)| {
+                prompt["name"] = json!(exposed_name);
+                prompt
+            })
+            .collect();
+
+        Ok(json!({ "prompts": prompts }))
+    }
+
+    async fn get_prompt(&self, mut params: Value) -> Result<Value, Value> {
+        let exposed_name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string();
+
+        if self.prompt_routes.read().await.is_empty() {
+            let _ = self.list_prompts().await;
+        }
+        let not_found = || rpc_error(-32602, format!("Prompt {} not found", exposed_name));
+        let route = self.prompt_routes.read().await.resolve(&exposed_name).cloned();
+        let (server_id, prompt_name) = route.ok_or_else(not_found)?;
+        let upstream = self.upstream_by_id(&server_id).await.ok_or_else(not_found)?;
+
+        params["name"] = json!(prompt_name);
+        let response = self
+            .request_upstream(&upstream, "prompts/get", params)
+            .await
+            .map_err(|e| rpc_error(-32603, e))?;
+        into_result(response)
+    }
+}
+
+/// Reload the gateway whenever the app rewrites its config file.
+async fn watch_config(path: PathBuf, gateway: Arc<Gateway>) {
+    let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut applied = modified(&path);
+    let mut failed = None;
+
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(CONFIG_POLL_SECS)).await;
+        let current = modified(&path);
+        if current.is_none() || current == applied {
+            continue;
+        }
+
+        match GatewayConfig::load(&path) {
+            Ok(config) => {
+                applied = current;
+                failed = None;
+                gateway.reload(config).await;
+            }
+            // Possibly read mid-write: retried on the next tick, reported
+            // once per version of the file.
+            Err(e) => {
+                if failed != current {
+                    eprintln!("[Relay] {}", e);
+                    failed = current;
+                }
+            }
+        }
+    }
+}
+
+/// Entry point for `relay --gateway`: serve the aggregated gateway on
+/// stdin/stdout until the client closes the stream.
+pub async fn run_stdio(config_path: PathBuf, tool_mode: ToolMode) -> Result<(), String> {
+    eprintln!(
+        "[Relay] Starting gateway with config {}",
+        config_path.display()
+    );
+
+    let config = GatewayConfig::load(&config_path)?;
+    let telemetry = config.telemetry.clone().filter(|t| t.is_enabled());
+    let gateway = Gateway::start(config).await;
+    let exporter = telemetry.map(|telemetry| crate::telemetry::spawn_exporter(telemetry, gateway.subscribe_spans()));
+    let watcher = tokio::spawn(watch_config(config_path.clone(), Arc::clone(&gateway)));
+    let session = Arc::new(ClientSession::new("stdio", tool_mode));
+    let control = control::ControlClient::spawn(Arc::clone(&gateway), config_path, session.id.clone());
+
+    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<Value>();
+    tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(message) = out_rx.recv().await {
+            let line = format!("{}\n", message);
+            if stdout.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+            let _ = stdout.flush().await;
+        }
+    });
+
+    let mut notifications = gateway.subscribe();
+    let notify_tx = out_tx.clone();
+    let session_id = session.id.clone();
+    tokio::spawn(async move {
+        loop {
+            match notifications.recv().await {
+                Ok(notification) if !notification.is_for(&session_id) => {}
+                Ok(notification) => {
+                    if notify_tx.send(notification.message).is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // Calls made through this process go into the app's audit log, and
+    // approval decisions into its approval log, whether or not the app is
+    // running.
+    let db = crate::db::connect_existing().await;
+    let mut decisions = gateway.approvals().subscribe_decisions();
+    let decision_db = db.clone();
+    tokio::spawn(async move {
+        loop {
+            match decisions.recv().await {
+                Ok(decision) => {
+                    eprintln!(
+                        "[Relay] Approval {} for {}/{}",
+                        decision.outcome.as_str(),
+                        decision.request.server_name,
+                        decision.request.tool
+                    );
+                    if let Some(db) = &decision_db {
+                        if let Err(e) = crate::commands::approvals::log_decision(db, &decision).await {
+                            eprintln!("[Relay] {}", e);
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    let audit_task = match db {
+        Some(db) => {
+            let mut calls = gateway.subscribe_tool_calls();
+            Some(tokio::spawn(async move {
+                loop {
+                    match calls.recv().await {
+                        Ok(call) => crate::audit::record(&db, &call).await,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            eprintln!("[Relay] Audit log missed {} tool calls", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            }))
+        }
+        None => {
+            eprintln!("[Relay] No Relay database found; tool calls will not be audited");
+            None
+        }
+    };
+
+    eprintln!("[Relay] Gateway running on stdio");
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let message = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                let _ = out_tx.send(json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": rpc_error(-32700, format!("Parse error: {}", e))
+                }));
+                continue;
+            }
+        };
+
+        if message.get("method").and_then(Value::as_str) == Some("initialize") {
+            if let Some(name) = message.pointer("/params/clientInfo/name").and_then(Value::as_str) {
+                control.set_client(name);
+            }
+        }
+
+        let gateway = Arc::clone(&gateway);
+        let session = Arc::clone(&session);
+        let out_tx = out_tx.clone();
+        tokio::spawn(async move {
+            if let Some(response) = gateway.handle_message(message, &session).await {
+                let _ = out_tx.send(response);
+            }
+        });
+    }
+
+    eprintln!("[Relay] Client disconnected, shutting down gateway");
+    watcher.abort();
+    drop(control);
+    gateway.shutdown().await;
+
+    // Dropping the gateway closes the call channel once in-flight calls
+    // finish, letting the audit task write what is left.
+    drop(gateway);
+    if let Some(audit_task) = audit_task {
+        let _ = tokio::time::timeout(std::time::Duration::from_secs(5), audit_task).await;
+    }
+    if let Some(exporter) = exporter {
+        let _ = tokio::time::timeout(std::time::Duration::from_secs(5), exporter).await;
+    }
+    Ok(())
+}
@@ -0,0 +1,195 @@
+//! Exposed names for aggregated tools and prompts, and the routing table that
+//! maps them back to (server, upstream name).
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Most clients reject tool names longer than this.
+pub const DEFAULT_MAX_NAME_LENGTH: usize = 64;
+/// Shortest usable limit: room for a hash suffix plus a few characters of
+/// the name.
+pub const MIN_NAME_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NamespaceStrategy {
+    /// Always `<alias><separator><name>`.
+    #[default]
+    Prefix,
+    /// Bare names, prefixed only where two servers expose the same name.
+    PrefixOnConflict,
+}
+
+impl NamespaceStrategy {
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "prefix_on_conflict" => Self::PrefixOnConflict,
+            _ => Self::Prefix,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceConfig {
+    #[serde(default)]
+    pub strategy: NamespaceStrategy,
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+}
+
+fn default_separator() -> String {
+    "_".to_string()
+}
+
+fn default_max_length() -> usize {
+    DEFAULT_MAX_NAME_LENGTH
+}
+
+impl NamespaceConfig {
+    /// Check the separator is made of characters clients accept in tool
+    /// names and the length limit leaves room for a hash suffix.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.separator.is_empty() || sanitize(&self.separator) != self.separator {
+            return Err(format!(
+                "Invalid tool namespace separator {:?}: use one or more of A-Z, a-z, 0-9, _ and -",
+                self.separator
+            ));
+        }
+        if self.max_length < MIN_NAME_LENGTH {
+            return Err(format!(
+                "Tool name length limit {} is too short; it must be at least {}",
+                self.max_length, MIN_NAME_LENGTH
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for NamespaceConfig {
+    fn default() -> Self {
+        Self {
+            strategy: NamespaceStrategy::default(),
+            separator: default_separator(),
+            max_length: default_max_length(),
+        }
+    }
+}
+
+/// Replace characters outside `[A-Za-z0-9_-]`, the set clients accept in
+/// tool names.
+pub fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// FNV-1a; stable across runs and platforms so exposed names don't change.
+fn short_hash(text: &str) -> String {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in text.bytes() {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    format!("{:08x}", hash)
+}
+
+/// Cut `name` to `max_length`, replacing the tail with a hash of the full
+/// name so that distinct long names stay distinct.
+fn fit_length(name: &str, max_length: usize) -> String {
+    if name.len() <= max_length {
+        return name.to_string();
+    }
+    let suffix = format!("_{}", short_hash(name));
+    let mut keep = max_length.saturating_sub(suffix.len());
+    while !name.is_char_boundary(keep) {
+        keep -= 1;
+    }
+    format!("{}{}", &name[..keep], suffix)
+}
+
+/// One tool or prompt offered by an upstream.
+pub struct NameRequest<'a> {
+    pub server_id: &'a str,
+    /// Server alias, or the sanitized server name when there is none.
+    pub prefix: &'a str,
+    pub name: &'a str,
+}
+
+/// Exposed name -> (server id, upstream name).
+#[derive(Debug, Clone, Default)]
+pub struct RoutingTable {
+    routes: HashMap<String, (String, String)>,
+}
+
+impl RoutingTable {
+    pub fn resolve(&self, exposed: &str) -> Option<&(String, String)> {
+        self.routes.get(exposed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+/// Assign an exposed name to every request (in order) and build the routing
+/// table. Any remaining collision gets a hash of the server id appended,
+/// retried until the name is free.
+pub fn assign(config: &NamespaceConfig, requests: &[NameRequest]) -> (Vec<String>, RoutingTable) {
+    let mut servers_per_name: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for request in requests {
+        servers_per_name
+            .entry(request.name)
+            .or_default()
+            .insert(request.server_id);
+    }
+
+    let mut table = RoutingTable::default();
+    let mut exposed_names = Vec::with_capacity(requests.len());
+
+    for request in requests {
+        let conflicting = servers_per_name
+            .get(request.name)
+            .is_some_and(|servers| servers.len() > 1);
+
+        let candidate = match config.strategy {
+            NamespaceStrategy::PrefixOnConflict if !conflicting => sanitize(request.name),
+            _ => format!(
+                "{}{}{}",
+                sanitize(request.prefix),
+                config.separator,
+                sanitize(request.name)
+            ),
+        };
+        let mut exposed = fit_length(&candidate, config.max_length);
+
+        // The first retry hashes the server id alone so names stay stable;
+        // later ones add a counter until the name is free.
+        let mut attempt = 0;
+        while table.routes.contains_key(&exposed) {
+            let seed = match attempt {
+                0 => request.server_id.to_string(),
+                n => format!("{}#{}", request.server_id, n),
+            };
+            let disambiguated = format!("{}_{}", candidate, short_hash(&seed));
+            exposed = fit_length(&disambiguated, config.max_length);
+            attempt += 1;
+        }
+
+        table.routes.insert(
+            exposed.clone(),
+            (request.server_id.to_string(), request.name.to_string()),
+        );
+        exposed_names.push(exposed);
+    }
+
+    (exposed_names, table)
+}
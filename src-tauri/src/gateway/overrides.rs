@@ -0,0 +1,80 @@
+//! Per-server tool overrides: exposed name, description and argument
+//! descriptions, keyed by the upstream tool name.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolOverride {
+    /// Name to expose instead of the upstream name (before the server prefix).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Argument name -> description.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub arguments: HashMap<String, String>,
+}
+
+/// Upstream tool name -> override.
+pub type ToolOverrides = HashMap<String, ToolOverride>;
+
+/// Rewrite a `tools/list` entry according to its override, if any.
+pub fn apply(overrides: &ToolOverrides, tool: &mut Value) {
+    let Some(name) = tool.get("name").and_then(Value::as_str) else {
+        return;
+    };
+    let Some(entry) = overrides.get(name) else {
+        return;
+    };
+
+    if let Some(exposed) = &entry.name {
+        tool["name"] = json!(exposed);
+    }
+    if let Some(description) = &entry.description {
+        tool["description"] = json!(description);
+    }
+    for (argument, description) in &entry.arguments {
+        if let Some(property) = tool
+            .get_mut("inputSchema")
+            .and_then(|schema| schema.get_mut("properties"))
+            .and_then(|properties| properties.get_mut(argument))
+            .and_then(Value::as_object_mut)
+        {
+            property.insert("description".to_string(), json!(description));
+        }
+    }
+}
+
+/// Map an exposed tool name back to the upstream name. A tool that has been
+/// renamed is no longer reachable under its original name.
+pub fn upstream_name(overrides: &ToolOverrides, exposed: &str) -> Option<String> {
+    if let Some((original, _)) = overrides
+        .iter()
+        .find(|(_, entry)| entry.name.as_deref() == Some(exposed))
+    {
+        return Some(original.clone());
+    }
+
+    match overrides.get(exposed) {
+        Some(entry) if entry.name.is_some() => None,
+        _ => Some(exposed.to_string()),
+    }
+}
+
+/// Overrides whose upstream tool no longer exists.
+pub fn stale<'a>(overrides: &'a ToolOverrides, upstream_tools: &[Value]) -> Vec<&'a str> {
+    let mut stale: Vec<&str> = overrides
+        .keys()
+        .filter(|original| {
+            !upstream_tools
+                .iter()
+                .any(|tool| tool.get("name").and_then(Value::as_str) == Some(original.as_str()))
+        })
+        .map(String::as_str)
+        .collect();
+    stale.sort_unstable();
+    stale
+}
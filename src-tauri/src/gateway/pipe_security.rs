@@ -0,0 +1,142 @@
+//! The control pipe is per user: the app creates it with a DACL that admits
+//! only the current user, and instances check that the pipe's server runs as
+//! the same user before trusting it.
+
+use std::ffi::c_void;
+use std::io;
+use std::os::windows::io::AsRawHandle;
+use windows_sys::Win32::Foundation::{CloseHandle, LocalFree, HANDLE};
+use windows_sys::Win32::Security::Authorization::{
+    ConvertSidToStringSidW, ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+};
+use windows_sys::Win32::Security::{EqualSid, GetTokenInformation, TokenUser, SECURITY_ATTRIBUTES, TOKEN_QUERY, TOKEN_USER};
+use windows_sys::Win32::System::Pipes::GetNamedPipeServerProcessId;
+use windows_sys::Win32::System::Threading::{
+    GetCurrentProcess, OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+
+/// `TOKEN_USER` of a process, in a buffer aligned for it.
+struct ProcessUser(Vec<u64>);
+
+impl ProcessUser {
+    fn of(process: HANDLE) -> io::Result<Self> {
+        unsafe {
+            let mut token: HANDLE = std::ptr::null_mut();
+            if OpenProcessToken(process, TOKEN_QUERY, &mut token) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut len = 0u32;
+            GetTokenInformation(token, TokenUser, std::ptr::null_mut(), 0, &mut len);
+            let mut buffer = vec![0u64; (len as usize).div_ceil(8)];
+            let ok = GetTokenInformation(token, TokenUser, buffer.as_mut_ptr().cast(), len, &mut len);
+            let error = io::Error::last_os_error();
+            CloseHandle(token);
+            if ok == 0 {
+                return Err(error);
+            }
+            Ok(Self(buffer))
+        }
+    }
+
+    fn current() -> io::Result<Self> {
+        Self::of(unsafe { GetCurrentProcess() })
+    }
+
+    fn sid(&self) -> *mut c_void {
+        unsafe { (*self.0.as_ptr().cast::<TOKEN_USER>()).User.Sid }
+    }
+
+    /// The SID in `S-1-5-...` form.
+    fn sid_string(&self) -> io::Result<String> {
+        unsafe {
+            let mut wide: *mut u16 = std::ptr::null_mut();
+            if ConvertSidToStringSidW(self.sid(), &mut wide) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let len = (0..).take_while(|&i| *wide.add(i) != 0).count();
+            let sid = String::from_utf16_lossy(std::slice::from_raw_parts(wide, len));
+            LocalFree(wide.cast());
+            Ok(sid)
+        }
+    }
+}
+
+/// Security attributes that give the current user, and nobody else, access
+/// to the pipe.
+pub struct CurrentUserOnly {
+    attributes: Box<SECURITY_ATTRIBUTES>,
+}
+
+impl CurrentUserOnly {
+    pub fn new() -> io::Result<Self> {
+        let sid = ProcessUser::current()?.sid_string()?;
+        // Protected DACL with a single allow-all entry for the user.
+        let sddl: Vec<u16> = format!("D:P(A;;GA;;;{})", sid)
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let mut descriptor: *mut c_void = std::ptr::null_mut();
+        let ok = unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                sddl.as_ptr(),
+                SDDL_REVISION_1,
+                &mut descriptor,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            attributes: Box::new(SECURITY_ATTRIBUTES {
+                nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: descriptor,
+                bInheritHandle: 0,
+            }),
+        })
+    }
+
+    /// For `ServerOptions::create_with_security_attributes_raw`; valid while
+    /// `self` lives.
+    pub fn as_ptr(&mut self) -> *mut c_void {
+        (&mut *self.attributes as *mut SECURITY_ATTRIBUTES).cast()
+    }
+}
+
+// SAFETY: the descriptor is owned by this value and never mutated, so it can
+// move to the thread that creates the next pipe instance.
+unsafe impl Send for CurrentUserOnly {}
+
+impl Drop for CurrentUserOnly {
+    fn drop(&mut self) {
+        unsafe {
+            LocalFree(self.attributes.lpSecurityDescriptor);
+        }
+    }
+}
+
+/// Check that the process serving `pipe` runs as the current user, so
+/// another account cannot stand in for the app.
+pub fn check_server(pipe: &impl AsRawHandle) -> io::Result<()> {
+    let server = unsafe {
+        let mut pid = 0u32;
+        if GetNamedPipeServerProcessId(pipe.as_raw_handle(), &mut pid) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let user = ProcessUser::of(process);
+        CloseHandle(process);
+        user?
+    };
+
+    if unsafe { EqualSid(server.sid(), ProcessUser::current()?.sid()) } == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "control pipe is served by another user",
+        ));
+    }
+    Ok(())
+}
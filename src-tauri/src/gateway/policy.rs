@@ -0,0 +1,289 @@
+//! Response size budgets for tool results, applied before a result reaches
+//! the client.
+
+use crate::utils::tokenizer::{context_text, Tokenizer};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Images are not text, so they are charged at roughly what a ~1MP image
+/// costs a vision model.
+const IMAGE_TOKEN_ESTIMATE: u64 = 1_600;
+const DEFAULT_MAX_ARRAY_ITEMS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    /// Cut text content to the budget and append a marker.
+    Truncate,
+    /// Replace image and audio blocks with a short text marker.
+    DropImages,
+    /// Shorten JSON arrays (in text content and structuredContent) to the
+    /// first `maxArrayItems` items.
+    SummarizeArrays,
+    /// Replace the result with a tool error.
+    Reject,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    /// Applied in order until the result fits. Defaults to `[truncate]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<PolicyAction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_array_items: Option<usize>,
+}
+
+/// Per-server response policy: server-wide limits plus per-tool overrides
+/// keyed by the upstream tool name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponsePolicy {
+    #[serde(flatten)]
+    pub defaults: ResponseLimits,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tools: HashMap<String, ResponseLimits>,
+}
+
+impl ResponsePolicy {
+    /// Whether no tool has a budget, so the policy never applies.
+    pub fn is_empty(&self) -> bool {
+        self.defaults.max_tokens.is_none() && self.tools.values().all(|limits| limits.max_tokens.is_none())
+    }
+
+    /// Limits for `tool`: its own settings, with anything it leaves unset
+    /// taken from the server-wide defaults. `None` when there is no budget.
+    pub fn limits_for(&self, tool: &str) -> Option<ResponseLimits> {
+        let limits = match self.tools.get(tool) {
+            Some(overrides) => ResponseLimits {
+                max_tokens: overrides.max_tokens.or(self.defaults.max_tokens),
+                actions: if overrides.actions.is_empty() {
+                    self.defaults.actions.clone()
+                } else {
+                    overrides.actions.clone()
+                },
+                max_array_items: overrides.max_array_items.or(self.defaults.max_array_items),
+            },
+            None => self.defaults.clone(),
+        };
+        limits.max_tokens.map(|_| limits)
+    }
+}
+
+/// A tool result that exceeded its budget.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyHit {
+    pub server_id: String,
+    pub tool: String,
+    pub max_tokens: u64,
+    pub tokens_before: u64,
+    pub tokens_after: u64,
+    pub actions: Vec<PolicyAction>,
+    pub rejected: bool,
+}
+
+fn is_binary_block(item: &Value) -> bool {
+    matches!(
+        item.get("type").and_then(Value::as_str),
+        Some("image") | Some("audio")
+    )
+}
+
+/// Context tokens of a `tools/call` result.
+pub fn result_tokens(result: &Value, tokenizer: Tokenizer) -> u64 {
+    let text_tokens = tokenizer.count(&context_text(&json!({ "result": result })));
+    let binary_blocks = result
+        .get("content")
+        .and_then(Value::as_array)
+        .map(|content| content.iter().filter(|item| is_binary_block(item)).count())
+        .unwrap_or(0) as u64;
+
+    text_tokens + binary_blocks * IMAGE_TOKEN_ESTIMATE
+}
+
+fn drop_images(result: &mut Value) {
+    let Some(content) = result.get_mut("content").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for item in content.iter_mut() {
+        if is_binary_block(item) {
+            let kind = item["type"].as_str().unwrap_or("binary").to_string();
+            *item = json!({
+                "type": "text",
+                "text": format!("[{} removed by Relay response policy]", kind)
+            });
+        }
+    }
+}
+
+fn shorten_arrays(value: &mut Value, max_items: usize) -> bool {
+    match value {
+        Value::Array(items) => {
+            let mut changed = false;
+            if items.len() > max_items {
+                let omitted = items.len() - max_items;
+                items.truncate(max_items);
+                items.push(Value::String(format!(
+                    "... {} more items omitted by Relay response policy",
+                    omitted
+                )));
+                changed = true;
+            }
+            for item in items.iter_mut() {
+                changed |= shorten_arrays(item, max_items);
+            }
+            changed
+        }
+        Value::Object(map) => {
+            let mut changed = false;
+            for item in map.values_mut() {
+                changed |= shorten_arrays(item, max_items);
+            }
+            changed
+        }
+        _ => false,
+    }
+}
+
+fn summarize_arrays(result: &mut Value, max_items: usize) {
+    if let Some(structured) = result.get_mut("structuredContent") {
+        shorten_arrays(structured, max_items);
+    }
+
+    let Some(content) = result.get_mut("content").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for item in content.iter_mut() {
+        let Some(text) = item.get("text").and_then(Value::as_str) else {
+            continue;
+        };
+        let Ok(mut parsed) = serde_json::from_str::<Value>(text) else {
+            continue;
+        };
+        if shorten_arrays(&mut parsed, max_items) {
+            let rewritten = if text.contains('\n') {
+                serde_json::to_string_pretty(&parsed)
+            } else {
+                serde_json::to_string(&parsed)
+            };
+            if let Ok(rewritten) = rewritten {
+                item["text"] = Value::String(rewritten);
+            }
+        }
+    }
+}
+
+fn truncate_text(result: &mut Value, max_tokens: u64, tokens_before: u64, tokenizer: Tokenizer) {
+    // structuredContent cannot be cut and still be valid, and it duplicates
+    // the text content anyway.
+    if let Some(obj) = result.as_object_mut() {
+        obj.remove("structuredContent");
+    }
+
+    let Some(content) = result.get_mut("content").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    let binary_blocks = content.iter().filter(|item| is_binary_block(item)).count() as u64;
+    let mut remaining = max_tokens.saturating_sub(binary_blocks * IMAGE_TOKEN_ESTIMATE);
+
+    // Text past the cut point is dropped entirely.
+    let mut exhausted = false;
+    content.retain_mut(|item| {
+        let Some(text) = item.get("text").and_then(Value::as_str) else {
+            return true;
+        };
+        if exhausted {
+            return false;
+        }
+        let tokens = tokenizer.count(text);
+        if tokens <= remaining {
+            remaining -= tokens;
+            return true;
+        }
+
+        let marker = format!(
+            "\n\n[Truncated by Relay: response was {} tokens, limit is {}]",
+            tokens_before, max_tokens
+        );
+        let budget = remaining.saturating_sub(tokenizer.count(&marker));
+        let mut cut = tokenizer.truncate(text, budget);
+        cut.push_str(&marker);
+        item["text"] = Value::String(cut);
+        exhausted = true;
+        true
+    });
+}
+
+/// Enforce the policy on a `tools/call` result in place. Returns the hit
+/// when the result was over budget.
+pub fn apply(
+    policy: &ResponsePolicy,
+    server_id: &str,
+    tool: &str,
+    result: &mut Value,
+    tokenizer: Tokenizer,
+) -> Option<PolicyHit> {
+    let limits = policy.limits_for(tool)?;
+    let max_tokens = limits.max_tokens?;
+
+    let tokens_before = result_tokens(result, tokenizer);
+    if tokens_before <= max_tokens {
+        return None;
+    }
+
+    let actions = if limits.actions.is_empty() {
+        vec![PolicyAction::Truncate]
+    } else {
+        limits.actions.clone()
+    };
+
+    let mut applied = Vec::new();
+    let mut tokens = tokens_before;
+    let mut rejected = false;
+
+    for action in actions {
+        if tokens <= max_tokens {
+            break;
+        }
+        match action {
+            PolicyAction::Truncate => truncate_text(result, max_tokens, tokens_before, tokenizer),
+            PolicyAction::DropImages => drop_images(result),
+            PolicyAction::SummarizeArrays => summarize_arrays(
+                result,
+                limits.max_array_items.unwrap_or(DEFAULT_MAX_ARRAY_ITEMS),
+            ),
+            PolicyAction::Reject => {
+                *result = json!({
+                    "content": [{
+                        "type": "text",
+                        "text": format!(
+                            "Relay rejected this result: it is {} tokens, over the {} token limit for {}. Narrow the request and try again.",
+                            tokens_before, max_tokens, tool
+                        )
+                    }],
+                    "isError": true
+                });
+                rejected = true;
+            }
+        }
+        applied.push(action);
+        tokens = result_tokens(result, tokenizer);
+        if rejected {
+            break;
+        }
+    }
+
+    Some(PolicyHit {
+        server_id: server_id.to_string(),
+        tool: tool.to_string(),
+        max_tokens,
+        tokens_before,
+        tokens_after: tokens,
+        actions: applied,
+        rejected,
+    })
+}
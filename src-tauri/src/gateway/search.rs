@@ -0,0 +1,252 @@
+//! Tool search mode: instead of every upstream tool, clients are shown three
+//! meta-tools and discover the rest on demand through a local BM25 index
+//! over tool names and descriptions.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+pub const SEARCH_TOOLS: &str = "search_tools";
+pub const DESCRIBE_TOOL: &str = "describe_tool";
+pub const INVOKE_TOOL: &str = "invoke_tool";
+
+pub const DEFAULT_RESULTS: usize = 10;
+const MAX_RESULTS: usize = 50;
+/// Descriptions in search results are cut to this many characters;
+/// `describe_tool` returns them in full.
+const SUMMARY_CHARS: usize = 200;
+
+// BM25 parameters.
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+/// Name terms count this many times, so a match on the name outranks one
+/// buried in a long description.
+const NAME_WEIGHT: usize = 3;
+
+/// What a client sees in `tools/list`, chosen per exported client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolMode {
+    /// Every exposed tool.
+    #[default]
+    Full,
+    /// Only the search meta-tools.
+    Search,
+}
+
+impl ToolMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "full" => Some(Self::Full),
+            "search" => Some(Self::Search),
+            _ => None,
+        }
+    }
+}
+
+pub fn is_meta_tool(name: &str) -> bool {
+    matches!(name, SEARCH_TOOLS | DESCRIBE_TOOL | INVOKE_TOOL)
+}
+
+/// Definitions of the meta-tools listed in search mode.
+pub fn meta_tools(tool_count: usize) -> Vec<Value> {
+    vec![
+        json!({
+            "name": SEARCH_TOOLS,
+            "description": format!(
+                "Search the {} tools available through Relay by keywords. Returns matching tool names with short descriptions; use describe_tool for a tool's input schema, then invoke_tool to call it.",
+                tool_count
+            ),
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Keywords describing what the tool should do" },
+                    "limit": {
+                        "type": "integer",
+                        "description": format!("Maximum number of results (default {})", DEFAULT_RESULTS),
+                        "minimum": 1,
+                        "maximum": MAX_RESULTS
+                    }
+                },
+                "required": ["query"]
+            },
+            "annotations": { "readOnlyHint": true }
+        }),
+        json!({
+            "name": DESCRIBE_TOOL,
+            "description": "Get the full description and input schema of a tool found with search_tools.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "Tool name as returned by search_tools" }
+                },
+                "required": ["name"]
+            },
+            "annotations": { "readOnlyHint": true }
+        }),
+        json!({
+            "name": INVOKE_TOOL,
+            "description": "Call a tool found with search_tools. Arguments must match the input schema from describe_tool.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "Tool name as returned by search_tools" },
+                    "arguments": { "type": "object", "description": "Arguments for the tool" }
+                },
+                "required": ["name"]
+            }
+        }),
+    ]
+}
+
+/// Lowercase terms of `text`, split on anything but letters and digits and
+/// at camelCase boundaries (`readFile` -> `read`, `file`).
+fn terms(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+
+    for c in text.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                terms.push(std::mem::take(&mut current));
+            }
+            previous_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lower && !current.is_empty() {
+            terms.push(std::mem::take(&mut current));
+        }
+        previous_lower = c.is_lowercase() || c.is_numeric();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        terms.push(current);
+    }
+    terms
+}
+
+struct Document {
+    /// The tool as exposed to clients.
+    tool: Value,
+    term_counts: HashMap<String, usize>,
+    length: usize,
+}
+
+/// BM25 index over exposed tools.
+pub struct ToolIndex {
+    documents: Vec<Document>,
+    /// Number of documents containing each term.
+    document_frequency: HashMap<String, usize>,
+    average_length: f64,
+}
+
+/// A search result, as returned by `search_tools`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub name: String,
+    pub description: String,
+    pub score: f64,
+}
+
+impl ToolIndex {
+    pub fn build(tools: Vec<Value>) -> Self {
+        let mut documents = Vec::with_capacity(tools.len());
+        let mut document_frequency: HashMap<String, usize> = HashMap::new();
+
+        for tool in tools {
+            let name = tool.get("name").and_then(Value::as_str).unwrap_or("");
+            let description = tool.get("description").and_then(Value::as_str).unwrap_or("");
+            let title = tool.get("title").and_then(Value::as_str).unwrap_or("");
+
+            let mut term_counts: HashMap<String, usize> = HashMap::new();
+            for term in terms(name).into_iter().chain(terms(title)) {
+                *term_counts.entry(term).or_default() += NAME_WEIGHT;
+            }
+            for term in terms(description) {
+                *term_counts.entry(term).or_default() += 1;
+            }
+            for term in term_counts.keys() {
+                *document_frequency.entry(term.clone()).or_default() += 1;
+            }
+            let length = term_counts.values().sum();
+            documents.push(Document {
+                tool,
+                term_counts,
+                length,
+            });
+        }
+
+        let total: usize = documents.iter().map(|d| d.length).sum();
+        let average_length = if documents.is_empty() {
+            0.0
+        } else {
+            total as f64 / documents.len() as f64
+        };
+
+        Self {
+            documents,
+            document_frequency,
+            average_length,
+        }
+    }
+
+    pub fn tool_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Best matches for `query`, highest score first. Tools matching no
+    /// query term are left out.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let mut query_terms = terms(query);
+        query_terms.sort();
+        query_terms.dedup();
+
+        let count = self.documents.len() as f64;
+        let mut scored: Vec<(f64, &Document)> = self
+            .documents
+            .iter()
+            .filter_map(|document| {
+                let mut score = 0.0;
+                for term in &query_terms {
+                    let Some(&frequency) = document.term_counts.get(term) else {
+                        continue;
+                    };
+                    let df = self.document_frequency.get(term).copied().unwrap_or(0) as f64;
+                    let idf = (1.0 + (count - df + 0.5) / (df + 0.5)).ln();
+                    let tf = frequency as f64;
+                    let norm = 1.0 - B + B * document.length as f64 / self.average_length.max(1.0);
+                    score += idf * tf * (K1 + 1.0) / (tf + K1 * norm);
+                }
+                (score > 0.0).then_some((score, document))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        scored
+            .into_iter()
+            .take(limit.clamp(1, MAX_RESULTS))
+            .map(|(score, document)| SearchHit {
+                name: document.tool.get("name").and_then(Value::as_str).unwrap_or("").to_string(),
+                description: summary(document.tool.get("description").and_then(Value::as_str).unwrap_or("")),
+                score: (score * 1000.0).round() / 1000.0,
+            })
+            .collect()
+    }
+
+    /// Full definition of an indexed tool.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.documents
+            .iter()
+            .map(|document| &document.tool)
+            .find(|tool| tool.get("name").and_then(Value::as_str) == Some(name))
+    }
+}
+
+fn summary(description: &str) -> String {
+    let description = description.trim();
+    match description.char_indices().nth(SUMMARY_CHARS) {
+        Some((end, _)) => format!("{}...", description[..end].trim_end()),
+        None => description.to_string(),
+    }
+}
@@ -0,0 +1,192 @@
+//! Named groups of tools across servers. While any toolset is active, the
+//! gateway exposes only the tools of active toolsets; with none active every
+//! tool is exposed. Clients can switch toolsets mid-session through the
+//! meta-tools below.
+
+use super::filter::glob_match;
+use crate::models::toolset::Toolset;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+pub const LIST_TOOLSETS: &str = "list_toolsets";
+pub const ENABLE_TOOLSET: &str = "enable_toolset";
+pub const DISABLE_TOOLSET: &str = "disable_toolset";
+
+/// Tools of one server in a toolset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolsetEntry {
+    pub server_id: String,
+    /// Upstream tool name globs; `*` takes every tool of the server.
+    #[serde(default)]
+    pub tools: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolsetConfig {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tools: Vec<ToolsetEntry>,
+    /// Active when a session starts.
+    #[serde(default)]
+    pub active: bool,
+}
+
+impl ToolsetConfig {
+    pub fn from_toolset(toolset: &Toolset) -> Self {
+        Self {
+            id: toolset.id.clone(),
+            name: toolset.name.clone(),
+            description: toolset.description.clone(),
+            tools: serde_json::from_str(&toolset.tools).unwrap_or_default(),
+            active: toolset.active,
+        }
+    }
+
+    pub fn contains(&self, server_id: &str, tool: &str) -> bool {
+        self.tools
+            .iter()
+            .filter(|entry| entry.server_id == server_id)
+            .any(|entry| entry.tools.iter().any(|pattern| glob_match(pattern, tool)))
+    }
+}
+
+/// Configured toolsets and which are active in one session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Toolsets {
+    configs: Vec<ToolsetConfig>,
+    /// Ids of active toolsets.
+    active: HashSet<String>,
+}
+
+impl Toolsets {
+    pub fn new(configs: Vec<ToolsetConfig>) -> Self {
+        let active = configs.iter().filter(|t| t.active).map(|t| t.id.clone()).collect();
+        Self { configs, active }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.configs.is_empty()
+    }
+
+    /// Take new definitions. Toolsets keep their state from this session
+    /// unless their configured `active` flag changed. Returns whether
+    /// anything changed.
+    pub fn update(&mut self, configs: Vec<ToolsetConfig>) -> bool {
+        let previous = self.clone();
+        let active = configs
+            .iter()
+            .filter(|config| {
+                match self.configs.iter().find(|previous| previous.id == config.id) {
+                    Some(previous) if previous.active == config.active => self.active.contains(&config.id),
+                    _ => config.active,
+                }
+            })
+            .map(|config| config.id.clone())
+            .collect();
+        self.configs = configs;
+        self.active = active;
+        *self != previous
+    }
+
+    /// Whether `tool` (upstream name) of `server_id` is exposed.
+    pub fn allows(&self, server_id: &str, tool: &str) -> bool {
+        self.active.is_empty()
+            || self
+                .configs
+                .iter()
+                .filter(|config| self.active.contains(&config.id))
+                .any(|config| config.contains(server_id, tool))
+    }
+
+    /// Activate or deactivate the toolset named (or with id) `name`. Returns
+    /// whether anything changed.
+    pub fn set_active(&mut self, name: &str, active: bool) -> Result<bool, String> {
+        let config = self
+            .configs
+            .iter()
+            .find(|config| config.name == name || config.id == name)
+            .ok_or_else(|| format!("Unknown toolset {}; use {} to see the available ones.", name, LIST_TOOLSETS))?;
+        Ok(if active {
+            self.active.insert(config.id.clone())
+        } else {
+            self.active.remove(&config.id)
+        })
+    }
+
+    /// Ids of active toolsets, sorted.
+    pub fn active_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.active.iter().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Names of active toolsets.
+    pub fn active_names(&self) -> Vec<String> {
+        self.configs
+            .iter()
+            .filter(|config| self.active.contains(&config.id))
+            .map(|config| config.name.clone())
+            .collect()
+    }
+
+    /// Toolsets as reported by `list_toolsets`.
+    pub fn summary(&self) -> Value {
+        let toolsets: Vec<Value> = self
+            .configs
+            .iter()
+            .map(|config| {
+                json!({
+                    "name": config.name,
+                    "description": config.description.as_deref().unwrap_or(""),
+                    "active": self.active.contains(&config.id),
+                })
+            })
+            .collect();
+        json!({
+            "toolsets": toolsets,
+            "note": if self.active.is_empty() {
+                "No toolset is active, so every tool is available. Enabling a toolset limits tools to the active toolsets."
+            } else {
+                "Only tools of active toolsets are available."
+            }
+        })
+    }
+}
+
+pub fn is_meta_tool(name: &str) -> bool {
+    matches!(name, LIST_TOOLSETS | ENABLE_TOOLSET | DISABLE_TOOLSET)
+}
+
+/// Definitions of the toolset meta-tools, listed when toolsets are configured.
+pub fn meta_tools() -> Vec<Value> {
+    let toolset_name = json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "description": "Toolset name as returned by list_toolsets" }
+        },
+        "required": ["name"]
+    });
+    vec![
+        json!({
+            "name": LIST_TOOLSETS,
+            "description": "List the toolsets (named groups of tools) available through Relay and which are active.",
+            "inputSchema": { "type": "object", "properties": {} },
+            "annotations": { "readOnlyHint": true }
+        }),
+        json!({
+            "name": ENABLE_TOOLSET,
+            "description": "Activate a toolset, making its tools available. The tool list is updated right away.",
+            "inputSchema": toolset_name.clone(),
+        }),
+        json!({
+            "name": DISABLE_TOOLSET,
+            "description": "Deactivate a toolset, removing its tools unless another active toolset includes them.",
+            "inputSchema": toolset_name,
+        }),
+    ]
+}
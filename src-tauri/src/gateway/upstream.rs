@@ -0,0 +1,602 @@
+use super::config::GatewayServerConfig;
+use crate::utils::redaction;
+use crate::utils::secrets::SecretManager;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::{timeout, Duration};
+
+const REQUEST_TIMEOUT_SECS: u64 = 300;
+const INITIALIZE_TIMEOUT_SECS: u64 = 30;
+pub const PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// A message an upstream sent on its own: a notification.
+#[derive(Debug, Clone)]
+pub struct UpstreamNotification {
+    pub message: Value,
+}
+
+type Pending = Mutex<HashMap<u64, oneshot::Sender<Value>>>;
+
+/// Routes messages read from an upstream to whoever is waiting for them.
+struct Dispatcher {
+    pending: Pending,
+    notifications: mpsc::UnboundedSender<UpstreamNotification>,
+}
+
+impl Dispatcher {
+    /// Route an incoming message. Returns a reply when the upstream sent a
+    /// request of its own (ping, roots/list, ...).
+    async fn dispatch(&self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        match (id, method) {
+            (Some(id), None) => {
+                if let Some(id) = id.as_u64() {
+                    if let Some(waiter) = self.pending.lock().await.remove(&id) {
+                        let _ = waiter.send(message);
+                    }
+                }
+                None
+            }
+            (None, Some(_)) => {
+                let _ = self.notifications.send(UpstreamNotification { message });
+                None
+            }
+            (Some(id), Some(method)) => Some(match method.as_str() {
+                "ping" => json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+                "roots/list" => json!({ "jsonrpc": "2.0", "id": id, "result": { "roots": [] } }),
+                _ => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {
+                        "code": -32601,
+                        "message": format!("Method not supported by Relay Gateway: {}", method)
+                    }
+                }),
+            }),
+            (None, None) => None,
+        }
+    }
+
+    /// Fail every in-flight request, e.g. after the upstream went away.
+    async fn disconnect(&self) {
+        self.pending.lock().await.clear();
+    }
+}
+
+#[derive(Default)]
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+/// Incremental parser for `text/event-stream` bodies.
+#[derive(Default)]
+struct SseParser {
+    buffer: String,
+}
+
+impl SseParser {
+    fn push(&mut self, chunk: &str) -> Vec<SseEvent> {
+        self.buffer.push_str(&chunk.replace("\r\n", "\n"));
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.find("\n\n") {
+            let block: String = self.buffer.drain(..end + 2).collect();
+            let mut event = SseEvent::default();
+            for line in block.lines() {
+                if let Some(value) = line.strip_prefix("event:") {
+                    event.event = value.trim().to_string();
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    if !event.data.is_empty() {
+                        event.data.push('\n');
+                    }
+                    event.data.push_str(value.strip_prefix(' ').unwrap_or(value));
+                }
+            }
+            if !event.data.is_empty() {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+enum Transport {
+    Stdio {
+        stdin: Arc<Mutex<ChildStdin>>,
+        child: Mutex<Child>,
+    },
+    StreamableHttp {
+        client: reqwest::Client,
+        url: String,
+        session_id: Arc<Mutex<Option<String>>>,
+    },
+    Sse {
+        client: reqwest::Client,
+        endpoint: String,
+        reader: JoinHandle<()>,
+    },
+}
+
+/// A connection from the gateway to one configured MCP server.
+pub struct Upstream {
+    pub config: GatewayServerConfig,
+    transport: Transport,
+    dispatcher: Arc<Dispatcher>,
+    next_id: AtomicU64,
+    capabilities: OnceLock<Value>,
+}
+
+fn build_command(command: &str) -> Command {
+    #[cfg(windows)]
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        #[cfg(windows)]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+        cmd
+    } else {
+        Command::new(command)
+    }
+}
+
+fn json_messages(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(batch) => batch,
+        single => vec![single],
+    }
+}
+
+impl Upstream {
+    /// Connect to the server and complete the MCP initialize handshake.
+    pub async fn connect(
+        config: GatewayServerConfig,
+        notifications: mpsc::UnboundedSender<UpstreamNotification>,
+    ) -> Result<Arc<Self>, String> {
+        let dispatcher = Arc::new(Dispatcher {
+            pending: Mutex::new(HashMap::new()),
+            notifications,
+        });
+
+        if !config.is_remote() {
+            let transport = Self::spawn_stdio(&config, Arc::clone(&dispatcher))?;
+            return Self::initialize(config, transport, dispatcher).await;
+        }
+
+        let url = config
+            .url
+            .clone()
+            .filter(|u| !u.trim().is_empty())
+            .ok_or_else(|| format!("{} has no URL configured", config.name))?;
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        // Servers configured as "sse" are usually legacy SSE endpoints, but
+        // many also speak Streamable HTTP; try the likely one first.
+        let prefer_sse = url.trim_end_matches('/').ends_with("/sse");
+        let attempts: [bool; 2] = if prefer_sse { [true, false] } else { [false, true] };
+
+        let mut last_error = String::new();
+        for use_sse in attempts {
+            let transport = if use_sse {
+                match Self::open_sse(&client, &url, Arc::clone(&dispatcher)).await {
+                    Ok(transport) => transport,
+                    Err(e) => {
+                        last_error = e;
+                        continue;
+                    }
+                }
+            } else {
+                Transport::StreamableHttp {
+                    client: client.clone(),
+                    url: url.clone(),
+                    session_id: Arc::new(Mutex::new(None)),
+                }
+            };
+
+            match Self::initialize(config.clone(), transport, Arc::clone(&dispatcher)).await {
+                Ok(upstream) => return Ok(upstream),
+                Err(e) => last_error = e,
+            }
+        }
+
+        Err(last_error)
+    }
+
+    fn spawn_stdio(
+        config: &GatewayServerConfig,
+        dispatcher: Arc<Dispatcher>,
+    ) -> Result<Transport, String> {
+        let mut envs = config.env.clone();
+        for key in &config.secrets {
+            if let Ok(value) = SecretManager::get_secret(&config.id, key) {
+                redaction::register_secret(&value);
+                envs.insert(key.clone(), value);
+            }
+        }
+
+        let mut child = build_command(&config.command)
+            .args(&config.args)
+            .envs(envs)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to spawn {}: {}", config.name, e))?;
+
+        let stdin = Arc::new(Mutex::new(child.stdin.take().ok_or("Failed to open stdin")?));
+        let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
+        let stderr = child.stderr.take().ok_or("Failed to open stderr")?;
+
+        let name = config.name.clone();
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = reader.next_line().await {
+                eprintln!("[Relay][{}] {}", name, redaction::redact(&line));
+            }
+        });
+
+        let name = config.name.clone();
+        let reply_stdin = Arc::clone(&stdin);
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = reader.next_line().await {
+                let Ok(value) = serde_json::from_str::<Value>(&line) else {
+                    if !line.trim().is_empty() {
+                        eprintln!("[Relay][{}] {}", name, redaction::redact(&line));
+                    }
+                    continue;
+                };
+                for message in json_messages(value) {
+                    if let Some(reply) = dispatcher.dispatch(message).await {
+                        let mut stdin = reply_stdin.lock().await;
+                        let _ = stdin.write_all(format!("{}\n", reply).as_bytes()).await;
+                    }
+                }
+            }
+            eprintln!("[Relay] {} closed its output stream", name);
+            dispatcher.disconnect().await;
+        });
+
+        Ok(Transport::Stdio {
+            stdin,
+            child: Mutex::new(child),
+        })
+    }
+
+    /// Open a legacy HTTP+SSE connection and wait for the `endpoint` event
+    /// that tells us where to POST messages.
+    async fn open_sse(
+        client: &reqwest::Client,
+        url: &str,
+        dispatcher: Arc<Dispatcher>,
+    ) -> Result<Transport, String> {
+        let mut response = client
+            .get(url)
+            .header("Accept", "text/event-stream")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to open SSE stream: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("SSE stream returned HTTP {}", response.status()));
+        }
+
+        let base = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+        let mut parser = SseParser::default();
+        let mut endpoint = None;
+        let mut early_events = Vec::new();
+
+        while endpoint.is_none() {
+            let chunk = timeout(Duration::from_secs(INITIALIZE_TIMEOUT_SECS), response.chunk())
+                .await
+                .map_err(|_| "Timed out waiting for SSE endpoint event".to_string())?
+                .map_err(|e| format!("SSE stream error: {}", e))?
+                .ok_or_else(|| "SSE stream closed before endpoint event".to_string())?;
+
+            for event in parser.push(&String::from_utf8_lossy(&chunk)) {
+                if event.event == "endpoint" && endpoint.is_none() {
+                    let resolved = base
+                        .join(event.data.trim())
+                        .map_err(|e| format!("Invalid SSE endpoint: {}", e))?;
+                    endpoint = Some(resolved.to_string());
+                } else {
+                    early_events.push(event);
+                }
+            }
+        }
+
+        let endpoint = endpoint.unwrap_or_default();
+        let reader_client = client.clone();
+        let reply_endpoint = endpoint.clone();
+        let reader = tokio::spawn(async move {
+            let mut pending_events = early_events;
+            loop {
+                for event in pending_events.drain(..) {
+                    if event.event != "message" && !event.event.is_empty() {
+                        continue;
+                    }
+                    let Ok(value) = serde_json::from_str::<Value>(&event.data) else {
+                        continue;
+                    };
+                    for message in json_messages(value) {
+                        if let Some(reply) = dispatcher.dispatch(message).await {
+                            // Best effort: replies are only pings and roots/list.
+                            let _ = reader_client.post(&reply_endpoint).json(&reply).send().await;
+                        }
+                    }
+                }
+
+                match response.chunk().await {
+                    Ok(Some(chunk)) => {
+                        pending_events = parser.push(&String::from_utf8_lossy(&chunk));
+                    }
+                    _ => break,
+                }
+            }
+            dispatcher.disconnect().await;
+        });
+
+        Ok(Transport::Sse {
+            client: client.clone(),
+            endpoint,
+            reader,
+        })
+    }
+
+    async fn initialize(
+        config: GatewayServerConfig,
+        transport: Transport,
+        dispatcher: Arc<Dispatcher>,
+    ) -> Result<Arc<Self>, String> {
+        let upstream = Arc::new(Self {
+            config,
+            transport,
+            dispatcher,
+            next_id: AtomicU64::new(1),
+            capabilities: OnceLock::new(),
+        });
+
+        let params = json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "roots": { "listChanged": false } },
+            "clientInfo": {
+                "name": "Relay-Gateway",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        });
+
+        let response = match upstream
+            .request_with_timeout("initialize", params, INITIALIZE_TIMEOUT_SECS)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                upstream.shutdown().await;
+                return Err(e);
+            }
+        };
+
+        if let Some(error) = response.get("error") {
+            upstream.shutdown().await;
+            return Err(format!(
+                "{} returned initialize error: {}",
+                upstream.config.name, error
+            ));
+        }
+
+        let capabilities = response
+            .pointer("/result/capabilities")
+            .cloned()
+            .unwrap_or(json!({}));
+        let _ = upstream.capabilities.set(capabilities);
+
+        upstream
+            .send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await?;
+
+        Ok(upstream)
+    }
+
+    /// Whether the server advertised a capability (`tools`, `resources`, ...).
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities
+            .get()
+            .map(|c| c.get(capability).is_some())
+            .unwrap_or(false)
+    }
+
+    async fn send(&self, message: Value) -> Result<(), String> {
+        match &self.transport {
+            Transport::Stdio { stdin, .. } => {
+                let mut stdin = stdin.lock().await;
+                stdin
+                    .write_all(format!("{}\n", message).as_bytes())
+                    .await
+                    .map_err(|e| format!("Failed to write to {}: {}", self.config.name, e))?;
+                stdin
+                    .flush()
+                    .await
+                    .map_err(|e| format!("Failed to write to {}: {}", self.config.name, e))
+            }
+            Transport::StreamableHttp {
+                client,
+                url,
+                session_id,
+            } => {
+                let mut request = client
+                    .post(url)
+                    .header("Accept", "application/json, text/event-stream")
+                    .header("MCP-Protocol-Version", PROTOCOL_VERSION)
+                    .json(&message);
+                if let Some(session) = session_id.lock().await.as_ref() {
+                    request = request.header("Mcp-Session-Id", session);
+                }
+
+                let response = request
+                    .send()
+                    .await
+                    .map_err(|e| format!("HTTP request to {} failed: {}", self.config.name, e))?;
+
+                if !response.status().is_success() {
+                    return Err(format!(
+                        "{} returned HTTP {}",
+                        self.config.name,
+                        response.status()
+                    ));
+                }
+
+                if let Some(session) = response
+                    .headers()
+                    .get("mcp-session-id")
+                    .and_then(|v| v.to_str().ok())
+                {
+                    *session_id.lock().await = Some(session.to_string());
+                }
+
+                let is_sse = response
+                    .headers()
+                    .get("content-type")
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.starts_with("text/event-stream"))
+                    .unwrap_or(false);
+
+                // Read the body in the background so long-running streamed
+                // responses don't hold up the caller; results arrive through
+                // the dispatcher like on every other transport.
+                let dispatcher = Arc::clone(&self.dispatcher);
+                tokio::spawn(async move {
+                    let mut response = response;
+                    let mut messages = Vec::new();
+                    if is_sse {
+                        let mut parser = SseParser::default();
+                        while let Ok(Some(chunk)) = response.chunk().await {
+                            for event in parser.push(&String::from_utf8_lossy(&chunk)) {
+                                if let Ok(value) = serde_json::from_str::<Value>(&event.data) {
+                                    messages.extend(json_messages(value));
+                                }
+                            }
+                            for message in messages.drain(..) {
+                                dispatcher.dispatch(message).await;
+                            }
+                        }
+                    } else if let Ok(text) = response.text().await {
+                        if let Ok(value) = serde_json::from_str::<Value>(&text) {
+                            messages.extend(json_messages(value));
+                        }
+                        for message in messages {
+                            dispatcher.dispatch(message).await;
+                        }
+                    }
+                });
+
+                Ok(())
+            }
+            Transport::Sse {
+                client, endpoint, ..
+            } => {
+                let response = client
+                    .post(endpoint)
+                    .json(&message)
+                    .send()
+                    .await
+                    .map_err(|e| format!("HTTP request to {} failed: {}", self.config.name, e))?;
+
+                if response.status().is_success() {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{} returned HTTP {}",
+                        self.config.name,
+                        response.status()
+                    ))
+                }
+            }
+        }
+    }
+
+    async fn request_with_timeout(
+        &self,
+        method: &str,
+        params: Value,
+        timeout_secs: u64,
+    ) -> Result<Value, String> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (waiter, response) = oneshot::channel();
+        self.dispatcher.pending.lock().await.insert(id, waiter);
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        });
+
+        if let Err(e) = self.send(request).await {
+            self.dispatcher.pending.lock().await.remove(&id);
+            return Err(e);
+        }
+
+        match timeout(Duration::from_secs(timeout_secs), response).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(format!(
+                "{} disconnected during {}",
+                self.config.name, method
+            )),
+            Err(_) => {
+                self.dispatcher.pending.lock().await.remove(&id);
+                Err(format!(
+                    "Timeout waiting for {} during {} (waited {}s)",
+                    self.config.name, method, timeout_secs
+                ))
+            }
+        }
+    }
+
+    /// Send a request and wait for its response. The full JSON-RPC response
+    /// is returned, including any `error` member.
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+        self.request_with_timeout(method, params, REQUEST_TIMEOUT_SECS)
+            .await
+    }
+
+    pub async fn shutdown(&self) {
+        match &self.transport {
+            Transport::Stdio { child, .. } => {
+                let _ = child.lock().await.kill().await;
+            }
+            Transport::StreamableHttp {
+                client,
+                url,
+                session_id,
+            } => {
+                if let Some(session) = session_id.lock().await.take() {
+                    let _ = client
+                        .delete(url)
+                        .header("Mcp-Session-Id", session)
+                        .send()
+                        .await;
+                }
+            }
+            Transport::Sse { reader, .. } => reader.abort(),
+        }
+        self.dispatcher.disconnect().await;
+    }
+}
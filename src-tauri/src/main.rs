@@ -1,11 +1,16 @@
 // Prevents additional console window on Windows in release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod audit;
+mod bridge;
 mod commands;
 mod db;
+mod gateway;
+mod metrics;
 mod models;
 mod proxy;
 mod state;
+mod telemetry;
 mod utils;
 
 use state::AppState;
@@ -13,6 +18,23 @@ use tauri::Manager;
 
 #[tokio::main]
 async fn main() {
+    // `relay --gateway` serves the aggregated MCP gateway on stdio instead of
+    // launching the desktop app.
+    let cli_args: Vec<String> = std::env::args().collect();
+    if cli_args.iter().any(|a| a == "--gateway") {
+        let config_path = gateway::config::resolve_config_path(&cli_args)
+            .expect("Could not determine gateway config path");
+        let result = match gateway::config::resolve_tool_mode(&cli_args) {
+            Ok(tool_mode) => gateway::run_stdio(config_path, tool_mode).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            eprintln!("[Relay] Gateway error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Initialize database
     let db_pool = db::init_db()
         .await
@@ -24,10 +46,20 @@ async fn main() {
     tauri::Builder::default()
         .manage(app_state)
         .setup(|app| {
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                commands::sessions::start_control_listener(&handle);
+                let state: tauri::State<AppState> = handle.state();
+                commands::settings::load_tokenizer(&state).await;
+                let _ = commands::servers::sync_servers(state.clone(), handle.clone()).await;
+                commands::gateway::restore_http_gateway(&state, &handle).await;
+                commands::metrics::restore_metrics_endpoint(&state).await;
+                commands::bridges::restore_bridges(&state, &handle).await;
+            });
             let handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 let state: tauri::State<AppState> = handle.state();
-                let _ = commands::servers::sync_servers(state, handle.clone()).await;
+                proxy::flush_usage_periodically(&state).await;
             });
             Ok(())
         })
@@ -52,6 +84,14 @@ async fn main() {
             commands::config::read_claude_config,
             commands::config::export_config_to_path,
             commands::config::expand_path,
+            // Gateway commands
+            commands::gateway::start_http_gateway,
+            commands::gateway::stop_http_gateway,
+            commands::gateway::get_http_gateway_status,
+            commands::gateway::list_gateway_clients,
+            commands::gateway::create_gateway_client,
+            commands::gateway::revoke_gateway_client,
+            commands::gateway::clear_response_cache,
             // Settings commands
             commands::settings::get_settings,
             commands::settings::update_settings,
@@ -60,6 +100,8 @@ async fn main() {
             // MCP commands
             commands::mcp::list_server_tools,
             commands::mcp::call_server_tool,
+            // Regression commands
+            commands::regression::replay_regression,
             // Diagnostics commands
             commands::diagnostics::check_server_dependencies,
             commands::diagnostics::test_server_connection,
@@ -67,9 +109,56 @@ async fn main() {
             commands::marketplace::fetch_marketplace_servers,
             // Conflict detection
             commands::conflicts::detect_tool_conflicts,
+            commands::conflicts::detect_stale_tool_overrides,
+            commands::conflicts::get_exposed_tool_names,
+            // Approval commands
+            commands::approvals::resolve_tool_approval,
+            commands::approvals::list_pending_approvals,
+            commands::approvals::list_approval_decisions,
+            // Audit commands
+            commands::audit::get_audit_log,
+            commands::audit::verify_audit_log,
+            commands::audit::export_audit_log,
+            // Traffic commands
+            commands::traffic::get_traffic_capture,
+            commands::traffic::clear_traffic_capture,
+            commands::traffic::export_traffic_capture,
+            // Metrics commands
+            commands::metrics::get_request_metrics,
+            commands::metrics::start_metrics_endpoint,
+            commands::metrics::stop_metrics_endpoint,
+            commands::metrics::get_metrics_endpoint_status,
+            // Gateway session commands
+            commands::sessions::list_gateway_sessions,
+            commands::sessions::send_gateway_command,
+            // Toolset commands
+            commands::toolsets::get_toolsets,
+            commands::toolsets::create_toolset,
+            commands::toolsets::update_toolset,
+            commands::toolsets::set_toolset_active,
+            commands::toolsets::delete_toolset,
+            // Bridge commands
+            commands::bridges::get_bridges,
+            commands::bridges::start_bridge,
+            commands::bridges::stop_bridge,
+            // Usage history
+            commands::usage::get_context_usage_history,
+            commands::usage::get_context_usage_totals,
+            commands::usage::get_tool_usage,
+            commands::usage::get_tool_list_cost,
             // Update commands
             commands::updates::check_update,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Write out usage still buffered, which would otherwise be lost
+            // on quit.
+            if let tauri::RunEvent::Exit = event {
+                let state: tauri::State<AppState> = app.state();
+                tokio::task::block_in_place(|| {
+                    tokio::runtime::Handle::current().block_on(proxy::flush_usage_samples(&state))
+                });
+            }
+        });
 }
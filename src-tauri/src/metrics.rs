@@ -0,0 +1,361 @@
+//! Rolling latency and error metrics per server, method and tool, and the
+//! optional Prometheus endpoint that exposes them.
+
+use crate::proxy::ContextUsageStats;
+use axum::routing::get;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+
+/// Samples older than this are dropped; also the longest window available.
+const MAX_WINDOW_SECS: u64 = 3600;
+/// Caps memory for a very chatty series; percentiles then cover the newest
+/// samples only.
+const MAX_SAMPLES_PER_SERIES: usize = 10_000;
+/// Window behind the quantiles on the Prometheus endpoint.
+const PROMETHEUS_WINDOW_SECS: u64 = 300;
+
+pub const DEFAULT_WINDOW_SECS: u64 = 300;
+
+/// One completed request to an upstream server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestSample {
+    pub server_id: String,
+    pub method: String,
+    /// Tool name for `tools/call`.
+    pub tool: Option<String>,
+    pub latency_ms: u64,
+    /// Transport failure, JSON-RPC error, or a result with `isError`.
+    pub is_error: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SeriesKey {
+    server_id: String,
+    method: String,
+    tool: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Series {
+    /// (recorded at, latency, error), oldest first.
+    samples: VecDeque<(Instant, u64, bool)>,
+    /// Since startup, for Prometheus counters.
+    total_count: u64,
+    total_errors: u64,
+    total_latency_ms: u64,
+}
+
+/// Latency distribution and error rate of one series, or of a whole server
+/// when `method` is absent.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyStats {
+    pub server_id: String,
+    pub method: Option<String>,
+    pub tool: Option<String>,
+    pub count: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub avg_ms: f64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+}
+
+impl LatencyStats {
+    fn from_samples(server_id: &str, method: Option<&str>, tool: Option<&str>, samples: &[(u64, bool)]) -> Self {
+        let mut latencies: Vec<u64> = samples.iter().map(|(latency, _)| *latency).collect();
+        latencies.sort_unstable();
+        let count = latencies.len() as u64;
+        let errors = samples.iter().filter(|(_, error)| *error).count() as u64;
+        let sum: u64 = latencies.iter().sum();
+
+        Self {
+            server_id: server_id.to_string(),
+            method: method.map(str::to_string),
+            tool: tool.map(str::to_string),
+            count,
+            errors,
+            error_rate: if count > 0 { errors as f64 / count as f64 } else { 0.0 },
+            avg_ms: if count > 0 { sum as f64 / count as f64 } else { 0.0 },
+            p50_ms: percentile(&latencies, 0.50),
+            p95_ms: percentile(&latencies, 0.95),
+            p99_ms: percentile(&latencies, 0.99),
+            max_ms: latencies.last().copied().unwrap_or(0),
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values.
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub window_secs: u64,
+    pub generated_at: String,
+    /// Per server, slowest p95 first.
+    pub servers: Vec<LatencyStats>,
+    /// Per server, method and tool, slowest p95 first.
+    pub series: Vec<LatencyStats>,
+}
+
+/// Name, help text and (inbound, outbound) values of a usage counter.
+type UsageCounter = (&'static str, &'static str, fn(&ContextUsageStats) -> [u64; 2]);
+
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    series: HashMap<SeriesKey, Series>,
+}
+
+impl MetricsRegistry {
+    pub fn record(&mut self, sample: RequestSample) {
+        let key = SeriesKey {
+            server_id: sample.server_id,
+            method: sample.method,
+            tool: sample.tool,
+        };
+        let series = self.series.entry(key).or_default();
+        series.total_count += 1;
+        series.total_latency_ms += sample.latency_ms;
+        if sample.is_error {
+            series.total_errors += 1;
+        }
+        if series.samples.len() >= MAX_SAMPLES_PER_SERIES {
+            series.samples.pop_front();
+        }
+        series.samples.push_back((Instant::now(), sample.latency_ms, sample.is_error));
+        self.prune();
+    }
+
+    fn prune(&mut self) {
+        let Some(cutoff) = Instant::now().checked_sub(Duration::from_secs(MAX_WINDOW_SECS)) else {
+            return;
+        };
+        for series in self.series.values_mut() {
+            while series.samples.front().is_some_and(|(at, _, _)| *at < cutoff) {
+                series.samples.pop_front();
+            }
+        }
+    }
+
+    /// Samples of each series within the last `window_secs`.
+    fn windowed(&self, window_secs: u64) -> Vec<(&SeriesKey, Vec<(u64, bool)>)> {
+        let window = Duration::from_secs(window_secs.min(MAX_WINDOW_SECS));
+        let cutoff = Instant::now().checked_sub(window);
+        self.series
+            .iter()
+            .map(|(key, series)| {
+                let samples = series
+                    .samples
+                    .iter()
+                    .filter(|(at, _, _)| cutoff.is_none_or(|cutoff| *at >= cutoff))
+                    .map(|(_, latency, error)| (*latency, *error))
+                    .collect();
+                (key, samples)
+            })
+            .collect()
+    }
+
+    pub fn snapshot(&self, window_secs: u64, server_id: Option<&str>) -> MetricsSnapshot {
+        let mut per_server: HashMap<&str, Vec<(u64, bool)>> = HashMap::new();
+        let mut series = Vec::new();
+
+        for (key, samples) in self.windowed(window_secs) {
+            if samples.is_empty() || server_id.is_some_and(|id| id != key.server_id) {
+                continue;
+            }
+            series.push(LatencyStats::from_samples(
+                &key.server_id,
+                Some(&key.method),
+                key.tool.as_deref(),
+                &samples,
+            ));
+            per_server.entry(&key.server_id).or_default().extend(samples);
+        }
+
+        let mut servers: Vec<LatencyStats> = per_server
+            .into_iter()
+            .map(|(server_id, samples)| LatencyStats::from_samples(server_id, None, None, &samples))
+            .collect();
+        servers.sort_by_key(|s| std::cmp::Reverse(s.p95_ms));
+        series.sort_by_key(|s| std::cmp::Reverse(s.p95_ms));
+
+        MetricsSnapshot {
+            window_secs: window_secs.min(MAX_WINDOW_SECS),
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            servers,
+            series,
+        }
+    }
+
+    /// Prometheus text exposition: a latency summary per series (quantiles
+    /// over a rolling window, count and sum since startup), error counters,
+    /// and the context usage counters.
+    pub fn prometheus(&self, usage: &[ContextUsageStats], names: &HashMap<String, String>) -> String {
+        let server_labels = |server_id: &str| {
+            format!(
+                "server_id=\"{}\",server=\"{}\"",
+                escape_label(server_id),
+                escape_label(names.get(server_id).map(String::as_str).unwrap_or(server_id))
+            )
+        };
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "# HELP relay_request_duration_milliseconds Upstream request latency; quantiles over the last {} seconds.",
+            PROMETHEUS_WINDOW_SECS
+        );
+        let _ = writeln!(out, "# TYPE relay_request_duration_milliseconds summary");
+        let mut windowed = self.windowed(PROMETHEUS_WINDOW_SECS);
+        windowed.sort_by(|(a, _), (b, _)| (&a.server_id, &a.method, &a.tool).cmp(&(&b.server_id, &b.method, &b.tool)));
+        for (key, samples) in &windowed {
+            let labels = format!(
+                "{},method=\"{}\",tool=\"{}\"",
+                server_labels(&key.server_id),
+                escape_label(&key.method),
+                escape_label(key.tool.as_deref().unwrap_or(""))
+            );
+            let stats = LatencyStats::from_samples(&key.server_id, None, None, samples);
+            if stats.count > 0 {
+                for (quantile, value) in [("0.5", stats.p50_ms), ("0.95", stats.p95_ms), ("0.99", stats.p99_ms)] {
+                    let _ = writeln!(
+                        out,
+                        "relay_request_duration_milliseconds{{{},quantile=\"{}\"}} {}",
+                        labels, quantile, value
+                    );
+                }
+            }
+            let series = &self.series[*key];
+            let _ = writeln!(out, "relay_request_duration_milliseconds_sum{{{}}} {}", labels, series.total_latency_ms);
+            let _ = writeln!(out, "relay_request_duration_milliseconds_count{{{}}} {}", labels, series.total_count);
+        }
+
+        let _ = writeln!(out, "# HELP relay_request_errors_total Upstream requests that failed or returned an error.");
+        let _ = writeln!(out, "# TYPE relay_request_errors_total counter");
+        for (key, _) in &windowed {
+            let _ = writeln!(
+                out,
+                "relay_request_errors_total{{{},method=\"{}\",tool=\"{}\"}} {}",
+                server_labels(&key.server_id),
+                escape_label(&key.method),
+                escape_label(key.tool.as_deref().unwrap_or("")),
+                self.series[*key].total_errors
+            );
+        }
+
+        let mut usage: Vec<&ContextUsageStats> = usage.iter().collect();
+        usage.sort_by(|a, b| a.server_id.cmp(&b.server_id));
+        let counters: [UsageCounter; 3] = [
+            ("relay_context_bytes_total", "JSON-RPC bytes exchanged with a server.", |u| {
+                [u.bytes_in, u.bytes_out]
+            }),
+            ("relay_context_tokens_total", "Tokens exchanged with a server.", |u| [u.tokens_in, u.tokens_out]),
+            ("relay_messages_total", "JSON-RPC messages exchanged with a server.", |u| {
+                [u.messages_in, u.messages_out]
+            }),
+        ];
+        for (name, help, values) in counters {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for stats in &usage {
+                let [inbound, outbound] = values(stats);
+                let labels = server_labels(&stats.server_id);
+                let _ = writeln!(out, "{}{{{},direction=\"inbound\"}} {}", name, labels, inbound);
+                let _ = writeln!(out, "{}{{{},direction=\"outbound\"}} {}", name, labels, outbound);
+            }
+        }
+
+        out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// What the Prometheus endpoint reads on every scrape.
+#[derive(Clone)]
+pub struct MetricsSources {
+    pub registry: Arc<Mutex<MetricsRegistry>>,
+    pub usage: Arc<Mutex<HashMap<String, ContextUsageStats>>>,
+    pub db: Arc<Mutex<SqlitePool>>,
+}
+
+async fn render(sources: &MetricsSources) -> String {
+    let pool = sources.db.lock().await.clone();
+    let names: HashMap<String, String> = sqlx::query_as::<_, (String, String)>("SELECT id, name FROM servers")
+        .fetch_all(&pool)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    let usage: Vec<ContextUsageStats> = sources.usage.lock().await.values().cloned().collect();
+    sources.registry.lock().await.prometheus(&usage, &names)
+}
+
+/// `GET /metrics` on localhost, in Prometheus text format.
+pub struct MetricsEndpoint {
+    pub port: u16,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl MetricsEndpoint {
+    pub async fn start(sources: MetricsSources, port: u16) -> Result<Self, String> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .await
+            .map_err(|e| format!("Failed to bind 127.0.0.1:{}: {}", port, e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| e.to_string())?
+            .port();
+
+        let router = Router::new().route(
+            "/metrics",
+            get(move || {
+                let sources = sources.clone();
+                async move {
+                    (
+                        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+                        render(&sources).await,
+                    )
+                }
+            }),
+        );
+
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let _ = axum::serve(listener, router)
+                .with_graceful_shutdown(async {
+                    let _ = shutdown_rx.await;
+                })
+                .await;
+        });
+
+        Ok(Self { port, shutdown, task })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}/metrics", self.port)
+    }
+
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.task.await;
+    }
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Bridge {
+    pub server_id: String,
+    pub mode: String, // 'shared' | 'isolated'
+    /// Last port the bridge listened on.
+    pub port: i64,
+    /// Listen on all interfaces instead of localhost only.
+    pub lan: bool,
+    /// Started when the app launches.
+    pub enabled: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
@@ -1,3 +1,5 @@
+pub mod bridge;
 pub mod profile;
 pub mod server;
 pub mod settings;
+pub mod toolset;
@@ -1,6 +1,14 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use std::collections::HashMap;
+use crate::gateway::approval::ApprovalPolicy;
+use crate::gateway::cache::CachePolicy;
+use crate::gateway::filter::ToolFilter;
+use crate::gateway::limits::RateLimitPolicy;
+use crate::gateway::overrides::ToolOverrides;
+use crate::gateway::policy::ResponsePolicy;
+use crate::gateway::transforms::TransformPolicy;
+use crate::utils::patch;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Server {
@@ -21,6 +29,14 @@ pub struct Server {
     pub previous_config: Option<String>,
     pub transport: Option<String>,
     pub url: Option<String>,
+    pub response_policy: Option<String>, // JSON ResponsePolicy
+    pub tool_filter: Option<String>,     // JSON ToolFilter
+    pub tool_overrides: Option<String>,  // JSON map of tool name -> ToolOverride
+    pub alias: Option<String>,           // Short tool namespace prefix
+    pub approval_policy: Option<String>, // JSON ApprovalPolicy
+    pub cache_policy: Option<String>,    // JSON CachePolicy
+    pub rate_limit: Option<String>,      // JSON RateLimitPolicy
+    pub transforms: Option<String>,      // JSON TransformPolicy
     pub created_at: String,
     pub updated_at: String,
 }
@@ -37,6 +53,14 @@ pub struct CreateServerInput {
     pub marketplace_id: Option<String>,
     pub transport: Option<String>,
     pub url: Option<String>,
+    pub response_policy: Option<ResponsePolicy>,
+    pub tool_filter: Option<ToolFilter>,
+    pub tool_overrides: Option<ToolOverrides>,
+    pub alias: Option<String>,
+    pub approval_policy: Option<ApprovalPolicy>,
+    pub cache_policy: Option<CachePolicy>,
+    pub rate_limit: Option<RateLimitPolicy>,
+    pub transforms: Option<TransformPolicy>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,4 +76,20 @@ pub struct UpdateServerInput {
     pub category: Option<String>,
     pub transport: Option<String>,
     pub url: Option<String>,
+    /// Policies: omitted ones are left as they are; `null` removes one.
+    #[serde(default, deserialize_with = "patch::deserialize")]
+    pub response_policy: Option<Option<ResponsePolicy>>,
+    #[serde(default, deserialize_with = "patch::deserialize")]
+    pub tool_filter: Option<Option<ToolFilter>>,
+    #[serde(default, deserialize_with = "patch::deserialize")]
+    pub tool_overrides: Option<Option<ToolOverrides>>,
+    pub alias: Option<String>,
+    #[serde(default, deserialize_with = "patch::deserialize")]
+    pub approval_policy: Option<Option<ApprovalPolicy>>,
+    #[serde(default, deserialize_with = "patch::deserialize")]
+    pub cache_policy: Option<Option<CachePolicy>>,
+    #[serde(default, deserialize_with = "patch::deserialize")]
+    pub rate_limit: Option<Option<RateLimitPolicy>>,
+    #[serde(default, deserialize_with = "patch::deserialize")]
+    pub transforms: Option<Option<TransformPolicy>>,
 }
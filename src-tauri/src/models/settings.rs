@@ -5,4 +5,12 @@ pub struct AppSettings {
     pub theme: String,
     #[serde(rename = "autoExport")]
     pub auto_export: bool,
+    /// Tokenizer used for context accounting (`cl100k_base`, `o200k_base`
+    /// or `heuristic`).
+    #[serde(default = "default_tokenizer")]
+    pub tokenizer: String,
+}
+
+fn default_tokenizer() -> String {
+    "cl100k_base".to_string()
 }
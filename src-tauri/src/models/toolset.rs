@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use crate::gateway::toolsets::ToolsetEntry;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Toolset {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub tools: String, // JSON array of ToolsetEntry
+    pub active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateToolsetInput {
+    pub name: String,
+    pub description: Option<String>,
+    pub tools: Vec<ToolsetEntry>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateToolsetInput {
+    pub id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tools: Option<Vec<ToolsetEntry>>,
+    pub active: Option<bool>,
+}
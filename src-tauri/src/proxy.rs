@@ -1,7 +1,23 @@
+use crate::audit::{self, CallStatus, ToolCallRecord};
+use crate::gateway::cache::CacheHit;
+use crate::gateway::limits::{LimitEvent, LimitOutcome};
+use crate::gateway::policy::PolicyHit;
+use crate::gateway::transforms::TransformReport;
+use crate::gateway::TrafficSample;
+use crate::metrics::RequestSample;
 use crate::state::AppState;
+use crate::utils::redaction;
+use crate::utils::tokenizer::context_text;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::{HashMap, VecDeque};
 use tauri::{AppHandle, Emitter};
 
+/// Maximum number of JSON-RPC messages kept per server for inspection and
+/// replay.
+const CAPTURE_LIMIT: usize = 1000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContextUsageStats {
     pub server_id: String,
@@ -13,6 +29,20 @@ pub struct ContextUsageStats {
     pub total_tokens: u64,
     pub messages_in: u64,
     pub messages_out: u64,
+    /// Tool results cut down or rejected by a response policy.
+    pub policy_hits: u64,
+    pub policy_tokens_saved: u64,
+    /// Tool results rewritten by the server's transforms, and the tokens and
+    /// bytes that saved (net of transforms that add some).
+    pub transform_hits: u64,
+    pub transform_tokens_saved: u64,
+    pub transform_bytes_saved: u64,
+    /// Tool calls answered from the gateway's response cache.
+    pub cache_hits: u64,
+    /// Tool calls held back by a rate or concurrency limit, and refused by one.
+    pub limit_queued: u64,
+    pub limit_queue_ms: u64,
+    pub limit_rejected: u64,
     pub updated_at: String,
 }
 
@@ -28,36 +58,352 @@ impl ContextUsageStats {
             total_tokens: 0,
             messages_in: 0,
             messages_out: 0,
+            policy_hits: 0,
+            policy_tokens_saved: 0,
+            transform_hits: 0,
+            transform_tokens_saved: 0,
+            transform_bytes_saved: 0,
+            cache_hits: 0,
+            limit_queued: 0,
+            limit_queue_ms: 0,
+            limit_rejected: 0,
             updated_at: chrono::Utc::now().to_rfc3339(),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TrafficDirection {
     Inbound,
     Outbound,
 }
 
-fn estimate_tokens(bytes: usize) -> u64 {
-    if bytes == 0 {
-        return 0;
+impl TrafficDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrafficDirection::Inbound => "inbound",
+            TrafficDirection::Outbound => "outbound",
+        }
     }
+}
 
-    // Conservative heuristic for mixed MCP JSON payloads.
-    ((bytes as f64) / 4.0).ceil() as u64
+const USAGE_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Usage of one hourly bucket not yet written to `context_usage_samples`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PendingUsage {
+    pub bytes: u64,
+    pub tokens: u64,
+    pub messages: u64,
 }
 
-pub async fn record_traffic(
+/// (server id, hour bucket, direction) -> usage.
+pub type PendingUsageSamples = HashMap<(String, String, TrafficDirection), PendingUsage>;
+
+/// A JSON-RPC message seen on a server connection, kept for inspection and
+/// replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedMessage {
+    /// Increases per server and survives eviction, so it can be used as a
+    /// stable reference.
+    pub seq: u64,
+    pub server_id: String,
+    pub direction: TrafficDirection,
+    /// For responses, the method of the request they answer.
+    pub method: Option<String>,
+    /// JSON-RPC id; absent for notifications.
+    pub id: Option<Value>,
+    /// Redacted; this is what the frontend and exports see.
+    pub payload: Value,
+    /// The unredacted payload of an outbound request, kept in memory only,
+    /// for replay.
+    #[serde(skip)]
+    pub replay: Option<Value>,
+    pub bytes: u64,
+    pub timestamp: String,
+    /// `seq` of the matching request or response, once paired.
+    pub paired_seq: Option<u64>,
+    /// Request to response time; set on both sides once paired.
+    pub latency_ms: Option<u64>,
+    /// An error response, or a request that got one.
+    pub is_error: bool,
+}
+
+/// Bounded capture of one server's traffic, oldest first.
+#[derive(Debug, Default)]
+pub struct TrafficCapture {
+    next_seq: u64,
+    pub messages: VecDeque<CapturedMessage>,
+}
+
+impl TrafficCapture {
+    /// Capture a message, pairing a response with the request it answers.
+    fn push(
+        &mut self,
+        server_id: &str,
+        direction: TrafficDirection,
+        payload: Value,
+        replay: Option<Value>,
+        bytes: u64,
+    ) -> CapturedMessage {
+        self.next_seq += 1;
+        let timestamp = chrono::Utc::now();
+        let mut message = CapturedMessage {
+            seq: self.next_seq,
+            server_id: server_id.to_string(),
+            direction,
+            method: payload.get("method").and_then(Value::as_str).map(str::to_string),
+            id: payload.get("id").cloned(),
+            is_error: payload.get("error").is_some()
+                || payload
+                    .pointer("/result/isError")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            payload,
+            replay,
+            bytes,
+            timestamp: timestamp.to_rfc3339(),
+            paired_seq: None,
+            latency_ms: None,
+        };
+
+        if message.method.is_none() && message.id.is_some() {
+            // Requests flow either way (servers send pings too), so look for
+            // the latest unanswered request from the other side.
+            let request = self.messages.iter_mut().rev().find(|m| {
+                m.direction != direction && m.method.is_some() && m.paired_seq.is_none() && m.id == message.id
+            });
+            if let Some(request) = request {
+                let latency_ms = chrono::DateTime::parse_from_rfc3339(&request.timestamp)
+                    .map(|sent| (timestamp - sent.with_timezone(&chrono::Utc)).num_milliseconds().max(0) as u64)
+                    .unwrap_or(0);
+                request.paired_seq = Some(message.seq);
+                request.latency_ms = Some(latency_ms);
+                request.is_error = message.is_error;
+                message.paired_seq = Some(request.seq);
+                message.latency_ms = Some(latency_ms);
+                message.method = request.method.clone();
+            }
+        }
+
+        if self.messages.len() >= CAPTURE_LIMIT {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message.clone());
+        message
+    }
+
+    pub fn get(&self, seq: u64) -> Option<&CapturedMessage> {
+        // `seq` is contiguous within the deque.
+        let first = self.messages.front()?.seq;
+        self.messages.get(seq.checked_sub(first)? as usize)
+    }
+}
+
+/// Call statistics for a single tool, built by pairing `tools/call` requests
+/// with their responses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUsageStats {
+    pub server_id: String,
+    pub tool: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_tokens: u64,
+    pub total_latency_ms: u64,
+    pub avg_latency_ms: f64,
+    pub max_latency_ms: u64,
+    /// Calls answered from the gateway's response cache; not in `calls`.
+    pub cache_hits: u64,
+    pub updated_at: String,
+}
+
+impl ToolUsageStats {
+    fn new(server_id: &str, tool: &str) -> Self {
+        Self {
+            server_id: server_id.to_string(),
+            tool: tool.to_string(),
+            calls: 0,
+            errors: 0,
+            error_rate: 0.0,
+            input_bytes: 0,
+            output_bytes: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            total_tokens: 0,
+            total_latency_ms: 0,
+            avg_latency_ms: 0.0,
+            max_latency_ms: 0,
+            cache_hits: 0,
+            updated_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Pending calls older than this are dropped; every connection has given up
+/// on them by then.
+const PENDING_TOOL_CALL_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// A `tools/call` request still waiting for its response.
+#[derive(Debug, Clone)]
+pub struct PendingToolCall {
+    /// The client that sent the call, for the audit log.
+    pub client: String,
+    pub tool: String,
+    pub arguments: Value,
+    pub timestamp: String,
+    pub bytes: u64,
+    pub tokens: u64,
+    pub started: std::time::Instant,
+}
+
+/// Pair `tools/call` requests with responses by JSON-RPC id and fold each
+/// completed call into the per-tool stats.
+async fn track_tool_call(
     state: &AppState,
-    app: &AppHandle,
     server_id: &str,
+    client: Option<&str>,
     direction: TrafficDirection,
-    payload: &str,
-) -> ContextUsageStats {
-    let bytes = payload.as_bytes().len();
-    let estimated_tokens = estimate_tokens(bytes);
+    message: &Value,
+    bytes: u64,
+    tokens: u64,
+) -> Option<ToolUsageStats> {
+    let id = message.get("id")?.to_string();
+    let key = (server_id.to_string(), id);
 
+    match direction {
+        TrafficDirection::Outbound => {
+            if message.get("method").and_then(Value::as_str) != Some("tools/call") {
+                return None;
+            }
+            let tool = message.pointer("/params/name")?.as_str()?.to_string();
+            let arguments = message
+                .pointer("/params/arguments")
+                .cloned()
+                .unwrap_or(Value::Null);
+            let mut pending = state.pending_tool_calls.lock().await;
+            // Calls whose response never came (timeouts, crashed servers).
+            pending.retain(|_, call| call.started.elapsed() < PENDING_TOOL_CALL_MAX_AGE);
+            pending.insert(
+                key,
+                PendingToolCall {
+                    client: client.unwrap_or_default().to_string(),
+                    tool,
+                    arguments,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                    bytes,
+                    tokens,
+                    started: std::time::Instant::now(),
+                },
+            );
+            None
+        }
+        TrafficDirection::Inbound => {
+            if message.get("method").is_some() {
+                return None; // a server-initiated request reusing the id space
+            }
+            let pending = state.pending_tool_calls.lock().await.remove(&key)?;
+            let latency_ms = pending.started.elapsed().as_millis() as u64;
+            let is_error = message.get("error").is_some()
+                || message
+                    .pointer("/result/isError")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+
+            audit_call(state, server_id, &pending, is_error, latency_ms).await;
+
+            let call = CompletedCall {
+                request: (pending.bytes, pending.tokens),
+                response: (bytes, tokens),
+                latency_ms,
+                is_error,
+            };
+            Some(add_tool_call(state, server_id, &pending.tool, call).await)
+        }
+    }
+}
+
+/// Sizes as (bytes, tokens).
+struct CompletedCall {
+    request: (u64, u64),
+    response: (u64, u64),
+    latency_ms: u64,
+    is_error: bool,
+}
+
+/// Fold a completed call into the tool's stats and return them.
+async fn add_tool_call(state: &AppState, server_id: &str, tool: &str, call: CompletedCall) -> ToolUsageStats {
+    let mut tool_map = state.tool_usage.lock().await;
+    let stats = tool_map
+        .entry(server_id.to_string())
+        .or_default()
+        .entry(tool.to_string())
+        .or_insert_with(|| ToolUsageStats::new(server_id, tool));
+
+    stats.calls += 1;
+    if call.is_error {
+        stats.errors += 1;
+    }
+    stats.error_rate = stats.errors as f64 / stats.calls as f64;
+    stats.input_bytes += call.request.0;
+    stats.output_bytes += call.response.0;
+    stats.input_tokens += call.request.1;
+    stats.output_tokens += call.response.1;
+    stats.total_tokens = stats.input_tokens + stats.output_tokens;
+    stats.total_latency_ms += call.latency_ms;
+    stats.avg_latency_ms = stats.total_latency_ms as f64 / stats.calls as f64;
+    stats.max_latency_ms = stats.max_latency_ms.max(call.latency_ms);
+    stats.updated_at = chrono::Utc::now().to_rfc3339();
+
+    stats.clone()
+}
+
+/// Calls made through the app's own connections (the inspector and bridges)
+/// go into the audit log alongside gateway calls.
+async fn audit_call(
+    state: &AppState,
+    server_id: &str,
+    call: &PendingToolCall,
+    is_error: bool,
+    latency_ms: u64,
+) {
+    let pool = state.db.lock().await.clone();
+    let server_name = sqlx::query_scalar::<_, String>("SELECT name FROM servers WHERE id = ?")
+        .bind(server_id)
+        .fetch_optional(&pool)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| server_id.to_string());
+
+    let record = ToolCallRecord {
+        timestamp: call.timestamp.clone(),
+        client: call.client.clone(),
+        server_id: server_id.to_string(),
+        server_name,
+        tool: call.tool.clone(),
+        arguments: call.arguments.clone(),
+        status: if is_error { CallStatus::Error } else { CallStatus::Ok },
+        duration_ms: latency_ms,
+        approval: None,
+    };
+    audit::record(&pool, &record).await;
+}
+
+/// Add one message to a server's context usage and to its hourly bucket,
+/// which `flush_usage_samples` writes out. Returns the updated totals.
+pub async fn record_usage(
+    state: &AppState,
+    server_id: &str,
+    direction: TrafficDirection,
+    bytes: u64,
+    tokens: u64,
+) -> ContextUsageStats {
     let snapshot = {
         let mut usage_map = state.context_usage.lock().await;
         let usage = usage_map
@@ -66,13 +412,13 @@ pub async fn record_traffic(
 
         match direction {
             TrafficDirection::Inbound => {
-                usage.bytes_in += bytes as u64;
-                usage.tokens_in += estimated_tokens;
+                usage.bytes_in += bytes;
+                usage.tokens_in += tokens;
                 usage.messages_in += 1;
             }
             TrafficDirection::Outbound => {
-                usage.bytes_out += bytes as u64;
-                usage.tokens_out += estimated_tokens;
+                usage.bytes_out += bytes;
+                usage.tokens_out += tokens;
                 usage.messages_out += 1;
             }
         }
@@ -84,6 +430,265 @@ pub async fn record_traffic(
         usage.clone()
     };
 
+    let bucket = chrono::Utc::now().format("%Y-%m-%dT%H:00:00Z").to_string();
+    let mut samples = state.usage_samples.lock().await;
+    let sample = samples
+        .entry((server_id.to_string(), bucket, direction))
+        .or_default();
+    sample.bytes += bytes;
+    sample.tokens += tokens;
+    sample.messages += 1;
+
+    snapshot
+}
+
+/// Record one message on a connection to a server. `client` names who sent
+/// an outbound message; responses are attributed to the client of the
+/// request they answer.
+pub async fn record_traffic(
+    state: &AppState,
+    app: &AppHandle,
+    server_id: &str,
+    client: Option<&str>,
+    direction: TrafficDirection,
+    payload: &str,
+) -> ContextUsageStats {
+    let bytes = payload.as_bytes().len();
+    let message = serde_json::from_str::<Value>(payload).ok();
+    let tokenizer = *state.tokenizer.lock().await;
+    let tokens = match &message {
+        Some(message) => tokenizer.count(&context_text(message)),
+        None => tokenizer.count(payload),
+    };
+
+    let snapshot = record_usage(state, server_id, direction, bytes as u64, tokens).await;
+
+    let tool_snapshot = match &message {
+        Some(message) => track_tool_call(state, server_id, client, direction, message, bytes as u64, tokens).await,
+        None => None,
+    };
+
+    let captured = match message {
+        Some(mut message) => {
+            let is_request = message.get("method").is_some() && message.get("id").is_some();
+            let replay = (direction == TrafficDirection::Outbound && is_request).then(|| message.clone());
+            redaction::redact_value(&mut message);
+            let mut capture_map = state.traffic_capture.lock().await;
+            let capture = capture_map.entry(server_id.to_string()).or_default();
+            let captured = capture.push(server_id, direction, message, replay, bytes as u64);
+            if let Some(sample) = request_sample(capture, &captured) {
+                state.metrics.lock().await.record(sample);
+            }
+            Some(captured)
+        }
+        None => None,
+    };
+
+    let _ = app.emit("context-usage", &snapshot);
+    if let Some(tool_snapshot) = tool_snapshot {
+        let _ = app.emit("tool-usage", &tool_snapshot);
+    }
+    if let Some(captured) = captured {
+        let _ = app.emit("traffic-message", &captured);
+    }
+    snapshot
+}
+
+/// Record traffic a gateway measured on its upstream connections: the same
+/// usage history and per-tool stats as `record_traffic`, from the sizes the
+/// gateway reports rather than payloads.
+pub async fn record_gateway_traffic(state: &AppState, app: &AppHandle, sample: &TrafficSample) {
+    let snapshot = record_usage(state, &sample.server_id, sample.direction, sample.bytes, sample.tokens).await;
+    let _ = app.emit("context-usage", &snapshot);
+
+    if let Some(call) = &sample.call {
+        let completed = CompletedCall {
+            request: (call.request_bytes, call.request_tokens),
+            response: (sample.bytes, sample.tokens),
+            latency_ms: call.latency_ms,
+            is_error: call.is_error,
+        };
+        let stats = add_tool_call(state, &sample.server_id, &call.tool, completed).await;
+        let _ = app.emit("tool-usage", &stats);
+    }
+}
+
+/// A latency sample for a response that completed one of our requests.
+fn request_sample(capture: &TrafficCapture, response: &CapturedMessage) -> Option<RequestSample> {
+    if response.direction != TrafficDirection::Inbound || response.payload.get("method").is_some() {
+        return None;
+    }
+    let request = capture.get(response.paired_seq?)?;
+    Some(RequestSample {
+        server_id: response.server_id.clone(),
+        method: request.method.clone()?,
+        tool: request
+            .payload
+            .pointer("/params/name")
+            .and_then(Value::as_str)
+            .filter(|_| request.method.as_deref() == Some("tools/call"))
+            .map(str::to_string),
+        latency_ms: response.latency_ms.unwrap_or(0),
+        is_error: response.is_error,
+    })
+}
+
+/// Count a response policy hit against the server's usage stats.
+pub async fn record_policy_hit(state: &AppState, app: &AppHandle, hit: &PolicyHit) -> ContextUsageStats {
+    let snapshot = {
+        let mut usage_map = state.context_usage.lock().await;
+        let usage = usage_map
+            .entry(hit.server_id.clone())
+            .or_insert_with(|| ContextUsageStats::new(&hit.server_id));
+
+        usage.policy_hits += 1;
+        usage.policy_tokens_saved += hit.tokens_before.saturating_sub(hit.tokens_after);
+        usage.updated_at = chrono::Utc::now().to_rfc3339();
+
+        usage.clone()
+    };
+
     let _ = app.emit("context-usage", &snapshot);
+    let _ = app.emit("response-policy-hit", hit);
     snapshot
 }
+
+/// Count a transformed tool result and its savings against the server's
+/// usage stats.
+pub async fn record_transform(state: &AppState, app: &AppHandle, report: &TransformReport) {
+    let snapshot = {
+        let mut usage_map = state.context_usage.lock().await;
+        let usage = usage_map
+            .entry(report.server_id.clone())
+            .or_insert_with(|| ContextUsageStats::new(&report.server_id));
+
+        usage.transform_hits += 1;
+        usage.transform_tokens_saved += report.tokens_before.saturating_sub(report.tokens_after);
+        usage.transform_bytes_saved += report.bytes_before.saturating_sub(report.bytes_after);
+        usage.updated_at = chrono::Utc::now().to_rfc3339();
+
+        usage.clone()
+    };
+
+    let _ = app.emit("context-usage", &snapshot);
+    let _ = app.emit("response-transform", report);
+}
+
+/// Count a response cache hit in the server's and the tool's usage stats.
+pub async fn record_cache_hit(state: &AppState, app: &AppHandle, hit: &CacheHit) {
+    let snapshot = {
+        let mut usage_map = state.context_usage.lock().await;
+        let usage = usage_map
+            .entry(hit.server_id.clone())
+            .or_insert_with(|| ContextUsageStats::new(&hit.server_id));
+        usage.cache_hits += 1;
+        usage.updated_at = chrono::Utc::now().to_rfc3339();
+        usage.clone()
+    };
+    let tool_snapshot = {
+        let mut tool_map = state.tool_usage.lock().await;
+        let stats = tool_map
+            .entry(hit.server_id.clone())
+            .or_default()
+            .entry(hit.tool.clone())
+            .or_insert_with(|| ToolUsageStats::new(&hit.server_id, &hit.tool));
+        stats.cache_hits += 1;
+        stats.updated_at = chrono::Utc::now().to_rfc3339();
+        stats.clone()
+    };
+
+    let _ = app.emit("context-usage", &snapshot);
+    let _ = app.emit("tool-usage", &tool_snapshot);
+}
+
+/// Count a call queued or refused by a rate or concurrency limit.
+pub async fn record_limit_event(state: &AppState, app: &AppHandle, event: &LimitEvent) {
+    let snapshot = {
+        let mut usage_map = state.context_usage.lock().await;
+        let usage = usage_map
+            .entry(event.server_id.clone())
+            .or_insert_with(|| ContextUsageStats::new(&event.server_id));
+        match &event.outcome {
+            LimitOutcome::Queued { wait_ms } => {
+                usage.limit_queued += 1;
+                usage.limit_queue_ms += wait_ms;
+            }
+            LimitOutcome::Rejected { .. } => usage.limit_rejected += 1,
+        }
+        usage.updated_at = chrono::Utc::now().to_rfc3339();
+        usage.clone()
+    };
+
+    let _ = app.emit("context-usage", &snapshot);
+    let _ = app.emit("rate-limit", event);
+}
+
+/// Write buffered usage buckets to the database. On failure they go back
+/// into the buffer, merged with anything recorded meanwhile, for the next
+/// flush.
+pub async fn flush_usage_samples(state: &AppState) {
+    let samples = std::mem::take(&mut *state.usage_samples.lock().await);
+    if samples.is_empty() {
+        return;
+    }
+    let pool = state.db.lock().await.clone();
+    if let Err(e) = persist_usage_samples(&pool, &samples).await {
+        eprintln!("[Relay] Failed to persist context usage: {}", e);
+        let mut pending = state.usage_samples.lock().await;
+        for (key, sample) in samples {
+            let entry = pending.entry(key).or_default();
+            entry.bytes += sample.bytes;
+            entry.tokens += sample.tokens;
+            entry.messages += sample.messages;
+        }
+    }
+}
+
+/// Flush buffered usage every `USAGE_FLUSH_INTERVAL`, for the life of the app.
+pub async fn flush_usage_periodically(state: &AppState) {
+    let mut interval = tokio::time::interval(USAGE_FLUSH_INTERVAL);
+    loop {
+        interval.tick().await;
+        flush_usage_samples(state).await;
+    }
+}
+
+async fn persist_usage_samples(db: &SqlitePool, samples: &PendingUsageSamples) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    for ((server_id, bucket, direction), sample) in samples {
+        sqlx::query(
+            "INSERT INTO context_usage_samples (server_id, bucket, direction, bytes, tokens, messages)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(server_id, bucket, direction) DO UPDATE SET
+                bytes = bytes + excluded.bytes,
+                tokens = tokens + excluded.tokens,
+                messages = messages + excluded.messages",
+        )
+        .bind(server_id)
+        .bind(bucket)
+        .bind(direction.as_str())
+        .bind(sample.bytes as i64)
+        .bind(sample.tokens as i64)
+        .bind(sample.messages as i64)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+/// Outbound requests captured for a server, unredacted and oldest first,
+/// excluding the handshake and notifications (which carry no id).
+pub async fn captured_requests(state: &AppState, server_id: &str) -> Vec<Value> {
+    let capture_map = state.traffic_capture.lock().await;
+    let Some(capture) = capture_map.get(server_id) else {
+        return Vec::new();
+    };
+
+    capture
+        .messages
+        .iter()
+        .filter_map(|m| m.replay.as_ref())
+        .filter(|request| request.get("method").and_then(Value::as_str) != Some("initialize"))
+        .cloned()
+        .collect()
+}
@@ -1,18 +1,46 @@
 use sqlx::SqlitePool;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 use std::collections::HashMap;
-use tokio::process::Child;
-use crate::proxy::ContextUsageStats;
+use tokio::process::{Child, ChildStdin};
+use crate::bridge::StdioBridge;
+use crate::commands::sessions::GatewaySession;
+use crate::gateway::http::{ClientTokens, HttpGatewayServer};
+use crate::gateway::Gateway;
+use crate::metrics::{MetricsEndpoint, MetricsRegistry};
+use crate::proxy::{ContextUsageStats, PendingToolCall, PendingUsageSamples, ToolUsageStats, TrafficCapture};
+use crate::utils::tokenizer::Tokenizer;
 
 pub struct ServerProcess {
     pub child: Child,
+    pub stdin: Arc<Mutex<ChildStdin>>,
+    /// Lines the server writes to stdout, for the stdio bridge.
+    pub output: broadcast::Sender<String>,
 }
 
 pub struct AppState {
     pub db: Arc<Mutex<SqlitePool>>,
     pub processes: Arc<Mutex<HashMap<String, ServerProcess>>>,
     pub context_usage: Arc<Mutex<HashMap<String, ContextUsageStats>>>,
+    /// Hourly usage buckets waiting to be flushed to the database.
+    pub usage_samples: Arc<Mutex<PendingUsageSamples>>,
+    pub traffic_capture: Arc<Mutex<HashMap<String, TrafficCapture>>>,
+    /// Server id -> tool name -> stats.
+    pub tool_usage: Arc<Mutex<HashMap<String, HashMap<String, ToolUsageStats>>>>,
+    /// (server id, JSON-RPC id) -> in-flight `tools/call`.
+    pub pending_tool_calls: Arc<Mutex<HashMap<(String, String), PendingToolCall>>>,
+    pub http_gateway: Arc<Mutex<Option<HttpGatewayServer>>>,
+    /// Kept connected for tool list views while the HTTP gateway is off.
+    pub inspection_gateway: Arc<Mutex<Option<Arc<Gateway>>>>,
+    pub gateway_tokens: ClientTokens,
+    pub tokenizer: Arc<Mutex<Tokenizer>>,
+    /// Request latency and errors from the inspector and the gateways.
+    pub metrics: Arc<Mutex<MetricsRegistry>>,
+    pub metrics_endpoint: Arc<Mutex<Option<MetricsEndpoint>>>,
+    /// `relay --gateway` instances on the control channel, by instance id.
+    pub gateway_sessions: Arc<Mutex<HashMap<String, GatewaySession>>>,
+    /// Stdio servers served over HTTP, by server id.
+    pub bridges: Arc<Mutex<HashMap<String, StdioBridge>>>,
 }
 
 impl AppState {
@@ -21,6 +49,18 @@ impl AppState {
             db: Arc::new(Mutex::new(db)),
             processes: Arc::new(Mutex::new(HashMap::new())),
             context_usage: Arc::new(Mutex::new(HashMap::new())),
+            usage_samples: Arc::new(Mutex::new(HashMap::new())),
+            traffic_capture: Arc::new(Mutex::new(HashMap::new())),
+            tool_usage: Arc::new(Mutex::new(HashMap::new())),
+            pending_tool_calls: Arc::new(Mutex::new(HashMap::new())),
+            http_gateway: Arc::new(Mutex::new(None)),
+            inspection_gateway: Arc::new(Mutex::new(None)),
+            gateway_tokens: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            tokenizer: Arc::new(Mutex::new(Tokenizer::default())),
+            metrics: Arc::new(Mutex::new(MetricsRegistry::default())),
+            metrics_endpoint: Arc::new(Mutex::new(None)),
+            gateway_sessions: Arc::new(Mutex::new(HashMap::new())),
+            bridges: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
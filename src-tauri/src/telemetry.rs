@@ -0,0 +1,300 @@
+//! OpenTelemetry spans for gateway traffic, exported as OTLP/JSON over HTTP
+//! or appended to a local file (one `ExportTraceServiceRequest` per line, as
+//! the collector's file exporter writes them).
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Spans are sent once this many are queued, or when the interval elapses.
+const BATCH_SIZE: usize = 256;
+const BATCH_INTERVAL_SECS: u64 = 2;
+const DEFAULT_SERVICE_NAME: &str = "relay-gateway";
+
+/// Where the gateway sends its spans; written to `relay.json` from the
+/// `traceOtlpEndpoint` and `traceOtlpFile` settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// OTLP/HTTP collector, e.g. `http://localhost:4318`. `/v1/traces` is
+    /// appended unless the URL already ends with it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otlp_endpoint: Option<String>,
+    /// Extra request headers, e.g. an API key for a hosted collector.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub otlp_headers: HashMap<String, String>,
+    /// Local file to append OTLP/JSON lines to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_name: Option<String>,
+}
+
+impl TelemetryConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.otlp_endpoint.as_deref().is_some_and(|e| !e.trim().is_empty())
+            || self.file.as_deref().is_some_and(|f| !f.trim().is_empty())
+    }
+
+    fn traces_url(&self) -> Option<String> {
+        let endpoint = self.otlp_endpoint.as_deref()?.trim().trim_end_matches('/');
+        if endpoint.is_empty() {
+            return None;
+        }
+        if endpoint.ends_with("/v1/traces") {
+            Some(endpoint.to_string())
+        } else {
+            Some(format!("{}/v1/traces", endpoint))
+        }
+    }
+}
+
+/// Trace and span a request runs under; children record it as their parent.
+#[derive(Debug, Clone)]
+pub struct SpanContext {
+    pub trace_id: String,
+    pub span_id: String,
+}
+
+impl SpanContext {
+    /// A new span, in the trace of `parent` or a new trace.
+    pub fn child_of(parent: Option<&SpanContext>) -> Self {
+        Self {
+            trace_id: parent.map(|p| p.trace_id.clone()).unwrap_or_else(new_trace_id),
+            span_id: new_span_id(),
+        }
+    }
+
+    /// Parse a W3C `traceparent` (`00-<trace id>-<span id>-<flags>`).
+    pub fn from_traceparent(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let (_version, trace_id, span_id) = (parts.next()?, parts.next()?, parts.next()?);
+        let is_id = |id: &str, len: usize| {
+            id.len() == len && id.chars().all(|c| c.is_ascii_hexdigit()) && id.chars().any(|c| c != '0')
+        };
+        if !is_id(trace_id, 32) || !is_id(span_id, 16) {
+            return None;
+        }
+        Some(Self {
+            trace_id: trace_id.to_ascii_lowercase(),
+            span_id: span_id.to_ascii_lowercase(),
+        })
+    }
+}
+
+fn new_trace_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+fn new_span_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..16].to_string()
+}
+
+pub fn now_unix_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    /// A request the gateway received from its client.
+    Server,
+    /// A request the gateway sent to an upstream.
+    Client,
+}
+
+#[derive(Debug, Clone)]
+pub enum AttributeValue {
+    String(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<u64> for AttributeValue {
+    fn from(value: u64) -> Self {
+        Self::Int(value as i64)
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+fn attribute(key: &str, value: &AttributeValue) -> Value {
+    // OTLP/JSON carries 64-bit integers as strings.
+    let value = match value {
+        AttributeValue::String(s) => json!({ "stringValue": s }),
+        AttributeValue::Int(i) => json!({ "intValue": i.to_string() }),
+        AttributeValue::Bool(b) => json!({ "boolValue": b }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+/// A finished span.
+#[derive(Debug, Clone)]
+pub struct Span {
+    pub context: SpanContext,
+    pub parent_span_id: Option<String>,
+    pub name: String,
+    pub kind: SpanKind,
+    pub start_unix_nanos: u64,
+    pub end_unix_nanos: u64,
+    pub attributes: Vec<(String, AttributeValue)>,
+    /// Error message when the request failed.
+    pub error: Option<String>,
+}
+
+impl Span {
+    /// Start a span; set its end with [`Span::finish`].
+    pub fn start(name: impl Into<String>, kind: SpanKind, context: SpanContext, parent: Option<&SpanContext>) -> Self {
+        let start = now_unix_nanos();
+        Self {
+            context,
+            parent_span_id: parent.map(|p| p.span_id.clone()),
+            name: name.into(),
+            kind,
+            start_unix_nanos: start,
+            end_unix_nanos: start,
+            attributes: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<AttributeValue>) {
+        self.attributes.push((key.to_string(), value.into()));
+    }
+
+    pub fn finish(mut self, error: Option<String>) -> Self {
+        self.end_unix_nanos = now_unix_nanos();
+        self.error = error;
+        self
+    }
+
+    fn to_otlp(&self) -> Value {
+        let (code, message) = match &self.error {
+            Some(message) => (2, message.as_str()),
+            None => (1, ""),
+        };
+        json!({
+            "traceId": self.context.trace_id,
+            "spanId": self.context.span_id,
+            "parentSpanId": self.parent_span_id.as_deref().unwrap_or(""),
+            "name": self.name,
+            "kind": match self.kind {
+                SpanKind::Server => 2,
+                SpanKind::Client => 3,
+            },
+            "startTimeUnixNano": self.start_unix_nanos.to_string(),
+            "endTimeUnixNano": self.end_unix_nanos.to_string(),
+            "attributes": self.attributes.iter().map(|(k, v)| attribute(k, v)).collect::<Vec<_>>(),
+            "status": { "code": code, "message": message },
+        })
+    }
+}
+
+/// An OTLP `ExportTraceServiceRequest` for a batch of spans.
+fn export_request(service_name: &str, spans: &[Span]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    attribute("service.name", &service_name.into()),
+                    attribute("service.version", &env!("CARGO_PKG_VERSION").into()),
+                ]
+            },
+            "scopeSpans": [{
+                "scope": { "name": "relay", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans.iter().map(Span::to_otlp).collect::<Vec<_>>(),
+            }]
+        }]
+    })
+}
+
+async fn export(config: &TelemetryConfig, client: &reqwest::Client, spans: &[Span]) {
+    let body = export_request(config.service_name.as_deref().unwrap_or(DEFAULT_SERVICE_NAME), spans);
+
+    if let Some(url) = config.traces_url() {
+        let mut request = client.post(&url).json(&body);
+        for (name, value) in &config.otlp_headers {
+            request = request.header(name, value);
+        }
+        match request.send().await {
+            Ok(response) if !response.status().is_success() => {
+                eprintln!("[Relay] OTLP export to {} failed: HTTP {}", url, response.status());
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("[Relay] OTLP export to {} failed: {}", url, e),
+        }
+    }
+
+    if let Some(path) = config.file.as_deref().filter(|f| !f.trim().is_empty()) {
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", body));
+        if let Err(e) = written {
+            eprintln!("[Relay] Failed to write traces to {}: {}", path, e);
+        }
+    }
+}
+
+/// Batch spans from `spans` to the configured destinations. Ends, after
+/// sending what is left, when the span channel closes.
+pub fn spawn_exporter(config: TelemetryConfig, mut spans: broadcast::Receiver<Span>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        let mut batch: Vec<Span> = Vec::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(BATCH_INTERVAL_SECS));
+
+        loop {
+            let closed = tokio::select! {
+                span = spans.recv() => match span {
+                    Ok(span) => {
+                        batch.push(span);
+                        if batch.len() < BATCH_SIZE {
+                            continue;
+                        }
+                        false
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        eprintln!("[Relay] Trace export dropped {} spans", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => true,
+                },
+                _ = interval.tick() => false,
+            };
+
+            if !batch.is_empty() {
+                export(&config, &client, &batch).await;
+                batch.clear();
+            }
+            if closed {
+                break;
+            }
+        }
+    })
+}
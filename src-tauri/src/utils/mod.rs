@@ -1,3 +1,6 @@
+pub mod patch;
 pub mod paths;
 pub mod process;
+pub mod redaction;
 pub mod secrets;
+pub mod tokenizer;
@@ -0,0 +1,18 @@
+//! Serde helpers for patch fields (`Option<Option<T>>`), which tell an
+//! absent field (`None`, left as it is) apart from an explicit `null`
+//! (`Some(None)`, cleared). Use with `#[serde(default)]`; fields that are
+//! also serialized need `skip_serializing_if = "Option::is_none"`, or an
+//! absent field is written as `null` and read back as a clear.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize<T: Serialize, S: Serializer>(value: &Option<Option<T>>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(inner) => inner.serialize(serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
@@ -2,10 +2,16 @@ use tokio::process::Command;
 use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tauri::{AppHandle, Emitter};
+use tokio::sync::{broadcast, Mutex};
+use std::sync::Arc;
 use crate::state::ServerProcess;
 
+use crate::utils::redaction;
 use crate::utils::secrets::SecretManager;
 
+/// Stdout lines buffered for a slow bridge reader before it starts missing them.
+const OUTPUT_CAPACITY: usize = 1024;
+
 pub async fn spawn_server(
     id: String,
     name: String,
@@ -18,6 +24,7 @@ pub async fn spawn_server(
     // Inject secrets from keyring
     for key in secrets {
         if let Ok(value) = SecretManager::get_secret(&id, &key) {
+            redaction::register_secret(&value);
             envs.insert(key, value);
         }
     }
@@ -44,22 +51,26 @@ pub async fn spawn_server(
         .spawn()
         .map_err(|e| format!("Failed to spawn server: {}", e))?;
 
+    let stdin = child.stdin.take().ok_or("Failed to capture stdin")?;
     let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
     let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
     
+    let (output, _) = broadcast::channel(OUTPUT_CAPACITY);
     let app_clone = app.clone();
     let id_clone = id.clone();
     let name_clone = name.clone();
+    let output_clone = output.clone();
 
     // Stream stdout logs
     tokio::spawn(async move {
         let mut reader = BufReader::new(stdout).lines();
         while let Ok(Some(line)) = reader.next_line().await {
+            let _ = output_clone.send(line.clone());
             let _ = app_clone.emit("server-log", json!({
                 "id": id_clone,
                 "name": name_clone,
                 "stream": "stdout",
-                "message": line,
+                "message": redaction::redact(&line),
                 "timestamp": chrono::Utc::now().to_rfc3339()
             }));
         }
@@ -77,7 +88,7 @@ pub async fn spawn_server(
                 "id": id_clone2,
                 "name": name_clone2,
                 "stream": "stderr",
-                "message": line,
+                "message": redaction::redact(&line),
                 "timestamp": chrono::Utc::now().to_rfc3339()
             }));
         }
@@ -85,6 +96,8 @@ pub async fn spawn_server(
 
     Ok(ServerProcess {
         child,
+        stdin: Arc::new(Mutex::new(stdin)),
+        output,
     })
 }
 
@@ -0,0 +1,136 @@
+//! Masks secrets in anything Relay persists, emits to the frontend or
+//! exports. Knows every secret value injected from the keyring in this
+//! process, plus the shapes of common API tokens.
+
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::{OnceLock, RwLock};
+
+pub const MASK: &str = "[REDACTED]";
+
+/// Shorter values ("1", "true", ...) would mask unrelated text.
+const MIN_SECRET_LEN: usize = 6;
+
+fn known_secrets() -> &'static RwLock<BTreeSet<String>> {
+    static SECRETS: OnceLock<RwLock<BTreeSet<String>>> = OnceLock::new();
+    SECRETS.get_or_init(|| RwLock::new(BTreeSet::new()))
+}
+
+/// Token patterns and their replacements; `$1` keeps a non-secret prefix.
+fn patterns() -> &'static [(Regex, String)] {
+    static PATTERNS: OnceLock<Vec<(Regex, String)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let keep_prefix = format!("${{1}}{}", MASK);
+        [
+            // Authorization headers
+            (r"(?i)\b((?:bearer|basic)\s+)[A-Za-z0-9._~+/=-]{8,}", keep_prefix.as_str()),
+            // key=value and "key": "value" pairs
+            (
+                r#"(?i)((?:api[_-]?key|access[_-]?token|auth[_-]?token|refresh[_-]?token|client[_-]?secret|secret|password|passwd)["']?\s*[:=]\s*["']?)[^\s"',;&]{6,}"#,
+                keep_prefix.as_str(),
+            ),
+            // OpenAI / Anthropic
+            (r"\bsk-[A-Za-z0-9_-]{20,}", MASK),
+            // GitHub
+            (r"\bgh[pousr]_[A-Za-z0-9]{30,}", MASK),
+            (r"\bgithub_pat_[A-Za-z0-9_]{30,}", MASK),
+            // GitLab
+            (r"\bglpat-[A-Za-z0-9_-]{20,}", MASK),
+            // Slack
+            (r"\bxox[abposr]-[A-Za-z0-9-]{10,}", MASK),
+            // AWS access key ids
+            (r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b", MASK),
+            // Google API keys
+            (r"\bAIza[0-9A-Za-z_-]{35}", MASK),
+            // JWTs
+            (r"\beyJ[A-Za-z0-9_-]{8,}\.eyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}", MASK),
+            // Relay gateway tokens
+            (r"\brelay_[0-9a-f]{64}\b", MASK),
+        ]
+        .into_iter()
+        .map(|(pattern, replacement)| {
+            (
+                Regex::new(pattern).expect("valid redaction pattern"),
+                replacement.to_string(),
+            )
+        })
+        .collect()
+    })
+}
+
+/// Object keys whose string values are masked wholesale.
+fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase().replace(['-', '_'], "");
+    [
+        "apikey",
+        "accesstoken",
+        "authtoken",
+        "refreshtoken",
+        "clientsecret",
+        "secret",
+        "password",
+        "passwd",
+        "authorization",
+    ]
+    .iter()
+    .any(|sensitive| key == *sensitive || key.ends_with(sensitive))
+}
+
+/// Remember a secret value injected into a server, so it is masked from now
+/// on.
+pub fn register_secret(value: &str) {
+    let value = value.trim();
+    if value.len() < MIN_SECRET_LEN {
+        return;
+    }
+    if let Ok(mut secrets) = known_secrets().write() {
+        secrets.insert(value.to_string());
+    }
+}
+
+/// Mask known secrets and token-shaped substrings.
+pub fn redact(text: &str) -> String {
+    let mut text = text.to_string();
+
+    if let Ok(secrets) = known_secrets().read() {
+        // Longest first, so a secret containing another is masked whole.
+        let mut secrets: Vec<&String> = secrets.iter().filter(|s| text.contains(s.as_str())).collect();
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        for secret in secrets {
+            text = text.replace(secret.as_str(), MASK);
+        }
+    }
+
+    for (pattern, replacement) in patterns() {
+        if pattern.is_match(&text) {
+            text = pattern.replace_all(&text, replacement.as_str()).into_owned();
+        }
+    }
+    text
+}
+
+/// Mask every string in a JSON value, and whole values under sensitive keys.
+pub fn redact_value(value: &mut Value) {
+    match value {
+        Value::String(text) => *text = redact(text),
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                if is_sensitive_key(key) && item.is_string() {
+                    *item = Value::String(MASK.to_string());
+                } else {
+                    redact_value(item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A redacted copy of a JSON value.
+pub fn redacted(value: &Value) -> Value {
+    let mut value = value.clone();
+    redact_value(&mut value);
+    value
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Token counting scheme used for context accounting. The BPE vocabularies
+/// are compiled into the binary, so counting never touches the network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tokenizer {
+    /// GPT-4 / GPT-3.5 family vocabulary; also a close proxy for Claude.
+    #[default]
+    Cl100kBase,
+    /// GPT-4o family vocabulary.
+    O200kBase,
+    /// The original `bytes / 4` estimate.
+    Heuristic,
+}
+
+impl Tokenizer {
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "o200k_base" => Self::O200kBase,
+            "heuristic" => Self::Heuristic,
+            _ => Self::Cl100kBase,
+        }
+    }
+
+    pub fn as_setting(&self) -> &'static str {
+        match self {
+            Self::Cl100kBase => "cl100k_base",
+            Self::O200kBase => "o200k_base",
+            Self::Heuristic => "heuristic",
+        }
+    }
+
+    pub fn count(&self, text: &str) -> u64 {
+        if text.is_empty() {
+            return 0;
+        }
+
+        match self {
+            Self::Cl100kBase => tiktoken_rs::cl100k_base_singleton()
+                .encode_ordinary(text)
+                .len() as u64,
+            Self::O200kBase => tiktoken_rs::o200k_base_singleton()
+                .encode_ordinary(text)
+                .len() as u64,
+            Self::Heuristic => ((text.len() as f64) / 4.0).ceil() as u64,
+        }
+    }
+
+    /// Longest prefix of `text` that fits in `max_tokens`.
+    pub fn truncate(&self, text: &str, max_tokens: u64) -> String {
+        let bpe = match self {
+            Self::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+            Self::O200kBase => tiktoken_rs::o200k_base_singleton(),
+            Self::Heuristic => {
+                let mut end = text.len().min(max_tokens as usize * 4);
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                return text[..end].to_string();
+            }
+        };
+
+        let tokens = bpe.encode_ordinary(text);
+        if tokens.len() as u64 <= max_tokens {
+            return text.to_string();
+        }
+
+        // A cut can land inside a multi-byte character; back off until the
+        // prefix decodes.
+        let mut end = max_tokens as usize;
+        while end > 0 {
+            if let Ok(prefix) = bpe.decode(tokens[..end].to_vec()) {
+                return prefix;
+            }
+            end -= 1;
+        }
+        String::new()
+    }
+}
+
+fn push_text(out: &mut String, text: &str) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(text);
+}
+
+/// Text of an MCP content array as a model would see it. Binary parts
+/// (images, audio, blobs) are not text and are skipped.
+fn content_text(content: &[Value], out: &mut String) {
+    for item in content {
+        match item.get("type").and_then(Value::as_str) {
+            Some("text") => {
+                if let Some(text) = item.get("text").and_then(Value::as_str) {
+                    push_text(out, text);
+                }
+            }
+            Some("resource") => {
+                if let Some(text) = item.pointer("/resource/text").and_then(Value::as_str) {
+                    push_text(out, text);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The part of a JSON-RPC message that ends up in a model's context, leaving
+/// out the envelope (`jsonrpc`, `id`, method names). Tool and prompt results
+/// contribute their text content; schemas and arguments their JSON.
+pub fn context_text(message: &Value) -> String {
+    let mut out = String::new();
+
+    if let Some(result) = message.get("result") {
+        if let Some(content) = result.get("content").and_then(Value::as_array) {
+            content_text(content, &mut out);
+            if let Some(structured) = result.get("structuredContent") {
+                push_text(&mut out, &structured.to_string());
+            }
+        } else if let Some(contents) = result.get("contents").and_then(Value::as_array) {
+            for item in contents {
+                if let Some(text) = item.get("text").and_then(Value::as_str) {
+                    push_text(&mut out, text);
+                }
+            }
+        } else if let Some(messages) = result.get("messages").and_then(Value::as_array) {
+            for message in messages {
+                if let Some(content) = message.get("content") {
+                    content_text(std::slice::from_ref(content), &mut out);
+                }
+            }
+        } else {
+            out = result.to_string();
+        }
+    } else if let Some(error) = message.get("error") {
+        if let Some(text) = error.get("message").and_then(Value::as_str) {
+            out.push_str(text);
+        }
+    } else if let Some(params) = message.get("params") {
+        match message.get("method").and_then(Value::as_str) {
+            Some("tools/call") | Some("prompts/get") => {
+                if let Some(arguments) = params.get("arguments") {
+                    out = arguments.to_string();
+                }
+            }
+            _ => out = params.to_string(),
+        }
+    }
+
+    out
+}
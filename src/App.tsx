@@ -12,6 +12,7 @@ import { SettingsPage } from './components/features/settings/SettingsPage';
 import { AddServerDialog } from './components/features/servers/AddServerDialog';
 import { DeleteServerDialog } from './components/features/servers/DeleteServerDialog';
 import { ServerLogsDialog } from './components/features/servers/ServerLogsDialog';
+import { ToolApprovalDialog } from './components/features/servers/ToolApprovalDialog';
 import { Toaster } from './components/ui/toaster';
 import { useUIStore } from './stores/uiStore';
 import { useSettingsStore } from './stores/settingsStore';
@@ -101,6 +102,7 @@ function App() {
         <AddServerDialog />
         <DeleteServerDialog />
         <ServerLogsDialog />
+        <ToolApprovalDialog />
 
         {/* Onboarding Tour */}
         <AppTour />
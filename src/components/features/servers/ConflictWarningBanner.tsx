@@ -12,12 +12,19 @@ interface ToolConflict {
     servers: ConflictingServer[];
 }
 
+interface StaleToolOverride {
+    server_id: string;
+    server_name: string;
+    tool_name: string;
+}
+
 interface ConflictWarningBannerProps {
     refreshKey?: number;
 }
 
 export function ConflictWarningBanner({ refreshKey }: ConflictWarningBannerProps) {
     const [conflicts, setConflicts] = useState<ToolConflict[]>([]);
+    const [staleOverrides, setStaleOverrides] = useState<StaleToolOverride[]>([]);
     const [dismissed, setDismissed] = useState(false);
 
     useEffect(() => {
@@ -30,10 +37,19 @@ export function ConflictWarningBanner({ refreshKey }: ConflictWarningBannerProps
                 console.warn('Failed to check tool conflicts:', err);
             }
         };
+        const checkOverrides = async () => {
+            try {
+                const result = await invoke<StaleToolOverride[]>('detect_stale_tool_overrides');
+                setStaleOverrides(result);
+            } catch (err) {
+                console.warn('Failed to check tool overrides:', err);
+            }
+        };
         checkConflicts();
+        checkOverrides();
     }, [refreshKey]);
 
-    if (dismissed || conflicts.length === 0) return null;
+    if (dismissed || (conflicts.length === 0 && staleOverrides.length === 0)) return null;
 
     return (
         <div className="mb-4 rounded-lg border border-yellow-500/30 bg-yellow-500/10 p-3">
@@ -42,7 +58,7 @@ export function ConflictWarningBanner({ refreshKey }: ConflictWarningBannerProps
                     <AlertTriangle className="h-4 w-4 text-yellow-500 mt-0.5 shrink-0" />
                     <div className="space-y-1">
                         <p className="text-sm font-medium text-yellow-200">
-                            Tool Conflict Detected
+                            {conflicts.length > 0 ? 'Tool Conflict Detected' : 'Outdated Tool Override'}
                         </p>
                         {conflicts.map((conflict, i) => (
                             <p key={i} className="text-xs text-muted-foreground">
@@ -57,6 +73,14 @@ export function ConflictWarningBanner({ refreshKey }: ConflictWarningBannerProps
                                 . This may cause unexpected behavior.
                             </p>
                         ))}
+                        {staleOverrides.map((stale) => (
+                            <p key={`${stale.server_id}:${stale.tool_name}`} className="text-xs text-muted-foreground">
+                                <span className="font-medium text-foreground">{stale.server_name}</span>
+                                {' '}no longer provides{' '}
+                                <code className="text-yellow-300/80 font-mono">{stale.tool_name}</code>
+                                , so its override has no effect.
+                            </p>
+                        ))}
                     </div>
                 </div>
                 <button
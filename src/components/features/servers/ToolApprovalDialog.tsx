@@ -0,0 +1,113 @@
+import { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import {
+    Dialog,
+    DialogContent,
+    DialogDescription,
+    DialogFooter,
+    DialogHeader,
+    DialogTitle,
+} from '../../ui/dialog';
+import { Button } from '../../ui/button';
+import { useToast } from '../../ui/use-toast';
+import { ShieldAlert } from 'lucide-react';
+import { approvalApi } from '../../../lib/tauri';
+import type { ApprovalDecision, ApprovalRequest } from '../../../types/approval';
+
+/**
+ * Prompts for gateway tool calls that need approval. Requests queue up and
+ * are shown one at a time; the call blocks in the gateway until answered or
+ * timed out.
+ */
+export function ToolApprovalDialog() {
+    const { toast } = useToast();
+    const [queue, setQueue] = useState<ApprovalRequest[]>([]);
+    const [loading, setLoading] = useState(false);
+
+    useEffect(() => {
+        approvalApi.pending().then(setQueue).catch(() => {});
+
+        const unlistenRequest = listen<ApprovalRequest>('tool-approval-request', (event) => {
+            setQueue((queue) => [...queue.filter((r) => r.id !== event.payload.id), event.payload]);
+        });
+        const unlistenDecided = listen<ApprovalDecision>('tool-approval-decided', (event) => {
+            setQueue((queue) => queue.filter((r) => r.id !== event.payload.request.id));
+        });
+
+        return () => {
+            unlistenRequest.then((unlisten) => unlisten());
+            unlistenDecided.then((unlisten) => unlisten());
+        };
+    }, []);
+
+    const current = queue[0] ?? null;
+
+    const decide = async (approved: boolean) => {
+        if (!current) return;
+
+        setLoading(true);
+        try {
+            await approvalApi.resolve(current.id, approved);
+        } catch (error) {
+            toast({
+                title: 'Approval',
+                description: String(error),
+                variant: 'destructive',
+            });
+        } finally {
+            setQueue((queue) => queue.filter((r) => r.id !== current.id));
+            setLoading(false);
+        }
+    };
+
+    return (
+        <Dialog open={current !== null} onOpenChange={(open) => !open && decide(false)}>
+            <DialogContent className="sm:max-w-[560px]">
+                <DialogHeader>
+                    <div className="flex items-center gap-3">
+                        <div className="w-10 h-10 rounded-full bg-yellow-500/20 flex items-center justify-center">
+                            <ShieldAlert className="h-5 w-5 text-yellow-500" />
+                        </div>
+                        <div>
+                            <DialogTitle>Approve Tool Call</DialogTitle>
+                            <DialogDescription>
+                                {current?.client} wants to call a tool
+                                {current?.mode === 'ask_once_per_session' && ' (approval lasts for this session)'}
+                            </DialogDescription>
+                        </div>
+                    </div>
+                </DialogHeader>
+
+                {current && (
+                    <div className="space-y-3 py-2">
+                        <div className="grid grid-cols-[80px_1fr] gap-y-1 text-sm">
+                            <span className="text-muted-foreground">Server</span>
+                            <span className="font-medium">{current.server_name}</span>
+                            <span className="text-muted-foreground">Tool</span>
+                            <span className="font-mono">{current.tool}</span>
+                        </div>
+                        <div>
+                            <p className="text-sm text-muted-foreground mb-1">Arguments</p>
+                            <pre className="max-h-64 overflow-auto rounded-md bg-muted p-3 text-xs font-mono whitespace-pre-wrap break-all">
+                                {JSON.stringify(current.arguments, null, 2)}
+                            </pre>
+                        </div>
+                        <p className="text-xs text-muted-foreground">
+                            Denied automatically after {current.timeout_secs} seconds
+                            {queue.length > 1 && ` · ${queue.length - 1} more waiting`}
+                        </p>
+                    </div>
+                )}
+
+                <DialogFooter>
+                    <Button variant="outline" onClick={() => decide(false)} disabled={loading}>
+                        Deny
+                    </Button>
+                    <Button onClick={() => decide(true)} disabled={loading}>
+                        Approve
+                    </Button>
+                </DialogFooter>
+            </DialogContent>
+        </Dialog>
+    );
+}
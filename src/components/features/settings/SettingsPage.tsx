@@ -14,7 +14,7 @@ import { Button } from '../../ui/button';
 import { useSettingsStore } from '../../../stores/settingsStore';
 import { useToast } from '../../ui/use-toast';
 import { configApi } from '../../../lib/tauri';
-import { THEMES, COMMAND_RUNNERS } from '../../../lib/constants';
+import { THEMES, COMMAND_RUNNERS, TOKENIZERS } from '../../../lib/constants';
 import { MCP_CLIENTS } from '../../../lib/clientCatalog';
 import { Moon, Sun, Monitor, Upload, RotateCcw, Download, AlertTriangle } from 'lucide-react';
 import {
@@ -172,6 +172,31 @@ export function SettingsPage() {
                                 </SelectContent>
                             </Select>
                         </div>
+
+                        <div className="flex items-center justify-between">
+                            <div className="space-y-0.5">
+                                <Label>Tokenizer</Label>
+                                <p className="text-sm text-muted-foreground">
+                                    Used to count context tokens in server traffic
+                                </p>
+                            </div>
+                            <Select
+                                value={settings.tokenizer}
+                                onValueChange={(value) => updateSettings({ tokenizer: value as typeof settings.tokenizer })}
+                                disabled={loading}
+                            >
+                                <SelectTrigger className="w-[160px]">
+                                    <SelectValue />
+                                </SelectTrigger>
+                                <SelectContent>
+                                    {TOKENIZERS.map((tokenizer) => (
+                                        <SelectItem key={tokenizer.value} value={tokenizer.value}>
+                                            {tokenizer.label}
+                                        </SelectItem>
+                                    ))}
+                                </SelectContent>
+                            </Select>
+                        </div>
                     </CardContent>
                 </Card>
 
@@ -71,3 +71,14 @@ export const COMMAND_RUNNERS = [
 
 export type CommandRunner = typeof COMMAND_RUNNERS[number]['value'];
 
+/**
+ * Tokenizers available for context accounting (bundled, offline)
+ */
+export const TOKENIZERS = [
+    { value: 'cl100k_base', label: 'cl100k (GPT-4, Claude approx.)' },
+    { value: 'o200k_base', label: 'o200k (GPT-4o)' },
+    { value: 'heuristic', label: 'Estimate (bytes / 4)' },
+] as const;
+
+export type TokenizerId = typeof TOKENIZERS[number]['value'];
+
@@ -2,7 +2,17 @@ import { invoke } from '@tauri-apps/api/core';
 import type { Server, CreateServerInput, UpdateServerInput } from '../types/server';
 import type { AppSettings } from '../types/settings';
 import type { Profile } from '../types/profile';
+import type { CreateToolsetInput, Toolset, UpdateToolsetInput } from '../types/toolset';
+import type { BridgeStatus, StartBridgeOptions } from '../types/bridge';
 import type { ConnectionTestResult, DependencyCheckInput, DependencyIssue, TestConnectionInput } from '../types/diagnostics';
+import type { RegressionReplayInput, RegressionReport } from '../types/regression';
+import type { ToolListCostReport, ToolUsageStats, UsageBucket, UsageHistoryQuery, UsageTotal } from '../types/usage';
+import type { ApprovalLogEntry, ApprovalRequest } from '../types/approval';
+import type { AuditEntry, AuditExportFormat, AuditQuery, AuditVerification } from '../types/audit';
+import type { TrafficFilter, TrafficPage } from '../types/traffic';
+import type { MetricsEndpointStatus, MetricsSnapshot } from '../types/metrics';
+import type { GatewayCommand, GatewaySession } from '../types/session';
+import type { ExportMode, ExposedTool, GatewayClient, GatewayClientToken, HttpGatewayStatus, ToolMode } from '../types/gateway';
 
 /**
  * Server API - CRUD operations for MCP servers
@@ -44,7 +54,8 @@ export const configApi = {
     exportToClient: (clientId: string) => invoke<string>('export_config', { clientId }),
 
     /** Export config to a specific path */
-    exportToPath: (path: string, key: string) => invoke<string>('export_config_to_path', { path, key }),
+    exportToPath: (path: string, key: string, mode?: ExportMode, clientName?: string, toolMode?: ToolMode) =>
+        invoke<string>('export_config_to_path', { path, key, mode, clientName, toolMode }),
 
     /** Read current Claude Desktop config */
     readClaude: () => invoke<Record<string, unknown>>('read_claude_config'),
@@ -84,6 +95,22 @@ export const profileApi = {
     setActive: (profileId: string) => invoke<string>('set_active_profile', { profileId }),
 };
 
+/**
+ * Toolset API - named groups of tools the gateway exposes while active
+ */
+export const toolsetApi = {
+    getAll: () => invoke<Toolset[]>('get_toolsets'),
+
+    create: (input: CreateToolsetInput) => invoke<Toolset>('create_toolset', { input }),
+
+    update: (input: UpdateToolsetInput) => invoke<Toolset>('update_toolset', { input }),
+
+    /** Change whether a toolset is active when gateway sessions start; running gateways follow */
+    setActive: (id: string, active: boolean) => invoke<Toolset>('set_toolset_active', { id, active }),
+
+    delete: (id: string) => invoke<void>('delete_toolset', { id }),
+};
+
 /**
  * Diagnostics API - dependency checks and connection validation
  */
@@ -94,3 +121,134 @@ export const diagnosticsApi = {
     testConnection: (input: TestConnectionInput) =>
         invoke<ConnectionTestResult>('test_server_connection', { input }),
 };
+
+/**
+ * Regression API - replay traffic against two server builds and diff the results
+ */
+export const regressionApi = {
+    replay: (input: RegressionReplayInput) =>
+        invoke<RegressionReport>('replay_regression', { input }),
+};
+
+/**
+ * Gateway API - App-hosted HTTP gateway and its client tokens
+ */
+export const gatewayApi = {
+    start: (port?: number) => invoke<HttpGatewayStatus>('start_http_gateway', { port }),
+
+    stop: () => invoke<void>('stop_http_gateway'),
+
+    status: () => invoke<HttpGatewayStatus>('get_http_gateway_status'),
+
+    listClients: () => invoke<GatewayClient[]>('list_gateway_clients'),
+
+    createClient: (name: string) => invoke<GatewayClientToken>('create_gateway_client', { name }),
+
+    revokeClient: (clientId: string) => invoke<void>('revoke_gateway_client', { clientId }),
+
+    /** Final tool names exposed for the active profile and where each routes */
+    exposedTools: () => invoke<ExposedTool[]>('get_exposed_tool_names'),
+
+    /** Drop cached tool results here and in running gateway instances; returns the HTTP gateway's count */
+    clearCache: (serverId?: string, tool?: string) => invoke<number>('clear_response_cache', { serverId, tool }),
+};
+
+/**
+ * Usage API - persisted context usage history
+ */
+export const usageApi = {
+    history: (query: UsageHistoryQuery) =>
+        invoke<UsageBucket[]>('get_context_usage_history', { query }),
+
+    totals: (from: string, to: string) =>
+        invoke<UsageTotal[]>('get_context_usage_totals', { from, to }),
+
+    /** Per-tool stats for this session; also pushed live via the `tool-usage` event */
+    tools: (serverId?: string) => invoke<ToolUsageStats[]>('get_tool_usage', { serverId }),
+
+    /** Token cost of the active profile's tool definitions */
+    toolListCost: () => invoke<ToolListCostReport>('get_tool_list_cost'),
+};
+
+/**
+ * Metrics API - request latency and error rates, optionally for Prometheus
+ */
+export const metricsApi = {
+    /** Rolling window in seconds; 300 by default, at most 3600 */
+    get: (windowSecs?: number, serverId?: string) =>
+        invoke<MetricsSnapshot>('get_request_metrics', { windowSecs, serverId }),
+
+    /** Serve /metrics in Prometheus text format on localhost */
+    startEndpoint: (port?: number) => invoke<MetricsEndpointStatus>('start_metrics_endpoint', { port }),
+
+    stopEndpoint: () => invoke<void>('stop_metrics_endpoint'),
+
+    endpointStatus: () => invoke<MetricsEndpointStatus>('get_metrics_endpoint_status'),
+};
+
+/**
+ * Bridge API - stdio servers served over HTTP to clients that cannot launch them
+ */
+export const bridgeApi = {
+    list: () => invoke<BridgeStatus[]>('get_bridges'),
+
+    /** Starts or restarts the bridge; the server must be running in shared mode */
+    start: (serverId: string, options: StartBridgeOptions = {}) =>
+        invoke<BridgeStatus>('start_bridge', { serverId, ...options }),
+
+    stop: (serverId: string) => invoke<void>('stop_bridge', { serverId }),
+};
+
+/**
+ * Session API - running `relay --gateway` instances, over the control channel
+ */
+export const sessionApi = {
+    /** Changes also arrive via the `gateway-sessions-changed` event */
+    list: () => invoke<GatewaySession[]>('list_gateway_sessions'),
+
+    send: (instanceId: string, command: GatewayCommand) =>
+        invoke<void>('send_gateway_command', { instanceId, command }),
+};
+
+/**
+ * Traffic API - captured JSON-RPC messages from the app's server connections
+ */
+export const trafficApi = {
+    /** Newest first; new messages also arrive via the `traffic-message` event */
+    page: (serverId: string, filter: TrafficFilter = {}, offset?: number, limit?: number) =>
+        invoke<TrafficPage>('get_traffic_capture', { serverId, filter, offset, limit }),
+
+    clear: (serverId?: string) => invoke<void>('clear_traffic_capture', { serverId }),
+
+    /** Writes HAR-style JSON; returns the number of exchanges written */
+    export: (serverId: string, filter: TrafficFilter, path: string) =>
+        invoke<number>('export_traffic_capture', { serverId, filter, path }),
+};
+
+/**
+ * Approval API - human-in-the-loop decisions for gateway tool calls
+ */
+export const approvalApi = {
+    resolve: (requestId: string, approved: boolean) =>
+        invoke<void>('resolve_tool_approval', { requestId, approved }),
+
+    /** Calls still waiting; new ones arrive via the `tool-approval-request` event */
+    pending: () => invoke<ApprovalRequest[]>('list_pending_approvals'),
+
+    decisions: (limit?: number) => invoke<ApprovalLogEntry[]>('list_approval_decisions', { limit }),
+};
+
+/**
+ * Audit API - tamper-evident log of tool calls
+ */
+export const auditApi = {
+    /** Newest entries matching the query, in chain order */
+    list: (query: AuditQuery = {}, limit?: number) =>
+        invoke<AuditEntry[]>('get_audit_log', { query, limit }),
+
+    verify: () => invoke<AuditVerification>('verify_audit_log'),
+
+    /** Returns the number of entries written */
+    export: (query: AuditQuery, format: AuditExportFormat, path: string) =>
+        invoke<number>('export_audit_log', { query, format, path }),
+};
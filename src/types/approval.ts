@@ -0,0 +1,49 @@
+export type ApprovalMode = 'always_allow' | 'ask_every_time' | 'ask_once_per_session' | 'deny';
+
+/** `no_approver`: a `relay --gateway` instance could not reach the app, so the call was refused */
+export type ApprovalOutcome = 'approved' | 'denied' | 'timed_out' | 'no_approver';
+
+/**
+ * Per-server approval policy, keyed by upstream tool name. Without a policy,
+ * tools annotated destructiveHint ask every time and the rest are allowed.
+ */
+export interface ApprovalPolicy {
+    default?: ApprovalMode;
+    tools?: Record<string, ApprovalMode>;
+}
+
+/** A gateway tool call waiting for the user (`tool-approval-request` event) */
+export interface ApprovalRequest {
+    id: string;
+    server_id: string;
+    server_name: string;
+    tool: string;
+    arguments: unknown;
+    client: string;
+    session_id: string;
+    mode: ApprovalMode;
+    requested_at: string;
+    timeout_secs: number;
+}
+
+/** Payload of the `tool-approval-decided` event */
+export interface ApprovalDecision {
+    request: ApprovalRequest;
+    outcome: ApprovalOutcome;
+    decided_at: string;
+}
+
+/** Row of the approval decision log */
+export interface ApprovalLogEntry {
+    id: string;
+    server_id: string;
+    server_name: string;
+    tool: string;
+    arguments: string; // JSON
+    client: string;
+    session_id: string;
+    mode: ApprovalMode;
+    outcome: ApprovalOutcome;
+    requested_at: string;
+    decided_at: string;
+}
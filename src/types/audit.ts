@@ -0,0 +1,39 @@
+export type AuditCallStatus = 'ok' | 'error' | 'rejected' | 'denied' | 'throttled';
+
+/** One hash-chained audit log entry */
+export interface AuditEntry {
+    seq: number;
+    timestamp: string;
+    client: string;
+    server_id: string;
+    server_name: string;
+    tool: string;
+    /** SHA-256 of the JSON arguments */
+    arguments_digest: string;
+    /** Full JSON arguments, when the auditLogArguments setting is 'full' */
+    arguments: string | null;
+    status: AuditCallStatus;
+    duration_ms: number;
+    /** Approval outcome, when the call needed one */
+    approval: string | null;
+    prev_hash: string;
+    hash: string;
+}
+
+export interface AuditQuery {
+    /** Inclusive RFC 3339 start */
+    from?: string;
+    /** Exclusive RFC 3339 end */
+    to?: string;
+    server_id?: string;
+}
+
+export type AuditExportFormat = 'jsonl' | 'csv';
+
+export interface AuditVerification {
+    valid: boolean;
+    entries: number;
+    /** First entry whose hash or link does not match */
+    broken_at: number | null;
+    message: string;
+}
@@ -0,0 +1,26 @@
+/** 'shared': every session uses the server process Relay manages; 'isolated': one process per session */
+export type BridgeMode = 'shared' | 'isolated';
+
+/**
+ * A stdio server served over Streamable HTTP at `/mcp`. Clients authenticate
+ * with gateway client tokens.
+ */
+export interface BridgeStatus {
+    server_id: string;
+    mode: BridgeMode;
+    port: number;
+    /** Listening on all interfaces rather than localhost only */
+    lan: boolean;
+    running: boolean;
+    url: string | null;
+    /** Open client sessions */
+    sessions: number;
+}
+
+export interface StartBridgeOptions {
+    /** Saved value, or 'shared', when unset */
+    mode?: BridgeMode;
+    /** Saved port when unset; 0 picks a free one */
+    port?: number;
+    lan?: boolean;
+}
@@ -0,0 +1,33 @@
+export interface HttpGatewayStatus {
+    running: boolean;
+    port: number;
+    url: string | null;
+}
+
+export interface GatewayClient {
+    id: string;
+    name: string;
+    created_at: string;
+}
+
+/** Returned once when a client is created; the token is not retrievable later. */
+export interface GatewayClientToken extends GatewayClient {
+    token: string;
+}
+
+export type ExportMode = 'stdio' | 'url';
+
+/** 'full': every exposed tool; 'search': only the search_tools / describe_tool / invoke_tool meta-tools */
+export type ToolMode = 'full' | 'search';
+
+/** 'prefix': always `<alias><separator><tool>`; 'prefix_on_conflict': bare names unless two servers clash */
+export type ToolNamespaceStrategy = 'prefix' | 'prefix_on_conflict';
+
+/** A tool as clients see it through the gateway */
+export interface ExposedTool {
+    exposed_name: string;
+    server_id: string;
+    server_name: string;
+    /** Name on the upstream server */
+    tool_name: string;
+}
@@ -0,0 +1,30 @@
+/** Latency and error rate of one server (method null) or one method/tool */
+export interface LatencyStats {
+    server_id: string;
+    method: string | null;
+    /** Tool name for tools/call */
+    tool: string | null;
+    count: number;
+    errors: number;
+    error_rate: number;
+    avg_ms: number;
+    p50_ms: number;
+    p95_ms: number;
+    p99_ms: number;
+    max_ms: number;
+}
+
+export interface MetricsSnapshot {
+    window_secs: number;
+    generated_at: string;
+    /** Slowest p95 first */
+    servers: LatencyStats[];
+    /** Per method and tool, slowest p95 first */
+    series: LatencyStats[];
+}
+
+export interface MetricsEndpointStatus {
+    running: boolean;
+    port: number;
+    url: string | null;
+}
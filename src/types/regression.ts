@@ -0,0 +1,37 @@
+export interface RegressionReplayInput {
+    source_server_id?: string;
+    requests?: Record<string, unknown>[];
+    baseline_server_id: string;
+    candidate_server_id: string;
+    ignore_paths?: string[];
+}
+
+export interface JsonChange {
+    path: string;
+    kind: 'added' | 'removed' | 'changed';
+    baseline: unknown | null;
+    candidate: unknown | null;
+}
+
+export interface ToolSchemaChange {
+    tool: string;
+    changes: JsonChange[];
+}
+
+export interface ResponseDiff {
+    index: number;
+    method: string;
+    params: unknown;
+    changes: JsonChange[];
+}
+
+export interface RegressionReport {
+    baseline_server_id: string;
+    candidate_server_id: string;
+    replayed: number;
+    identical: number;
+    tools_added: string[];
+    tools_removed: string[];
+    schema_changes: ToolSchemaChange[];
+    response_diffs: ResponseDiff[];
+}
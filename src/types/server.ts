@@ -1,3 +1,5 @@
+import type { ApprovalPolicy } from './approval';
+
 /**
  * Server entity from database
  */
@@ -11,9 +13,137 @@ export interface ContextUsage {
     total_tokens: number;
     messages_in: number;
     messages_out: number;
+    policy_hits: number;
+    policy_tokens_saved: number;
+    /** Tool calls answered from the gateway's response cache */
+    cache_hits: number;
+    /** Tool calls held back by a rate or concurrency limit, and refused by one */
+    limit_queued: number;
+    limit_queue_ms: number;
+    limit_rejected: number;
+    /** Tool results rewritten by transforms, and what that saved */
+    transform_hits: number;
+    transform_tokens_saved: number;
+    transform_bytes_saved: number;
     updated_at: string;
 }
 
+export type ResponsePolicyAction = 'truncate' | 'drop_images' | 'summarize_arrays' | 'reject';
+
+export interface ResponseLimits {
+    maxTokens?: number;
+    /** Applied in order until the result fits; defaults to ['truncate'] */
+    actions?: ResponsePolicyAction[];
+    maxArrayItems?: number;
+}
+
+/** Server-wide limits plus per-tool overrides keyed by tool name */
+export interface ResponsePolicy extends ResponseLimits {
+    tools?: Record<string, ResponseLimits>;
+}
+
+/** Which of a server's tools the gateway exposes */
+export interface ToolFilter {
+    /** 'allow': only matching tools; 'deny' (default): all but matching tools */
+    mode?: 'allow' | 'deny';
+    /** Tool name globs (`*`, `?`) */
+    patterns?: string[];
+    /** Only tools annotated readOnlyHint: true */
+    readOnly?: boolean;
+}
+
+/**
+ * Opt-in result caching. Only tools annotated readOnlyHint: true, or
+ * destructiveHint: false with idempotentHint: true, are cached.
+ */
+export interface CachePolicy {
+    /** Upstream tool name globs (`*`, `?`) */
+    tools: string[];
+    /** Defaults to 300 */
+    ttlSecs?: number;
+    /** Defaults to 256; least recently used entries go first */
+    maxEntries?: number;
+}
+
+export interface CallLimits {
+    /** Sustained rate; unlimited when unset */
+    callsPerMinute?: number;
+    /** Calls allowed back to back; defaults to callsPerMinute */
+    burst?: number;
+    maxConcurrent?: number;
+}
+
+/** Server-wide limits plus per-tool limits keyed by upstream tool name; a call has to fit both */
+export interface RateLimitPolicy extends CallLimits {
+    tools?: Record<string, CallLimits>;
+    /** 'reject' (default): JSON-RPC error -32029; 'queue': wait up to maxWaitSecs */
+    overLimit?: 'reject' | 'queue';
+    /** Defaults to 30 */
+    maxWaitSecs?: number;
+}
+
+/** Payload of the `rate-limit` event */
+export type RateLimitEvent = {
+    server_id: string;
+    tool: string;
+} & ({ outcome: 'queued'; wait_ms: number } | { outcome: 'rejected'; reason: string });
+
+export type Transform =
+    | 'strip_ansi'
+    | 'html_to_markdown'
+    | 'minify_json'
+    | 'pretty_json'
+    | 'json_to_csv'
+    | 'drop_images'
+    | 'downscale_images'
+    | 'wrap_untrusted';
+
+export interface TransformSteps {
+    /** Applied in order; wrap_untrusted always runs last */
+    steps?: Transform[];
+    /** Smallest JSON array json_to_csv converts; defaults to 20 */
+    csvMinRows?: number;
+    /** Longest side of a downscaled image; defaults to 768 */
+    maxImageDimension?: number;
+}
+
+/** Server-wide steps plus per-tool overrides keyed by upstream tool name */
+export interface TransformPolicy extends TransformSteps {
+    tools?: Record<string, TransformSteps>;
+}
+
+/** Payload of the `response-transform` event */
+export interface TransformReport {
+    server_id: string;
+    tool: string;
+    transforms: Transform[];
+    tokens_before: number;
+    tokens_after: number;
+    bytes_before: number;
+    bytes_after: number;
+}
+
+/** Exposed name/description overrides for one upstream tool */
+export interface ToolOverride {
+    name?: string;
+    description?: string;
+    /** Argument name -> description */
+    arguments?: Record<string, string>;
+}
+
+/** Upstream tool name -> override */
+export type ToolOverrides = Record<string, ToolOverride>;
+
+export interface ResponsePolicyHit {
+    server_id: string;
+    tool: string;
+    max_tokens: number;
+    tokens_before: number;
+    tokens_after: number;
+    actions: ResponsePolicyAction[];
+    rejected: boolean;
+}
+
 export interface Server {
     id: string;
     name: string;
@@ -33,6 +163,14 @@ export interface Server {
     previous_config: string | null;
     transport: string | null; // 'stdio' | 'sse'
     url: string | null;
+    response_policy: string | null; // JSON ResponsePolicy
+    tool_filter: string | null; // JSON ToolFilter
+    tool_overrides: string | null; // JSON ToolOverrides
+    alias: string | null; // Short tool namespace prefix
+    approval_policy: string | null; // JSON ApprovalPolicy
+    cache_policy: string | null; // JSON CachePolicy
+    rate_limit: string | null; // JSON RateLimitPolicy
+    transforms: string | null; // JSON TransformPolicy
     context_usage?: ContextUsage;
     created_at: string;
     updated_at: string;
@@ -52,6 +190,15 @@ export interface CreateServerInput {
     marketplace_id?: string;
     transport?: string;
     url?: string;
+    response_policy?: ResponsePolicy;
+    tool_filter?: ToolFilter;
+    tool_overrides?: ToolOverrides;
+    /** Short prefix for this server's tools; the server name when unset */
+    alias?: string;
+    approval_policy?: ApprovalPolicy;
+    cache_policy?: CachePolicy;
+    rate_limit?: RateLimitPolicy;
+    transforms?: TransformPolicy;
 }
 
 /**
@@ -69,6 +216,16 @@ export interface UpdateServerInput {
     category?: string;
     transport?: string;
     url?: string;
+    /** Policies: omitted ones are left as they are; `null` removes one */
+    response_policy?: ResponsePolicy | null;
+    tool_filter?: ToolFilter | null;
+    tool_overrides?: ToolOverrides | null;
+    /** Short prefix for this server's tools; the server name when unset */
+    alias?: string;
+    approval_policy?: ApprovalPolicy | null;
+    cache_policy?: CachePolicy | null;
+    rate_limit?: RateLimitPolicy | null;
+    transforms?: TransformPolicy | null;
 }
 
 /**
@@ -103,3 +260,87 @@ export function parseServerSecrets(server: Server): string[] {
         return [];
     }
 }
+
+/**
+ * Parse server response policy from JSON string
+ */
+export function parseServerResponsePolicy(server: Server): ResponsePolicy | null {
+    if (!server.response_policy) return null;
+    try {
+        return JSON.parse(server.response_policy);
+    } catch {
+        return null;
+    }
+}
+
+/**
+ * Parse server tool filter from JSON string
+ */
+export function parseServerToolFilter(server: Server): ToolFilter | null {
+    if (!server.tool_filter) return null;
+    try {
+        return JSON.parse(server.tool_filter);
+    } catch {
+        return null;
+    }
+}
+
+/**
+ * Parse server tool overrides from JSON string
+ */
+export function parseServerToolOverrides(server: Server): ToolOverrides {
+    if (!server.tool_overrides) return {};
+    try {
+        return JSON.parse(server.tool_overrides);
+    } catch {
+        return {};
+    }
+}
+
+/**
+ * Parse server approval policy from JSON string
+ */
+export function parseServerApprovalPolicy(server: Server): ApprovalPolicy | null {
+    if (!server.approval_policy) return null;
+    try {
+        return JSON.parse(server.approval_policy);
+    } catch {
+        return null;
+    }
+}
+
+/**
+ * Parse server cache policy from JSON string
+ */
+export function parseServerCachePolicy(server: Server): CachePolicy | null {
+    if (!server.cache_policy) return null;
+    try {
+        return JSON.parse(server.cache_policy);
+    } catch {
+        return null;
+    }
+}
+
+/**
+ * Parse server rate limits from JSON string
+ */
+export function parseServerRateLimit(server: Server): RateLimitPolicy | null {
+    if (!server.rate_limit) return null;
+    try {
+        return JSON.parse(server.rate_limit);
+    } catch {
+        return null;
+    }
+}
+
+/**
+ * Parse server transforms from JSON string
+ */
+export function parseServerTransforms(server: Server): TransformPolicy | null {
+    if (!server.transforms) return null;
+    try {
+        return JSON.parse(server.transforms);
+    } catch {
+        return null;
+    }
+}
@@ -0,0 +1,41 @@
+import type { ApprovalPolicy } from './approval';
+import type { RateLimitPolicy, ResponsePolicy, ToolFilter, TransformPolicy } from './server';
+
+export interface UpstreamSummary {
+    id: string;
+    name: string;
+    remote: boolean;
+}
+
+/** A `relay --gateway` process connected to the app */
+export interface GatewaySession {
+    instance_id: string;
+    pid: number;
+    config_path: string;
+    started_at: string;
+    /** clientInfo.name from the client's initialize; null until then */
+    client: string | null;
+    upstreams: UpstreamSummary[];
+    /** Names of the toolsets active in this instance */
+    active_toolsets: string[];
+}
+
+/**
+ * Command for one gateway instance. For set_policy, an omitted policy is
+ * left unchanged and null clears it.
+ */
+export type GatewayCommand =
+    | { type: 'reload' }
+    | { type: 'disconnect_upstream'; server_id: string }
+    | { type: 'clear_cache'; server_id?: string; tool?: string }
+    | {
+          type: 'set_policy';
+          server_id: string;
+          response_policy?: ResponsePolicy | null;
+          tool_filter?: ToolFilter | null;
+          approval_policy?: ApprovalPolicy | null;
+          rate_limit?: RateLimitPolicy | null;
+          transforms?: TransformPolicy | null;
+      }
+    | { type: 'set_toolset'; toolset: string; active: boolean }
+    | { type: 'resolve_approval'; request_id: string; approved: boolean };
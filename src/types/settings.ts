@@ -1,4 +1,4 @@
-import type { CommandRunner } from '../lib/constants';
+import type { CommandRunner, TokenizerId } from '../lib/constants';
 
 /**
  * App settings from database
@@ -14,6 +14,9 @@ export interface AppSettings {
     // Command settings
     defaultRunner: CommandRunner;
 
+    // Context accounting
+    tokenizer: TokenizerId;
+
     // Startup settings
     startMinimized: boolean;
 
@@ -37,6 +40,7 @@ export const DEFAULT_SETTINGS: AppSettings = {
     autoExport: false,
     defaultExportClient: 'claude-desktop',
     defaultRunner: 'npx',
+    tokenizer: 'cl100k_base',
     startMinimized: false,
     confirmBeforeDelete: true,
     showEnvVarsInLogs: false,
@@ -53,6 +57,7 @@ export const SETTING_DESCRIPTIONS: Record<keyof AppSettings, string> = {
     autoExport: 'Automatically export config when servers change',
     defaultExportClient: 'Default client for quick export actions',
     defaultRunner: 'Default command runner for new servers',
+    tokenizer: 'Tokenizer used to count context tokens in server traffic',
     startMinimized: 'Start Relay minimized to system tray',
     confirmBeforeDelete: 'Show confirmation dialog when deleting servers',
     showEnvVarsInLogs: 'Show environment variable values in log output',
@@ -0,0 +1,45 @@
+/** Tools of one server in a toolset */
+export interface ToolsetEntry {
+    serverId: string;
+    /** Upstream tool name globs; '*' takes every tool of the server */
+    tools: string[];
+}
+
+/**
+ * Named group of tools across servers. While any toolset is active, the
+ * gateway exposes only the tools of active toolsets.
+ */
+export interface Toolset {
+    id: string;
+    name: string;
+    description: string | null;
+    /** JSON-encoded ToolsetEntry[] */
+    tools: string;
+    /** Active when a gateway session starts */
+    active: boolean;
+    created_at: string;
+    updated_at: string;
+}
+
+export interface CreateToolsetInput {
+    name: string;
+    description?: string;
+    tools: ToolsetEntry[];
+    active?: boolean;
+}
+
+export interface UpdateToolsetInput {
+    id: string;
+    name?: string;
+    description?: string;
+    tools?: ToolsetEntry[];
+    active?: boolean;
+}
+
+export function parseToolsetEntries(toolset: Toolset): ToolsetEntry[] {
+    try {
+        return JSON.parse(toolset.tools);
+    } catch {
+        return [];
+    }
+}
@@ -0,0 +1,38 @@
+export type TrafficDirection = 'inbound' | 'outbound';
+
+/** A captured JSON-RPC message; new ones arrive via the `traffic-message` event */
+export interface CapturedMessage {
+    /** Increases per server; stable across eviction */
+    seq: number;
+    server_id: string;
+    direction: TrafficDirection;
+    /** For responses, the method of the request they answer */
+    method: string | null;
+    /** JSON-RPC id; null for notifications */
+    id: string | number | null;
+    /** The message, with secrets redacted */
+    payload: unknown;
+    bytes: number;
+    timestamp: string;
+    /** seq of the matching request or response, once paired */
+    paired_seq: number | null;
+    /** Request to response time, set on both sides once paired */
+    latency_ms: number | null;
+    is_error: boolean;
+}
+
+export interface TrafficFilter {
+    /** Method prefix, e.g. 'tools/' */
+    method?: string;
+    errors_only?: boolean;
+    slow_only?: boolean;
+    /** Defaults to 1000 ms */
+    slow_threshold_ms?: number;
+}
+
+export interface TrafficPage {
+    /** Matching messages across all pages */
+    total: number;
+    /** Newest first */
+    messages: CapturedMessage[];
+}
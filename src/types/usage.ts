@@ -0,0 +1,80 @@
+export type UsageGranularity = 'hour' | 'day';
+
+export interface UsageHistoryQuery {
+    /** Inclusive RFC 3339 start */
+    from: string;
+    /** Exclusive RFC 3339 end */
+    to: string;
+    granularity?: UsageGranularity;
+    server_id?: string;
+}
+
+export interface UsageBucket {
+    server_id: string;
+    bucket: string;
+    bytes_in: number;
+    bytes_out: number;
+    tokens_in: number;
+    tokens_out: number;
+    messages_in: number;
+    messages_out: number;
+}
+
+export interface UsageTotal {
+    server_id: string;
+    server_name: string | null;
+    bytes_in: number;
+    bytes_out: number;
+    tokens_in: number;
+    tokens_out: number;
+    total_tokens: number;
+    messages_in: number;
+    messages_out: number;
+}
+
+export interface ToolUsageStats {
+    server_id: string;
+    tool: string;
+    calls: number;
+    errors: number;
+    error_rate: number;
+    input_bytes: number;
+    output_bytes: number;
+    input_tokens: number;
+    output_tokens: number;
+    total_tokens: number;
+    total_latency_ms: number;
+    avg_latency_ms: number;
+    max_latency_ms: number;
+    /** Answered from the gateway's response cache; not counted in calls */
+    cache_hits: number;
+    updated_at: string;
+}
+
+export interface ToolDefinitionCost {
+    name: string;
+    name_tokens: number;
+    description_tokens: number;
+    schema_tokens: number;
+    total_tokens: number;
+}
+
+export interface ServerToolListCost {
+    server_id: string;
+    server_name: string;
+    tool_count: number;
+    total_tokens: number;
+    tools: ToolDefinitionCost[];
+    error: string | null;
+}
+
+export interface ToolListCostReport {
+    profile_id: string;
+    tokenizer: string;
+    /** `toolContextBudget` setting, in tokens */
+    budget: number;
+    total_tokens: number;
+    over_budget: boolean;
+    servers: ServerToolListCost[];
+    warnings: string[];
+}